@@ -14,7 +14,7 @@ fn main() -> Result<()> {
     let decoder = GzDecoder::new(file);
 
     let mut parser = Parser::new(decoder);
-    parser.next()?;
+    parser.advance()?;
     print_tag(&mut parser, 0)?;
 
     Ok(())
@@ -25,10 +25,10 @@ fn print_tag<R>(parser: &mut Parser<R>, indent: usize) -> Result<()> where R: st
 
     print!("\"{}\": ", parser.get_string_value()?);
 
-    parser.next()?;
+    parser.advance()?;
     print_value(parser, indent)?;
 
-    print!("\n");
+    println!();
 
     Ok(())
 }
@@ -53,19 +53,19 @@ fn print_seq<R>(parser: &mut Parser<R>, indent: usize) -> Result<()> where R: st
     print!("[");
 
     loop {
-        parser.next()?;
+        parser.advance()?;
         let value_type = parser.get_value_type();
         if value_type == ValueType::SeqEnd {
             break;
         }
 
-        print!("\n");
+        println!();
         print_indent(indent + 1);
         print_value(parser, indent + 1)?;
         print!(",");
     }
 
-    print!("\n");
+    println!();
     print_indent(indent);
     print!("]");
 
@@ -73,10 +73,10 @@ fn print_seq<R>(parser: &mut Parser<R>, indent: usize) -> Result<()> where R: st
 }
 
 fn print_map<R>(parser: &mut Parser<R>, indent: usize) -> Result<()> where R: std::io::Read {
-    print!("{{\n");
+    println!("{{");
 
     loop {
-        parser.next()?;
+        parser.advance()?;
         let value_type = parser.get_value_type();
         if value_type == ValueType::MapEnd {
             break;
@@ -0,0 +1,212 @@
+// Wrapper types and `#[serde(with = "...")]` modules which read and write
+// sequences as one of NBT's array types instead of as a `TAG_LIST`
+//
+// The serializer and deserializer recognize the newtype struct names below,
+// other serde data formats see an ordinary newtype around a sequence.
+
+use std::convert::TryFrom;
+use serde::de::{self, Deserialize, Deserializer, SeqAccess, Visitor};
+use serde::ser::{Serialize, Serializer};
+
+pub(crate) const I8_ARRAY_NAME: &str = "__serde_nbt_i8_array";
+pub(crate) const I32_ARRAY_NAME: &str = "__serde_nbt_i32_array";
+pub(crate) const I64_ARRAY_NAME: &str = "__serde_nbt_i64_array";
+
+/// a `Vec<i8>` which is always written as, and read from, a `TAG_I8_ARRAY`
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct ByteArray(pub Vec<i8>);
+
+/// a `Vec<i32>` which is always written as, and read from, a `TAG_I32_ARRAY`
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct IntArray(pub Vec<i32>);
+
+/// a `Vec<i64>` which is always written as, and read from, a `TAG_I64_ARRAY`
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct LongArray(pub Vec<i64>);
+
+/// visitor for the newtype struct that wraps an array's elements
+struct ArrayVisitor<T> {
+    expecting: &'static str,
+    marker: std::marker::PhantomData<T>,
+}
+
+impl<T> ArrayVisitor<T> {
+    fn new(expecting: &'static str) -> Self {
+        ArrayVisitor { expecting, marker: std::marker::PhantomData }
+    }
+}
+
+impl<'de, T> Visitor<'de> for ArrayVisitor<T>
+where
+    T: Deserialize<'de>,
+{
+    type Value = Vec<T>;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str(self.expecting)
+    }
+
+    fn visit_newtype_struct<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Vec::deserialize(deserializer)
+    }
+
+    fn visit_seq<A>(self, seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        Vec::deserialize(de::value::SeqAccessDeserializer::new(seq))
+    }
+}
+
+fn serialize_array<S, T>(name: &'static str, value: &[T], serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+    T: Serialize,
+{
+    serializer.serialize_newtype_struct(name, value)
+}
+
+fn deserialize_array<'de, D, T>(name: &'static str, expecting: &'static str, deserializer: D) -> Result<Vec<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    deserializer.deserialize_newtype_struct(name, ArrayVisitor::new(expecting))
+}
+
+/// converts the deserialized elements into the field's type, which can be a
+/// `Vec` or a fixed size array
+fn from_vec<T, E, U>(value: Vec<T>) -> Result<U, E>
+where
+    U: TryFrom<Vec<T>>,
+    E: de::Error,
+{
+    let len = value.len();
+    U::try_from(value).map_err(|_| E::invalid_length(len, &"an array of a different length"))
+}
+
+impl Serialize for ByteArray {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serialize_array(I8_ARRAY_NAME, &self.0, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for ByteArray {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserialize_array(I8_ARRAY_NAME, "a TAG_I8_ARRAY", deserializer).map(ByteArray)
+    }
+}
+
+impl Serialize for IntArray {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serialize_array(I32_ARRAY_NAME, &self.0, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for IntArray {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserialize_array(I32_ARRAY_NAME, "a TAG_I32_ARRAY", deserializer).map(IntArray)
+    }
+}
+
+impl Serialize for LongArray {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serialize_array(I64_ARRAY_NAME, &self.0, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for LongArray {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserialize_array(I64_ARRAY_NAME, "a TAG_I64_ARRAY", deserializer).map(LongArray)
+    }
+}
+
+/// use with `#[serde(with = "serde_nbt::byte_array")]` to read and write a
+/// `Vec<i8>` or `[i8; N]` field as a `TAG_I8_ARRAY`
+pub mod byte_array {
+    use std::convert::TryFrom;
+    use serde::{Deserializer, Serializer};
+
+    pub fn serialize<S, T>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+        T: AsRef<[i8]>,
+    {
+        super::serialize_array(super::I8_ARRAY_NAME, value.as_ref(), serializer)
+    }
+
+    pub fn deserialize<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+    where
+        D: Deserializer<'de>,
+        T: TryFrom<Vec<i8>>,
+    {
+        super::from_vec(super::deserialize_array(super::I8_ARRAY_NAME, "a TAG_I8_ARRAY", deserializer)?)
+    }
+}
+
+/// use with `#[serde(with = "serde_nbt::int_array")]` to read and write a
+/// `Vec<i32>` or `[i32; N]` field as a `TAG_I32_ARRAY`
+pub mod int_array {
+    use std::convert::TryFrom;
+    use serde::{Deserializer, Serializer};
+
+    pub fn serialize<S, T>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+        T: AsRef<[i32]>,
+    {
+        super::serialize_array(super::I32_ARRAY_NAME, value.as_ref(), serializer)
+    }
+
+    pub fn deserialize<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+    where
+        D: Deserializer<'de>,
+        T: TryFrom<Vec<i32>>,
+    {
+        super::from_vec(super::deserialize_array(super::I32_ARRAY_NAME, "a TAG_I32_ARRAY", deserializer)?)
+    }
+}
+
+/// use with `#[serde(with = "serde_nbt::long_array")]` to read and write a
+/// `Vec<i64>` or `[i64; N]` field as a `TAG_I64_ARRAY`
+pub mod long_array {
+    use std::convert::TryFrom;
+    use serde::{Deserializer, Serializer};
+
+    pub fn serialize<S, T>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+        T: AsRef<[i64]>,
+    {
+        super::serialize_array(super::I64_ARRAY_NAME, value.as_ref(), serializer)
+    }
+
+    pub fn deserialize<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+    where
+        D: Deserializer<'de>,
+        T: TryFrom<Vec<i64>>,
+    {
+        super::from_vec(super::deserialize_array(super::I64_ARRAY_NAME, "a TAG_I64_ARRAY", deserializer)?)
+    }
+}
@@ -0,0 +1,869 @@
+// Command-line tool for reading and editing NBT, built with the `cli` feature
+//
+// Files are read as binary NBT in Java's big endian or Bedrock's little
+// endian format, as SNBT or as JSON, and gzip or zlib compression is
+// detected. A chunk of a region file is addressed by the file and the
+// chunk's coordinates, as in `world/region/r.0.0.mca:3,7`.
+
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, Cursor, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use flate2::read::{GzDecoder, ZlibDecoder};
+use flate2::write::{GzEncoder, ZlibEncoder};
+use serde_nbt::nbt::parser::Parser;
+use serde_nbt::{
+    from_json_value, to_json_value, transcode, ByteOrder, DeserializerConfig, JsonMode, SerializerConfig, SnbtConfig,
+    SnbtDialect, StringEncoding, Value,
+};
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+const USAGE: &str = "usage:
+    nbt dump [options] <file>
+    nbt get [options] <file> <path>
+    nbt set [options] <file> <path> <snbt>
+    nbt convert [options] <input> <output>
+    nbt validate [options] <file>...
+
+<file> is a file, or a chunk of a region file as in r.0.0.mca:3,7. validate
+checks every chunk of a region file given without coordinates.
+
+<path> addresses a tag, as in Level.Sections[0].\"block states\".
+
+options:
+    --format <format>   the input's format: java, bedrock, snbt or json,
+                        detected by default
+    --to <format>       the output format of convert, by default the output
+                        file's extension or java
+    --compression <c>   gzip, zlib or none for binary output, by default gzip
+                        for java, none for bedrock and zlib in region files
+    --json              print JSON instead of SNBT
+    --tagged            JSON holds every value's type, as with --json
+    --modern            read and write SNBT of 1.21.5 and later
+    --compact           print on a single line
+    --sort-keys         print the tags of compounds sorted by name";
+
+/// the region file sector size
+const SECTOR_SIZE: usize = 4096;
+
+// the compression types of chunks in region files
+const REGION_GZIP: u8 = 1;
+const REGION_ZLIB: u8 = 2;
+const REGION_NONE: u8 = 3;
+const REGION_LZ4: u8 = 4;
+// set when the chunk is stored in a separate `.mcc` file
+const REGION_EXTERNAL: u8 = 0x80;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Format {
+    Java,
+    Bedrock,
+    Snbt,
+    Json,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Compression {
+    Gzip,
+    Zlib,
+    None,
+}
+
+#[derive(Default)]
+struct Options {
+    format: Option<Format>,
+    to: Option<Format>,
+    compression: Option<Compression>,
+    json: bool,
+    tagged: bool,
+    modern: bool,
+    compact: bool,
+    sort_keys: bool,
+}
+
+impl Options {
+    fn snbt(&self) -> SnbtConfig {
+        let dialect = if self.modern { SnbtDialect::Modern } else { SnbtDialect::Legacy };
+        SnbtConfig::new().dialect(dialect).pretty(!self.compact).sort_keys(self.sort_keys)
+    }
+
+    fn json_mode(&self) -> JsonMode {
+        if self.tagged { JsonMode::Tagged } else { JsonMode::Lossy }
+    }
+}
+
+/// a file, or a chunk of a region file
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Source {
+    File(PathBuf),
+    Chunk { path: PathBuf, x: i32, z: i32 },
+}
+
+impl Source {
+    fn parse(arg: &str) -> Result<Source> {
+        if let Some((path, coords)) = arg.rsplit_once(':') {
+            if path.ends_with(".mca") {
+                let (x, z) = coords
+                    .split_once(',')
+                    .and_then(|(x, z)| Some((x.trim().parse().ok()?, z.trim().parse().ok()?)))
+                    .ok_or_else(|| format!("invalid chunk coordinates \"{}\"", coords))?;
+                return Ok(Source::Chunk { path: PathBuf::from(path), x, z });
+            }
+        }
+        Ok(Source::File(PathBuf::from(arg)))
+    }
+
+    fn path(&self) -> &Path {
+        match self {
+            Source::File(path) | Source::Chunk { path, .. } => path,
+        }
+    }
+}
+
+/// a root tag along with how it was stored
+struct Document {
+    name: String,
+    value: Value,
+    format: Format,
+    compression: Compression,
+    // the version of Bedrock's `level.dat` header
+    bedrock_header: Option<i32>,
+}
+
+fn main() {
+    if let Err(e) = run(std::env::args().skip(1).collect()) {
+        eprintln!("nbt: {}", e);
+        std::process::exit(1);
+    }
+}
+
+fn run(args: Vec<String>) -> Result<()> {
+    let (options, args) = parse_options(args)?;
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    match args.as_slice() {
+        ["dump", file] => dump(&Source::parse(file)?, &options),
+        ["get", file, path] => get(&Source::parse(file)?, path, &options),
+        ["set", file, path, snbt] => set(&Source::parse(file)?, path, snbt, &options),
+        ["convert", input, output] => convert(&Source::parse(input)?, &Source::parse(output)?, &options),
+        ["validate", files @ ..] if !files.is_empty() => validate(files, &options),
+        _ => Err(USAGE.into()),
+    }
+}
+
+fn parse_options(args: Vec<String>) -> Result<(Options, Vec<String>)> {
+    let mut options = Options::default();
+    let mut positional = Vec::new();
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| format!("{} needs a value", arg));
+        match arg.as_str() {
+            "--format" => options.format = Some(parse_format(&value()?)?),
+            "--to" => options.to = Some(parse_format(&value()?)?),
+            "--compression" => {
+                options.compression = Some(match value()?.as_str() {
+                    "gzip" => Compression::Gzip,
+                    "zlib" => Compression::Zlib,
+                    "none" => Compression::None,
+                    other => return Err(format!("unknown compression \"{}\"", other).into()),
+                })
+            },
+            "--json" => options.json = true,
+            "--tagged" => {
+                options.json = true;
+                options.tagged = true;
+            },
+            "--modern" => options.modern = true,
+            "--compact" => options.compact = true,
+            "--sort-keys" => options.sort_keys = true,
+            "-h" | "--help" => return Err(USAGE.into()),
+            _ if arg.starts_with("--") => return Err(format!("unknown option {}\n\n{}", arg, USAGE).into()),
+            _ => positional.push(arg),
+        }
+    }
+    Ok((options, positional))
+}
+
+fn parse_format(name: &str) -> Result<Format> {
+    match name {
+        "java" => Ok(Format::Java),
+        "bedrock" => Ok(Format::Bedrock),
+        "snbt" => Ok(Format::Snbt),
+        "json" => Ok(Format::Json),
+        _ => Err(format!("unknown format \"{}\"", name).into()),
+    }
+}
+
+fn format_from_extension(path: &Path) -> Option<Format> {
+    match path.extension()?.to_str()? {
+        "snbt" => Some(Format::Snbt),
+        "json" => Some(Format::Json),
+        _ => None,
+    }
+}
+
+fn dump(source: &Source, options: &Options) -> Result<()> {
+    let (mut reader, _) = open(source)?;
+    let format = input_format(source, &mut reader, options)?;
+
+    // binary input is written as JSON while it is read
+    if options.json && matches!(format, Format::Java | Format::Bedrock) {
+        if format == Format::Bedrock && has_bedrock_header(reader.fill_buf()?) {
+            reader.consume(8);
+        }
+        let mut parser = Parser::new(reader);
+        if format == Format::Bedrock {
+            parser.set_byte_order(ByteOrder::LittleEndian);
+        } else {
+            parser.set_string_encoding(StringEncoding::ModifiedUtf8);
+        }
+        parser.set_heterogeneous_lists(true);
+
+        let stdout = io::stdout();
+        let mut output = stdout.lock();
+        if options.compact {
+            transcode(&mut parser, &mut serde_json::Serializer::new(&mut output), options.json_mode())?;
+        } else {
+            transcode(&mut parser, &mut serde_json::Serializer::pretty(&mut output), options.json_mode())?;
+        }
+        writeln!(output)?;
+        return Ok(());
+    }
+
+    let document = read_document(reader, format, Compression::None, options)?;
+    print_value(&document.value, options)
+}
+
+fn get(source: &Source, path: &str, options: &Options) -> Result<()> {
+    let document = read(source, options)?;
+    let value = get_path(&document.value, &parse_path(path)?).ok_or_else(|| format!("no tag at {}", path))?;
+    print_value(&value, options)
+}
+
+fn set(source: &Source, path: &str, snbt: &str, options: &Options) -> Result<()> {
+    let mut document = read(source, options)?;
+    let value = options.snbt().from_str(snbt)?;
+    set_path(&mut document.value, &parse_path(path)?, value)?;
+    write(source, &document, Some(options))
+}
+
+fn convert(input: &Source, output: &Source, options: &Options) -> Result<()> {
+    let mut document = read(input, options)?;
+    let format = match (options.to, output) {
+        (Some(format), _) => format,
+        (None, Source::Chunk { .. }) => Format::Java,
+        (None, Source::File(path)) => format_from_extension(path).unwrap_or(Format::Java),
+    };
+
+    // files converted to the same format keep their compression
+    document.compression = match (options.compression, input, output, format) {
+        (Some(compression), ..) => compression,
+        (None, _, Source::Chunk { .. }, _) => Compression::Zlib,
+        (None, Source::File(_), Source::File(_), _) if document.format == format => document.compression,
+        (None, _, Source::File(_), Format::Java) => Compression::Gzip,
+        (None, _, Source::File(_), _) => Compression::None,
+    };
+    if document.format != format {
+        document.bedrock_header = None;
+    }
+    document.format = format;
+    write(output, &document, Some(options))
+}
+
+fn validate(files: &[&str], options: &Options) -> Result<()> {
+    let mut failed = 0;
+    for file in files {
+        let source = Source::parse(file)?;
+        let results = match &source {
+            // every chunk of a region file
+            Source::File(path) if path.extension().is_some_and(|e| e == "mca") => {
+                check_region(file, path, options).unwrap_or_else(|e| vec![(file.to_string(), Err(e))])
+            },
+            _ => vec![(file.to_string(), check(&source, options))],
+        };
+
+        for (name, result) in results {
+            match result {
+                Ok(()) => println!("{}: ok", name),
+                Err(e) => {
+                    println!("{}: {}", name, e);
+                    failed += 1;
+                },
+            }
+        }
+    }
+
+    match failed {
+        0 => Ok(()),
+        _ => Err(format!("{} invalid", failed).into()),
+    }
+}
+
+/// checks every chunk of a region file, reading the file only once
+fn check_region(file: &str, path: &Path, options: &Options) -> Result<Vec<(String, Result<()>)>> {
+    let region = fs::read(path)?;
+    check_region_header(&region)?;
+    let mut results = Vec::new();
+    for z in 0..32 {
+        for x in 0..32 {
+            if chunk_location(&region, x, z).0 != 0 {
+                let chunk = Source::Chunk { path: path.to_path_buf(), x, z };
+                let result = open_chunk(&region, path, x, z).and_then(|(reader, _)| check_input(&chunk, reader, options));
+                results.push((format!("{}:{},{}", file, x, z), result));
+            }
+        }
+    }
+    Ok(results)
+}
+
+fn check(source: &Source, options: &Options) -> Result<()> {
+    let (reader, _) = open(source)?;
+    check_input(source, reader, options)
+}
+
+/// reads all of the input, binary NBT without keeping it in memory
+fn check_input(source: &Source, mut reader: Box<dyn BufRead>, options: &Options) -> Result<()> {
+    match input_format(source, &mut reader, options)? {
+        format @ (Format::Java | Format::Bedrock) => {
+            if format == Format::Bedrock && has_bedrock_header(reader.fill_buf()?) {
+                reader.consume(8);
+            }
+            let mut parser = Parser::new(reader);
+            if format == Format::Bedrock {
+                parser.set_byte_order(ByteOrder::LittleEndian);
+            } else {
+                parser.set_string_encoding(StringEncoding::ModifiedUtf8);
+            }
+            parser.set_heterogeneous_lists(true);
+
+            let mut events = 0;
+            for event in parser {
+                event?;
+                events += 1;
+            }
+            match events {
+                0 => Err("no root tag".into()),
+                _ => Ok(()),
+            }
+        },
+        format => read_document(reader, format, Compression::None, options).map(|_| ()),
+    }
+}
+
+fn print_value(value: &Value, options: &Options) -> Result<()> {
+    let stdout = io::stdout();
+    let mut output = stdout.lock();
+    if options.json {
+        let json = to_json_value(value, options.json_mode());
+        if options.compact {
+            serde_json::to_writer(&mut output, &json)?;
+        } else {
+            serde_json::to_writer_pretty(&mut output, &json)?;
+        }
+        writeln!(output)?;
+    } else {
+        writeln!(output, "{}", options.snbt().to_string(value)?)?;
+    }
+    Ok(())
+}
+
+/// opens a file or region chunk, undoing its compression
+fn open(source: &Source) -> Result<(Box<dyn BufRead>, Compression)> {
+    match source {
+        Source::File(path) => decompress(Box::new(BufReader::new(File::open(path)?)), None),
+        Source::Chunk { path, x, z } => {
+            let region = fs::read(path)?;
+            check_region_header(&region)?;
+            open_chunk(&region, path, *x, *z)
+        },
+    }
+}
+
+/// opens a chunk of a region file which has been read already
+fn open_chunk(region: &[u8], path: &Path, x: i32, z: i32) -> Result<(Box<dyn BufRead>, Compression)> {
+    let (data, compression) = read_chunk(region, path, x, z)?;
+    let compression = match compression {
+        REGION_GZIP => Compression::Gzip,
+        REGION_ZLIB => Compression::Zlib,
+        REGION_NONE => Compression::None,
+        REGION_LZ4 => return Err("LZ4 compressed chunks are not supported".into()),
+        other => return Err(format!("unknown chunk compression {}", other).into()),
+    };
+    decompress(Box::new(Cursor::new(data)), Some(compression))
+}
+
+/// undoes the given compression, or the one the start of the input looks
+/// like
+fn decompress(mut reader: Box<dyn BufRead>, compression: Option<Compression>) -> Result<(Box<dyn BufRead>, Compression)> {
+    let compression = match compression {
+        Some(compression) => compression,
+        None => match reader.fill_buf()? {
+            [0x1f, 0x8b, ..] => Compression::Gzip,
+            // the headers zlib writes at each compression level
+            [0x78, 0x01 | 0x5e | 0x9c | 0xda, ..] => Compression::Zlib,
+            _ => Compression::None,
+        },
+    };
+    let reader: Box<dyn BufRead> = match compression {
+        Compression::Gzip => Box::new(BufReader::new(GzDecoder::new(reader))),
+        Compression::Zlib => Box::new(BufReader::new(ZlibDecoder::new(reader))),
+        Compression::None => reader,
+    };
+    Ok((reader, compression))
+}
+
+/// the format given by the options or the file's extension, or else the
+/// binary format the start of the input looks like
+fn input_format(source: &Source, reader: &mut dyn BufRead, options: &Options) -> Result<Format> {
+    if let Some(format) = options.format.or_else(|| format_from_extension(source.path())) {
+        return Ok(format);
+    }
+
+    let start = reader.fill_buf()?;
+    let format = match start {
+        _ if has_bedrock_header(start) => Format::Bedrock,
+        // a little endian length below 256 of the root tag's name, or of its
+        // first tag's name if the root's is empty
+        [0x0a, len, 0, ..] if *len != 0 => Format::Bedrock,
+        [0x0a, 0, 0, 1..=12, len, 0, ..] if *len != 0 => Format::Bedrock,
+        _ => Format::Java,
+    };
+    Ok(format)
+}
+
+/// whether the input starts with the version and length that Bedrock puts
+/// in front of `level.dat`
+fn has_bedrock_header(start: &[u8]) -> bool {
+    start.len() >= 12 && start[1..4] == [0, 0, 0] && start[8] == 0x0a
+}
+
+fn read(source: &Source, options: &Options) -> Result<Document> {
+    let (mut reader, compression) = open(source)?;
+    let format = input_format(source, &mut reader, options)?;
+    read_document(reader, format, compression, options)
+}
+
+fn read_document(mut reader: Box<dyn BufRead>, format: Format, compression: Compression, options: &Options) -> Result<Document> {
+    let mut document = Document { name: String::new(), value: Value::Byte(0), format, compression, bedrock_header: None };
+    match format {
+        Format::Snbt => {
+            let mut input = String::new();
+            reader.read_to_string(&mut input)?;
+            document.value = options.snbt().from_str(&input)?;
+        },
+        Format::Json => {
+            let json: serde_json::Value = serde_json::from_reader(reader)?;
+            document.value = from_json_value(&json, options.json_mode())?;
+        },
+        Format::Java => {
+            let config = DeserializerConfig::new().string_encoding(StringEncoding::ModifiedUtf8).heterogeneous_lists(true);
+            (document.name, document.value) = config.from_reader_named(reader)?;
+        },
+        Format::Bedrock => {
+            if has_bedrock_header(reader.fill_buf()?) {
+                let mut header = [0; 8];
+                reader.read_exact(&mut header)?;
+                document.bedrock_header = Some(i32::from_le_bytes([header[0], header[1], header[2], header[3]]));
+            }
+            let config = DeserializerConfig::new().byte_order(ByteOrder::LittleEndian).heterogeneous_lists(true);
+            (document.name, document.value) = config.from_reader_named(reader)?;
+        },
+    }
+    Ok(document)
+}
+
+/// writes the document in its format, text formats use `options`, or the
+/// defaults when writing back to the input
+fn write(target: &Source, document: &Document, options: Option<&Options>) -> Result<()> {
+    let mut data = Vec::new();
+    match document.format {
+        Format::Snbt => {
+            let config = options.map_or_else(SnbtConfig::new, Options::snbt);
+            data = config.to_string(&document.value)?.into_bytes();
+            data.push(b'\n');
+        },
+        Format::Json => {
+            let mode = options.map_or(JsonMode::Lossy, Options::json_mode);
+            serde_json::to_writer_pretty(&mut data, &to_json_value(&document.value, mode))?;
+            data.push(b'\n');
+        },
+        Format::Java => {
+            let config = SerializerConfig::new().string_encoding(StringEncoding::ModifiedUtf8).heterogeneous_lists(true);
+            config.to_writer_named(&mut data, &document.name, &document.value)?;
+        },
+        Format::Bedrock => {
+            let config = SerializerConfig::new().byte_order(ByteOrder::LittleEndian).heterogeneous_lists(true);
+            config.to_writer_named(&mut data, &document.name, &document.value)?;
+            if let Some(version) = document.bedrock_header {
+                let mut header = version.to_le_bytes().to_vec();
+                header.extend_from_slice(&(data.len() as u32).to_le_bytes());
+                data.splice(0..0, header);
+            }
+        },
+    }
+
+    match target {
+        Source::File(path) => fs::write(path, compress(&data, document.compression)?)?,
+        Source::Chunk { path, x, z } => {
+            let compression = match document.compression {
+                Compression::Gzip => REGION_GZIP,
+                Compression::Zlib => REGION_ZLIB,
+                Compression::None => REGION_NONE,
+            };
+            write_chunk(path, *x, *z, &compress(&data, document.compression)?, compression)?;
+        },
+    }
+    Ok(())
+}
+
+fn compress(data: &[u8], compression: Compression) -> Result<Vec<u8>> {
+    let compressed = match compression {
+        Compression::Gzip => {
+            let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(data)?;
+            encoder.finish()?
+        },
+        Compression::Zlib => {
+            let mut encoder = ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(data)?;
+            encoder.finish()?
+        },
+        Compression::None => data.to_vec(),
+    };
+    Ok(compressed)
+}
+
+fn check_region_header(region: &[u8]) -> Result<()> {
+    if region.len() < 2 * SECTOR_SIZE {
+        return Err("region file is shorter than its header".into());
+    }
+    Ok(())
+}
+
+/// the first sector and the number of sectors of a chunk, both 0 if the
+/// chunk is not present
+fn chunk_location(region: &[u8], x: i32, z: i32) -> (usize, usize) {
+    let i = (((x & 31) + (z & 31) * 32) * 4) as usize;
+    let location = u32::from_be_bytes([region[i], region[i + 1], region[i + 2], region[i + 3]]) as usize;
+    (location >> 8, location & 0xff)
+}
+
+/// reads a chunk's compressed data and compression type from the contents
+/// of the region file at `path`
+fn read_chunk(region: &[u8], path: &Path, x: i32, z: i32) -> Result<(Vec<u8>, u8)> {
+    let missing = || format!("chunk {},{} is not in {}", x, z, path.display());
+    let (offset, _) = chunk_location(region, x, z);
+    if offset < 2 {
+        return Err(missing().into());
+    }
+
+    let start = offset * SECTOR_SIZE;
+    let header = region.get(start..start + 5).ok_or_else(missing)?;
+    let len = u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as usize;
+    let compression = header[4];
+    if compression & REGION_EXTERNAL != 0 {
+        let (region_x, region_z) = region_coords(path)?;
+        let name = format!("c.{}.{}.mcc", region_x * 32 + (x & 31), region_z * 32 + (z & 31));
+        return Ok((fs::read(path.with_file_name(name))?, compression & !REGION_EXTERNAL));
+    }
+    let data = len.checked_sub(1).and_then(|len| region.get(start + 5..start + 5 + len)).ok_or_else(missing)?;
+    Ok((data.to_vec(), compression))
+}
+
+/// the region's coordinates from a file name such as `r.-1.2.mca`
+fn region_coords(path: &Path) -> Result<(i32, i32)> {
+    let name = path.file_name().and_then(|name| name.to_str()).unwrap_or_default();
+    let parts: Vec<&str> = name.split('.').collect();
+    match parts.as_slice() {
+        ["r", x, z, "mca"] => Ok((x.parse()?, z.parse()?)),
+        _ => Err(format!("can not tell the region's coordinates from \"{}\"", name).into()),
+    }
+}
+
+/// stores a chunk in its sectors if it still fits, or else at the end of the
+/// region file, which is created if it does not exist
+fn write_chunk(path: &Path, x: i32, z: i32, data: &[u8], compression: u8) -> Result<()> {
+    let mut region = match fs::read(path) {
+        Ok(region) => region,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => vec![0; 2 * SECTOR_SIZE],
+        Err(e) => return Err(e.into()),
+    };
+    check_region_header(&region)?;
+
+    let mut chunk = ((data.len() + 1) as u32).to_be_bytes().to_vec();
+    chunk.push(compression);
+    chunk.extend_from_slice(data);
+    let sectors = chunk.len().div_ceil(SECTOR_SIZE);
+    if sectors > 255 {
+        return Err("chunk is too large for a region file".into());
+    }
+    chunk.resize(sectors * SECTOR_SIZE, 0);
+
+    // the chunk is appended if its sectors are too few or, in a truncated
+    // file, missing
+    let (offset, count) = chunk_location(&region, x, z);
+    let offset = if offset >= 2 && count >= sectors && (offset + count) * SECTOR_SIZE <= region.len() {
+        // clear the sectors which are no longer used
+        region[offset * SECTOR_SIZE..(offset + count) * SECTOR_SIZE].fill(0);
+        offset
+    } else {
+        region.resize(region.len().div_ceil(SECTOR_SIZE) * SECTOR_SIZE, 0);
+        region.resize(region.len() + chunk.len(), 0);
+        region.len() / SECTOR_SIZE - sectors
+    };
+    region[offset * SECTOR_SIZE..offset * SECTOR_SIZE + chunk.len()].copy_from_slice(&chunk);
+
+    let i = (((x & 31) + (z & 31) * 32) * 4) as usize;
+    region[i..i + 4].copy_from_slice(&((offset as u32) << 8 | sectors as u32).to_be_bytes());
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |time| time.as_secs() as u32);
+    region[SECTOR_SIZE + i..SECTOR_SIZE + i + 4].copy_from_slice(&now.to_be_bytes());
+
+    fs::write(path, region)?;
+    Ok(())
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Step {
+    Name(String),
+    Index(i64),
+}
+
+/// parses a path such as `a.b[0]."c d"`, an empty path is the root tag
+fn parse_path(path: &str) -> Result<Vec<Step>> {
+    let mut steps = Vec::new();
+    let mut chars = path.chars().peekable();
+    let invalid = || format!("invalid path \"{}\"", path);
+
+    while let Some(c) = chars.next() {
+        match c {
+            '[' => {
+                let mut index = String::new();
+                loop {
+                    match chars.next() {
+                        Some(']') => break,
+                        Some(c) => index.push(c),
+                        None => return Err(invalid().into()),
+                    }
+                }
+                steps.push(Step::Index(index.trim().parse().map_err(|_| invalid())?));
+            },
+            '"' => {
+                let mut name = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => name.push(chars.next().ok_or_else(invalid)?),
+                        Some(c) => name.push(c),
+                        None => return Err(invalid().into()),
+                    }
+                }
+                steps.push(Step::Name(name));
+            },
+            '.' if !steps.is_empty() && chars.peek().is_some_and(|&c| c != '.' && c != '[') => {},
+            '.' | ']' => return Err(invalid().into()),
+            _ => {
+                let mut name = c.to_string();
+                while let Some(&c) = chars.peek() {
+                    if c == '.' || c == '[' {
+                        break;
+                    }
+                    name.push(c);
+                    chars.next();
+                }
+                steps.push(Step::Name(name));
+            },
+        }
+    }
+    Ok(steps)
+}
+
+/// the position of `index` in a sequence of length `len`, negative indices
+/// count from the end
+fn resolve_index(index: i64, len: usize) -> Option<usize> {
+    let index = if index < 0 { len as i64 + index } else { index };
+    if index >= 0 && (index as usize) < len { Some(index as usize) } else { None }
+}
+
+fn get_path(value: &Value, steps: &[Step]) -> Option<Value> {
+    let (step, rest) = match steps.split_first() {
+        Some(split) => split,
+        None => return Some(value.clone()),
+    };
+    match (value, step) {
+        (Value::Compound(v), Step::Name(name)) => get_path(v.get(name)?, rest),
+        (Value::List(v), Step::Index(i)) => get_path(&v[resolve_index(*i, v.len())?], rest),
+        // array elements have no tags inside them
+        (Value::ByteArray(v), Step::Index(i)) if rest.is_empty() => Some(Value::Byte(v[resolve_index(*i, v.len())?])),
+        (Value::IntArray(v), Step::Index(i)) if rest.is_empty() => Some(Value::Int(v[resolve_index(*i, v.len())?])),
+        (Value::LongArray(v), Step::Index(i)) if rest.is_empty() => Some(Value::Long(v[resolve_index(*i, v.len())?])),
+        _ => None,
+    }
+}
+
+/// replaces the tag at the path, the last name in a path may also add a tag
+/// to a compound
+fn set_path(value: &mut Value, steps: &[Step], new_value: Value) -> Result<()> {
+    let (step, rest) = match steps.split_first() {
+        Some(split) => split,
+        None => {
+            *value = new_value;
+            return Ok(());
+        },
+    };
+    let missing = || format!("no tag at {:?}", step);
+    match (value, step, new_value) {
+        (Value::Compound(v), Step::Name(name), new_value) if rest.is_empty() => {
+            v.insert(name.clone(), new_value);
+            Ok(())
+        },
+        (Value::Compound(v), Step::Name(name), new_value) => {
+            set_path(v.get_mut(name).ok_or_else(missing)?, rest, new_value)
+        },
+        (Value::List(v), Step::Index(i), new_value) => {
+            let i = resolve_index(*i, v.len()).ok_or_else(missing)?;
+            set_path(&mut v[i], rest, new_value)
+        },
+        (Value::ByteArray(v), Step::Index(i), Value::Byte(new_value)) if rest.is_empty() => {
+            let i = resolve_index(*i, v.len()).ok_or_else(missing)?;
+            v[i] = new_value;
+            Ok(())
+        },
+        (Value::IntArray(v), Step::Index(i), Value::Int(new_value)) if rest.is_empty() => {
+            let i = resolve_index(*i, v.len()).ok_or_else(missing)?;
+            v[i] = new_value;
+            Ok(())
+        },
+        (Value::LongArray(v), Step::Index(i), Value::Long(new_value)) if rest.is_empty() => {
+            let i = resolve_index(*i, v.len()).ok_or_else(missing)?;
+            v[i] = new_value;
+            Ok(())
+        },
+        _ => Err(format!("can not set a tag at {:?}", step).into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_nbt::Compound;
+    use super::*;
+
+    #[test]
+    fn test_sources() {
+        assert_eq!(Source::parse("level.dat").unwrap(), Source::File(PathBuf::from("level.dat")));
+        assert_eq!(
+            Source::parse("world/region/r.0.0.mca:3,-7").unwrap(),
+            Source::Chunk { path: PathBuf::from("world/region/r.0.0.mca"), x: 3, z: -7 }
+        );
+        assert!(Source::parse("r.0.0.mca:3").is_err());
+    }
+
+    #[test]
+    fn test_paths() {
+        let steps = parse_path("Level.Sections[-1].\"block states\"[0]").unwrap();
+        assert_eq!(
+            steps,
+            vec![
+                Step::Name("Level".to_string()),
+                Step::Name("Sections".to_string()),
+                Step::Index(-1),
+                Step::Name("block states".to_string()),
+                Step::Index(0),
+            ]
+        );
+        assert!(parse_path("").unwrap().is_empty());
+        assert!(parse_path("a..b").is_err());
+        assert!(parse_path("a[x]").is_err());
+
+        let mut value = SnbtConfig::new().from_str("{a: [{b: 1}, {b: [I; 2, 3]}]}").unwrap();
+        assert_eq!(get_path(&value, &parse_path("a[-1].b[1]").unwrap()), Some(Value::Int(3)));
+        assert_eq!(get_path(&value, &parse_path("a[2]").unwrap()), None);
+
+        set_path(&mut value, &parse_path("a[0].c").unwrap(), Value::Byte(4)).unwrap();
+        set_path(&mut value, &parse_path("a[1].b[0]").unwrap(), Value::Int(5)).unwrap();
+        assert!(set_path(&mut value, &parse_path("a[1].b[0]").unwrap(), Value::Byte(5)).is_err());
+        assert!(set_path(&mut value, &parse_path("x.y").unwrap(), Value::Byte(5)).is_err());
+        let expected = SnbtConfig::new().from_str("{a: [{b: 1, c: 4b}, {b: [I; 5, 3]}]}").unwrap();
+        assert_eq!(value, expected);
+    }
+
+    #[test]
+    fn test_set_text_formats() {
+        let dir = std::env::temp_dir().join(format!("serde_nbt_set_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+
+        // tagged JSON keeps the tag types
+        let source = Source::File(dir.join("t.json"));
+        fs::write(source.path(), r#"{"type":"compound","value":{"a":{"type":"long","value":"1"}}}"#).unwrap();
+        let options = Options { json: true, tagged: true, ..Options::default() };
+        set(&source, "c", "3s", &options).unwrap();
+        let mut expected = Compound::new();
+        expected.insert("a".to_string(), Value::Long(1));
+        expected.insert("c".to_string(), Value::Short(3));
+        assert_eq!(read(&source, &options).unwrap().value, Value::Compound(expected));
+
+        // modern SNBT keeps lists with elements of different types
+        let source = Source::File(dir.join("m.snbt"));
+        fs::write(source.path(), "{l: [1b, \"x\"]}").unwrap();
+        let options = Options { modern: true, ..Options::default() };
+        set(&source, "c", "1b", &options).unwrap();
+        let mut expected = Compound::new();
+        expected.insert("l".to_string(), Value::List(vec![Value::Byte(1), Value::String("x".to_string())]));
+        expected.insert("c".to_string(), Value::Byte(1));
+        assert_eq!(read(&source, &options).unwrap().value, Value::Compound(expected));
+        assert!(!fs::read_to_string(source.path()).unwrap().contains("\"\":"));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_region_chunks() {
+        let dir = std::env::temp_dir().join(format!("serde_nbt_region_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("r.0.0.mca");
+
+        let mut compound = Compound::new();
+        compound.insert("a".to_string(), Value::Int(1));
+        let mut document = Document {
+            name: String::new(),
+            value: Value::Compound(compound),
+            format: Format::Java,
+            compression: Compression::Zlib,
+            bedrock_header: None,
+        };
+        let chunk = Source::Chunk { path: path.clone(), x: 35, z: 7 };
+        write(&chunk, &document, None).unwrap();
+
+        // chunks grow into new sectors when they no longer fit
+        let options = Options::default();
+        assert_eq!(read(&chunk, &options).unwrap().value, document.value);
+        let mut compound = Compound::new();
+        compound.insert("a".to_string(), Value::ByteArray(vec![1; 3 * SECTOR_SIZE]));
+        document.value = Value::Compound(compound);
+        document.compression = Compression::None;
+        write(&chunk, &document, None).unwrap();
+        assert_eq!(read(&chunk, &options).unwrap().value, document.value);
+
+        let region = fs::read(&path).unwrap();
+        assert_eq!(chunk_location(&region, 3, 7), (3, 4));
+        assert_eq!(region.len(), 7 * SECTOR_SIZE);
+        assert!(read(&Source::Chunk { path: path.clone(), x: 0, z: 0 }, &options).is_err());
+        assert!(check(&chunk, &options).is_ok());
+        let file = path.to_str().unwrap();
+        assert!(check_region(file, &path, &options).unwrap().iter().all(|(_, result)| result.is_ok()));
+
+        // the sectors of a truncated file are not reused
+        fs::write(&path, &region[..4 * SECTOR_SIZE]).unwrap();
+        let results = check_region(file, &path, &options).unwrap();
+        assert_eq!(results.len(), 1);
+        assert!(results[0].1.is_err());
+        document.value = Value::Compound(Compound::new());
+        write(&chunk, &document, None).unwrap();
+        assert_eq!(chunk_location(&fs::read(&path).unwrap(), 3, 7), (4, 1));
+        assert_eq!(read(&chunk, &options).unwrap().value, document.value);
+
+        fs::write(&path, b"").unwrap();
+        assert!(check_region(file, &path, &options).is_err());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
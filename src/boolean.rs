@@ -0,0 +1,57 @@
+// `#[serde(with = "...")]` module for `bool` fields that are stored as bytes
+//
+// The deserializer reads a `TAG_I8` as a `bool` directly, but values that are
+// buffered first, for example the fields of `#[serde(tag = "id")]` enums or
+// `#[serde(flatten)]` structs, only remember that they were an integer.
+
+/// use with `#[serde(with = "serde_nbt::byte_bool")]` to read a `bool` from
+/// any integer or boolean, and to write it as a `TAG_I8`
+pub mod byte_bool {
+    use serde::de::{self, Deserializer, Visitor};
+    use serde::ser::Serializer;
+
+    pub fn serialize<S>(value: &bool, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_i8(*value as i8)
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<bool, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(ByteBoolVisitor)
+    }
+
+    struct ByteBoolVisitor;
+
+    impl<'de> Visitor<'de> for ByteBoolVisitor {
+        type Value = bool;
+
+        fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+            formatter.write_str("a boolean or an integer")
+        }
+
+        fn visit_bool<E>(self, v: bool) -> Result<bool, E>
+        where
+            E: de::Error,
+        {
+            Ok(v)
+        }
+
+        fn visit_i64<E>(self, v: i64) -> Result<bool, E>
+        where
+            E: de::Error,
+        {
+            Ok(v != 0)
+        }
+
+        fn visit_u64<E>(self, v: u64) -> Result<bool, E>
+        where
+            E: de::Error,
+        {
+            Ok(v != 0)
+        }
+    }
+}
@@ -0,0 +1,502 @@
+// Options shared by the serializer and the deserializer
+
+use std::convert::TryFrom;
+use serde::{de, Deserialize, Serialize};
+use crate::de::{Deserializer, StreamDeserializer};
+use crate::error::{Error, Result};
+use crate::nbt;
+use crate::nbt::input::{Input, SliceInput};
+use crate::ser::Serializer;
+use crate::snbt::{SnbtParser, SnbtPrinter};
+use crate::value::Value;
+
+/// the order of the bytes of numbers and length prefixes
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ByteOrder {
+    /// used by Java Edition
+    #[default]
+    BigEndian,
+    /// used by Bedrock Edition's files
+    LittleEndian,
+}
+
+/// how the bytes of strings and tag names are encoded
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum StringEncoding {
+    #[default]
+    Utf8,
+    /// Java's `DataOutput.writeUTF` encoding, which vanilla uses. `\0` is
+    /// written as two bytes and characters outside of the basic multilingual
+    /// plane as surrogate pairs. Plain UTF-8 is accepted when reading.
+    ModifiedUtf8,
+}
+
+/// the tag at the root of the NBT data
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RootMode {
+    /// a `TAG_COMPOUND` with a name, as in files
+    #[default]
+    NamedCompound,
+    /// a `TAG_COMPOUND` without a name, as sent over the network since 1.20.2
+    UnnamedCompound,
+    /// a tag of any type without a name, as sent over the network since
+    /// 1.20.3
+    UnnamedAny,
+}
+
+impl RootMode {
+    pub(crate) fn is_named(self) -> bool {
+        self == RootMode::NamedCompound
+    }
+
+    /// fails unless the root tag may have type `tag_type`
+    pub(crate) fn check_tag_type(self, tag_type: u8) -> Result<()> {
+        match (self, tag_type) {
+            (_, nbt::TAG_END) => Err(Error::InvalidTagTypeError),
+            (RootMode::UnnamedAny, _) | (_, nbt::TAG_COMPOUND) => Ok(()),
+            _ => Err(Error::InvalidTagTypeError),
+        }
+    }
+}
+
+/// the syntax of SNBT
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SnbtDialect {
+    /// the syntax vanilla reads and writes before 1.21.5
+    #[default]
+    Legacy,
+    /// the syntax of 1.21.5, which adds hexadecimal and binary integers,
+    /// signedness suffixes, more escape sequences, lists with elements of
+    /// different types and the operations `bool(...)` and `uuid(...)`
+    Modern,
+}
+
+/// how `u32` and `u64` are mapped to `TAG_I32` and `TAG_I64`
+///
+/// NBT has no unsigned types. `u8` and `u16` are always written as the
+/// `TAG_I8` or `TAG_I16` with the same bits, for `u32` and `u64` this can be
+/// chosen.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum UnsignedMode {
+    /// values are written with the same bits, so `u32::MAX` becomes `-1`
+    #[default]
+    TwosComplement,
+    /// values have to fit into the signed type, anything else is an error
+    RangeChecked,
+}
+
+impl UnsignedMode {
+    pub(crate) fn u32_from_i32(self, value: i32) -> Result<u32> {
+        match self {
+            UnsignedMode::TwosComplement => Ok(value as u32),
+            UnsignedMode::RangeChecked => u32::try_from(value).map_err(|_| {
+                de::Error::invalid_value(de::Unexpected::Signed(value as i64), &"a u32")
+            }),
+        }
+    }
+
+    pub(crate) fn u64_from_i64(self, value: i64) -> Result<u64> {
+        match self {
+            UnsignedMode::TwosComplement => Ok(value as u64),
+            UnsignedMode::RangeChecked => u64::try_from(value).map_err(|_| {
+                de::Error::invalid_value(de::Unexpected::Signed(value), &"a u64")
+            }),
+        }
+    }
+
+    pub(crate) fn i32_from_u32(self, value: u32) -> Result<i32> {
+        match self {
+            UnsignedMode::TwosComplement => Ok(value as i32),
+            UnsignedMode::RangeChecked => i32::try_from(value)
+                .map_err(|_| Error::Message(format!("{} does not fit in a TAG_I32", value))),
+        }
+    }
+
+    pub(crate) fn i64_from_u64(self, value: u64) -> Result<i64> {
+        match self {
+            UnsignedMode::TwosComplement => Ok(value as i64),
+            UnsignedMode::RangeChecked => i64::try_from(value)
+                .map_err(|_| Error::Message(format!("{} does not fit in a TAG_I64", value))),
+        }
+    }
+}
+
+/// options for reading NBT, `DeserializerConfig::new()` gives the same
+/// behavior as `from_reader` and `from_slice`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DeserializerConfig {
+    pub(crate) byte_order: ByteOrder,
+    pub(crate) string_encoding: StringEncoding,
+    pub(crate) root_mode: RootMode,
+    pub(crate) max_depth: usize,
+    pub(crate) size_quota: usize,
+    pub(crate) lenient_numbers: bool,
+    pub(crate) unsigned_mode: UnsignedMode,
+    pub(crate) deny_unknown_fields: bool,
+    pub(crate) deny_trailing_data: bool,
+    pub(crate) heterogeneous_lists: bool,
+}
+
+impl Default for DeserializerConfig {
+    fn default() -> Self {
+        DeserializerConfig {
+            byte_order: ByteOrder::default(),
+            string_encoding: StringEncoding::default(),
+            root_mode: RootMode::default(),
+            max_depth: nbt::DEFAULT_MAX_DEPTH,
+            size_quota: usize::MAX,
+            lenient_numbers: false,
+            unsigned_mode: UnsignedMode::default(),
+            deny_unknown_fields: false,
+            deny_trailing_data: false,
+            heterogeneous_lists: false,
+        }
+    }
+}
+
+impl DeserializerConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn byte_order(mut self, byte_order: ByteOrder) -> Self {
+        self.byte_order = byte_order;
+        self
+    }
+
+    pub fn string_encoding(mut self, string_encoding: StringEncoding) -> Self {
+        self.string_encoding = string_encoding;
+        self
+    }
+
+    /// the root tag has to match `root_mode`, anything else fails with
+    /// `Error::InvalidTagTypeError`
+    pub fn root_mode(mut self, root_mode: RootMode) -> Self {
+        self.root_mode = root_mode;
+        self
+    }
+
+    /// the maximum number of nested compounds and lists, more deeply nested
+    /// input fails with `Error::DepthLimitError`
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// the maximum number of bytes of strings, arrays and lists which are
+    /// read, exceeding it fails with `Error::SizeLimitError`
+    pub fn size_quota(mut self, size_quota: usize) -> Self {
+        self.size_quota = size_quota;
+        self
+    }
+
+    /// when enabled numbers and bools are read from any numeric tag and
+    /// converted the way vanilla's `CompoundTag` getters do, otherwise the
+    /// tag has to be of the requested type
+    pub fn lenient_numbers(mut self, lenient_numbers: bool) -> Self {
+        self.lenient_numbers = lenient_numbers;
+        self
+    }
+
+    /// how `u32` and `u64` are read from `TAG_I32` and `TAG_I64`
+    pub fn unsigned_mode(mut self, unsigned_mode: UnsignedMode) -> Self {
+        self.unsigned_mode = unsigned_mode;
+        self
+    }
+
+    /// when enabled a compound's tags which are not fields of the struct it
+    /// is read into are an error, otherwise they are skipped
+    pub fn deny_unknown_fields(mut self, deny_unknown_fields: bool) -> Self {
+        self.deny_unknown_fields = deny_unknown_fields;
+        self
+    }
+
+    /// when enabled any input after the root tag fails with
+    /// `Error::TrailingDataError`, otherwise it is not read
+    pub fn deny_trailing_data(mut self, deny_trailing_data: bool) -> Self {
+        self.deny_trailing_data = deny_trailing_data;
+        self
+    }
+
+    /// when enabled compounds in lists which only hold a tag named "" are
+    /// read as that tag's value, like vanilla does for lists with elements
+    /// of different types since 1.21.5
+    pub fn heterogeneous_lists(mut self, heterogeneous_lists: bool) -> Self {
+        self.heterogeneous_lists = heterogeneous_lists;
+        self
+    }
+
+    pub fn from_reader<R, T>(&self, input: R) -> Result<T>
+    where
+        R: std::io::Read,
+        T: de::DeserializeOwned,
+    {
+        self.read(input)
+    }
+
+    /// like `from_reader`, `&str` and `&[u8]` fields are borrowed from
+    /// `input` instead of being copied
+    pub fn from_slice<'de, T>(&self, input: &'de [u8]) -> Result<T>
+    where
+        T: Deserialize<'de>,
+    {
+        self.read(SliceInput::new(input))
+    }
+
+    /// like `from_reader`, but also returns the root tag's name
+    pub fn from_reader_named<R, T>(&self, input: R) -> Result<(String, T)>
+    where
+        R: std::io::Read,
+        T: de::DeserializeOwned,
+    {
+        self.read_named(input)
+    }
+
+    /// like `from_slice`, but also returns the root tag's name
+    pub fn from_slice_named<'de, T>(&self, input: &'de [u8]) -> Result<(String, T)>
+    where
+        T: Deserialize<'de>,
+    {
+        self.read_named(SliceInput::new(input))
+    }
+
+    /// reads consecutive root tags from `input` as values of type `T`
+    pub fn stream_from_reader<R, T>(&self, input: R) -> StreamDeserializer<'static, R, T>
+    where
+        R: std::io::Read,
+        T: de::DeserializeOwned,
+    {
+        StreamDeserializer::with_config(input, *self)
+    }
+
+    /// like `stream_from_reader`, but borrows from `input` like `from_slice`
+    pub fn stream_from_slice<'de, T>(&self, input: &'de [u8]) -> StreamDeserializer<'de, SliceInput<'de>, T>
+    where
+        T: Deserialize<'de>,
+    {
+        StreamDeserializer::with_config(SliceInput::new(input), *self)
+    }
+
+    fn read<'de, R, T>(&self, input: R) -> Result<T>
+    where
+        R: Input<'de>,
+        T: Deserialize<'de>,
+    {
+        self.read_named(input).map(|(_, value)| value)
+    }
+
+    fn read_named<'de, R, T>(&self, input: R) -> Result<(String, T)>
+    where
+        R: Input<'de>,
+        T: Deserialize<'de>,
+    {
+        let mut deserializer = Deserializer::with_config(input, *self)?;
+        let value = T::deserialize(&mut deserializer)?;
+        deserializer.end()?;
+        Ok((deserializer.root_name().to_string(), value))
+    }
+}
+
+/// options for writing NBT, `SerializerConfig::new()` gives the same
+/// behavior as `to_writer`
+///
+/// Options that both configs have work the same way, data written with a
+/// `SerializerConfig` can be read with a `DeserializerConfig` set up alike.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SerializerConfig {
+    pub(crate) byte_order: ByteOrder,
+    pub(crate) string_encoding: StringEncoding,
+    pub(crate) root_mode: RootMode,
+    pub(crate) max_depth: usize,
+    pub(crate) size_quota: usize,
+    pub(crate) unsigned_mode: UnsignedMode,
+    pub(crate) sort_keys: bool,
+    pub(crate) buffer_limit: usize,
+    pub(crate) heterogeneous_lists: bool,
+    pub(crate) typed_empty_lists: bool,
+}
+
+impl Default for SerializerConfig {
+    fn default() -> Self {
+        SerializerConfig {
+            byte_order: ByteOrder::default(),
+            string_encoding: StringEncoding::default(),
+            root_mode: RootMode::default(),
+            max_depth: nbt::DEFAULT_MAX_DEPTH,
+            size_quota: usize::MAX,
+            unsigned_mode: UnsignedMode::default(),
+            sort_keys: false,
+            buffer_limit: nbt::DEFAULT_BUFFER_LIMIT,
+            heterogeneous_lists: false,
+            typed_empty_lists: false,
+        }
+    }
+}
+
+impl SerializerConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn byte_order(mut self, byte_order: ByteOrder) -> Self {
+        self.byte_order = byte_order;
+        self
+    }
+
+    pub fn string_encoding(mut self, string_encoding: StringEncoding) -> Self {
+        self.string_encoding = string_encoding;
+        self
+    }
+
+    /// values which do not match `root_mode` fail with
+    /// `Error::InvalidTagTypeError`
+    pub fn root_mode(mut self, root_mode: RootMode) -> Self {
+        self.root_mode = root_mode;
+        self
+    }
+
+    /// the maximum number of nested compounds and lists, writing more deeply
+    /// nested values fails with `Error::DepthLimitError`
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// the maximum number of bytes of strings, arrays and lists which are
+    /// written, counted like the deserializer does, exceeding it fails with
+    /// `Error::SizeLimitError`
+    pub fn size_quota(mut self, size_quota: usize) -> Self {
+        self.size_quota = size_quota;
+        self
+    }
+
+    /// how `u32` and `u64` are written as `TAG_I32` and `TAG_I64`
+    pub fn unsigned_mode(mut self, unsigned_mode: UnsignedMode) -> Self {
+        self.unsigned_mode = unsigned_mode;
+        self
+    }
+
+    /// whether the tags of every compound are written sorted by name instead
+    /// of in the order they are serialized, so that maps are written the
+    /// same way every time
+    pub fn sort_keys(mut self, sort_keys: bool) -> Self {
+        self.sort_keys = sort_keys;
+        self
+    }
+
+    /// the maximum number of bytes of a sequence of unknown length which are
+    /// kept in memory until its length is known, exceeding it fails with
+    /// `Error::BufferLimitError`
+    pub fn buffer_limit(mut self, buffer_limit: usize) -> Self {
+        self.buffer_limit = buffer_limit;
+        self
+    }
+
+    /// when enabled lists with elements of different types are written as a
+    /// list of compounds, each holding an element as a tag named "", which
+    /// vanilla reads since 1.21.5, otherwise they fail with
+    /// `Error::ListElementTypeError`
+    ///
+    /// Since the element types are only known once a list ends, all lists
+    /// are then kept in memory until they end, up to the buffer limit.
+    pub fn heterogeneous_lists(mut self, heterogeneous_lists: bool) -> Self {
+        self.heterogeneous_lists = heterogeneous_lists;
+        self
+    }
+
+    /// when enabled empty lists of fields using one of the list modules, such
+    /// as `int_list`, are written with the module's element type, otherwise
+    /// all empty lists are written with `TAG_END` like vanilla does
+    pub fn typed_empty_lists(mut self, typed_empty_lists: bool) -> Self {
+        self.typed_empty_lists = typed_empty_lists;
+        self
+    }
+
+    pub fn to_writer<W, T>(&self, output: W, value: &T) -> Result<()>
+    where
+        W: std::io::Write,
+        T: ?Sized + Serialize,
+    {
+        self.to_writer_named(output, "", value)
+    }
+
+    /// like `to_writer`, but gives the root tag a name
+    pub fn to_writer_named<W, T>(&self, output: W, name: &str, value: &T) -> Result<()>
+    where
+        W: std::io::Write,
+        T: ?Sized + Serialize,
+    {
+        let mut serializer = Serializer::with_config(output, *self);
+        serializer.set_root_name(name)?;
+        value.serialize(&mut serializer)?;
+        Ok(())
+    }
+}
+
+/// options for reading and writing SNBT, `SnbtConfig::new()` gives the same
+/// behavior as `from_snbt` and `to_snbt`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SnbtConfig {
+    pub(crate) dialect: SnbtDialect,
+    pub(crate) max_depth: usize,
+    pub(crate) sort_keys: bool,
+    pub(crate) pretty: bool,
+}
+
+impl Default for SnbtConfig {
+    fn default() -> Self {
+        SnbtConfig {
+            dialect: SnbtDialect::default(),
+            max_depth: nbt::DEFAULT_MAX_DEPTH,
+            sort_keys: false,
+            pretty: false,
+        }
+    }
+}
+
+impl SnbtConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// the syntax which is read and written
+    ///
+    /// The legacy dialect writes lists with elements of different types as
+    /// lists of compounds, each holding an element as a tag named "".
+    pub fn dialect(mut self, dialect: SnbtDialect) -> Self {
+        self.dialect = dialect;
+        self
+    }
+
+    /// the maximum number of nested compounds and lists, more deeply nested
+    /// input fails with `Error::DepthLimitError`
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// whether the tags of every compound are written sorted by name
+    pub fn sort_keys(mut self, sort_keys: bool) -> Self {
+        self.sort_keys = sort_keys;
+        self
+    }
+
+    /// when enabled the tags of compounds and the elements of lists are
+    /// written on separate, indented lines
+    pub fn pretty(mut self, pretty: bool) -> Self {
+        self.pretty = pretty;
+        self
+    }
+
+    /// reads a value, syntax errors fail with `Error::SyntaxError` holding
+    /// the byte offset of the error in `input`
+    pub fn from_str(&self, input: &str) -> Result<Value> {
+        SnbtParser::new(input, *self).parse()
+    }
+
+    /// writes a value, NaN and infinite floats fail since SNBT can not
+    /// express them
+    pub fn to_string(&self, value: &Value) -> Result<String> {
+        SnbtPrinter::new(*self).print(value)
+    }
+}
@@ -1,47 +1,325 @@
+use std::borrow::Cow;
+use std::convert::TryFrom;
 use log::trace;
-use serde::de::{self, Deserialize, DeserializeSeed, MapAccess, SeqAccess, Visitor};
+use serde::de::{self, Deserialize, DeserializeOwned, DeserializeSeed, IntoDeserializer, MapAccess, SeqAccess, Visitor};
+use crate::array;
+use crate::config::{DeserializerConfig, UnsignedMode};
 use crate::error::{Error, Result};
+use crate::nbt;
+use crate::nbt::input::{Input, Reference, SliceInput};
 use crate::nbt::parser::{Parser, ValueType};
+use crate::number::Number;
+use crate::value;
 
-pub fn from_reader<'de, R, T>(input: R) -> Result<T>
+// largest size hint given for the elements of a list
+const SIZE_HINT_MAX: usize = 4096;
+
+pub fn from_reader<R, T>(input: R) -> Result<T>
 where
     R: std::io::Read,
-    T: Deserialize<'de>,
+    T: DeserializeOwned,
 {
-    let mut deserializer = Deserializer::new(input);
-    let value = T::deserialize(&mut deserializer)?;
-    Ok(value)
+    DeserializerConfig::default().from_reader(input)
 }
 
-pub struct Deserializer<R> {
-    parser: Parser<R>,
+/// deserializes `T` from a byte slice, `&str` and `&[u8]` fields are borrowed
+/// from `input` instead of being copied
+pub fn from_slice<'de, T>(input: &'de [u8]) -> Result<T>
+where
+    T: Deserialize<'de>,
+{
+    DeserializerConfig::default().from_slice(input)
 }
 
-impl<'de, R> Deserializer<R>
+/// like `from_reader`, but also returns the root tag's name
+pub fn from_reader_named<R, T>(input: R) -> Result<(String, T)>
 where
     R: std::io::Read,
+    T: DeserializeOwned,
 {
-    pub fn new(input: R) -> Self {
+    DeserializerConfig::default().from_reader_named(input)
+}
+
+/// like `from_slice`, but also returns the root tag's name
+pub fn from_slice_named<'de, T>(input: &'de [u8]) -> Result<(String, T)>
+where
+    T: Deserialize<'de>,
+{
+    DeserializerConfig::default().from_slice_named(input)
+}
+
+pub struct Deserializer<'de, R> {
+    parser: Parser<'de, R>,
+    config: DeserializerConfig,
+    root_name: String,
+}
+
+impl<'de> Deserializer<'de, SliceInput<'de>> {
+    pub fn from_slice(input: &'de [u8]) -> Result<Self> {
+        Deserializer::new(SliceInput::new(input))
+    }
+}
+
+impl<'de, R> Deserializer<'de, R>
+where
+    R: Input<'de>,
+{
+    pub fn new(input: R) -> Result<Self> {
+        Deserializer::with_config(input, DeserializerConfig::default())
+    }
+
+    /// reads the root tag's header, which has to match the config's root mode
+    pub fn with_config(input: R, config: DeserializerConfig) -> Result<Self> {
         #[cfg(debug_assertions)]
-        trace!("Deserializer::new");
+        trace!("Deserializer::with_config");
+
+        let mut deserializer = Deserializer::before_root(input, config);
+        deserializer.begin_root()?;
+        Ok(deserializer)
+    }
 
+    /// creates a deserializer which has not read a root tag yet
+    fn before_root(input: R, config: DeserializerConfig) -> Self {
         let mut parser = Parser::new(input);
+        parser.set_byte_order(config.byte_order);
+        parser.set_string_encoding(config.string_encoding);
+        parser.set_named_root(config.root_mode.is_named());
+        parser.set_max_depth(config.max_depth);
+        parser.set_size_quota(config.size_quota);
+        parser.set_heterogeneous_lists(config.heterogeneous_lists);
+
+        Deserializer { parser, config, root_name: String::new() }
+    }
+
+    /// reads the next root tag's header, fails with `Error::Eof` if the
+    /// input ends before it
+    fn begin_root(&mut self) -> Result<()> {
+        #[cfg(debug_assertions)]
+        trace!("Deserializer::begin_root");
 
-        // advance the parser into the intial TAG_COMPOUND tag
-        // FIXME: this can panic, needs better error handling
-        parser.next().expect("");
+        // advance the parser to the root tag's header
+        self.parser.advance()?;
+        self.config.root_mode.check_tag_type(self.parser.get_tag_type())?;
+        self.root_name = self.parser.get_string_value()?;
 
         // read/parse before values are retrieved so we can check their types
-        // FIXME: this can panic, needs better error handling
-        parser.next().expect("");
+        self.parser.advance()
+    }
+
+    /// the root tag's name, empty if the root mode has no names
+    pub fn root_name(&self) -> &str {
+        &self.root_name
+    }
+
+    /// checks that the root tag has been read completely, and that nothing
+    /// follows it if the config denies trailing data
+    pub fn end(&mut self) -> Result<()> {
+        #[cfg(debug_assertions)]
+        trace!("Deserializer::end");
+
+        if !self.parser.is_between_roots() {
+            return Err(Error::InvalidParserStateError);
+        }
+
+        if self.config.deny_trailing_data {
+            match self.parser.advance() {
+                Err(Error::Eof) => {},
+                _ => return Err(Error::TrailingDataError),
+            }
+        }
+        Ok(())
+    }
+
+    /// returns the current value if it is any numeric tag
+    fn get_number_value(&self) -> Result<Number> {
+        match self.parser.get_value_type() {
+            ValueType::I8 => Ok(Number::I8(self.parser.get_i8_value()?)),
+            ValueType::I16 => Ok(Number::I16(self.parser.get_i16_value()?)),
+            ValueType::I32 => Ok(Number::I32(self.parser.get_i32_value()?)),
+            ValueType::I64 => Ok(Number::I64(self.parser.get_i64_value()?)),
+            ValueType::F32 => Ok(Number::F32(self.parser.get_f32_value()?)),
+            ValueType::F64 => Ok(Number::F64(self.parser.get_f64_value()?)),
+            _ => Err(Error::InvalidTagTypeError),
+        }
+    }
+
+    fn get_bool_value(&self) -> Result<bool> {
+        if self.config.lenient_numbers {
+            return Ok(self.get_number_value()?.as_bool());
+        }
+        Ok(self.parser.get_i8_value()? != 0)
+    }
+
+    fn get_i8_value(&self) -> Result<i8> {
+        if self.config.lenient_numbers {
+            return Ok(self.get_number_value()?.as_i8());
+        }
+        self.parser.get_i8_value()
+    }
+
+    fn get_i16_value(&self) -> Result<i16> {
+        if self.config.lenient_numbers {
+            return Ok(self.get_number_value()?.as_i16());
+        }
+        self.parser.get_i16_value()
+    }
+
+    fn get_i32_value(&self) -> Result<i32> {
+        if self.config.lenient_numbers {
+            return Ok(self.get_number_value()?.as_i32());
+        }
+        self.parser.get_i32_value()
+    }
+
+    fn get_i64_value(&self) -> Result<i64> {
+        if self.config.lenient_numbers {
+            return Ok(self.get_number_value()?.as_i64());
+        }
+        self.parser.get_i64_value()
+    }
+
+    fn get_f32_value(&self) -> Result<f32> {
+        if self.config.lenient_numbers {
+            return Ok(self.get_number_value()?.as_f32());
+        }
+        self.parser.get_f32_value()
+    }
+
+    fn get_f64_value(&self) -> Result<f64> {
+        if self.config.lenient_numbers {
+            return Ok(self.get_number_value()?.as_f64());
+        }
+        self.parser.get_f64_value()
+    }
+
+    /// passes the elements of an array which was read all at once to `visitor`
+    fn visit_array<T, V>(&mut self, value: Vec<T>, visitor: V) -> Result<V::Value>
+    where
+        T: ArrayElement,
+        V: Visitor<'de>,
+    {
+        let len = value.len();
+        let mut access = ArrayAccess {
+            iter: value.into_iter(),
+            unsigned_mode: self.config.unsigned_mode,
+            lenient_numbers: self.config.lenient_numbers,
+        };
+        let value = visitor.visit_seq(&mut access)?;
+
+        if access.iter.len() != 0 {
+            return Err(de::Error::invalid_length(len, &"fewer elements in array"));
+        }
+
+        // continue after the end of the array
+        self.parser.advance()?;
+
+        Ok(value)
+    }
+
+    /// passes the current value to `Value`'s visitor, arrays are passed as a
+    /// map from the name of their wrapper type to their elements so that
+    /// they can be told apart from lists
+    fn deserialize_value<V>(&mut self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        #[cfg(debug_assertions)]
+        trace!("Deserializer::deserialize_value");
+
+        let name = match self.parser.get_tag_type() {
+            nbt::TAG_I8_ARRAY => array::I8_ARRAY_NAME,
+            nbt::TAG_I32_ARRAY => array::I32_ARRAY_NAME,
+            nbt::TAG_I64_ARRAY => array::I64_ARRAY_NAME,
+            _ => return de::Deserializer::deserialize_any(self, visitor),
+        };
 
-        Deserializer { parser }
+        visitor.visit_map(ArrayEntry { de: self, name: Some(name) })
     }
 }
 
-impl<'de, 'a, R> de::Deserializer<'de> for &'a mut Deserializer<R>
+/// reads consecutive root tags from a single input, such as concatenated
+/// NBT files or captured network data, as values of type `T`
+///
+/// Iteration stops at the end of the input, or after the first error. The
+/// config's trailing data policy does not apply, all input is read.
+pub struct StreamDeserializer<'de, R, T> {
+    de: Deserializer<'de, R>,
+    failed: bool,
+    marker: std::marker::PhantomData<T>,
+}
+
+impl<'de, T> StreamDeserializer<'de, SliceInput<'de>, T>
 where
-    R: std::io::Read,
+    T: Deserialize<'de>,
+{
+    pub fn from_slice(input: &'de [u8]) -> Self {
+        StreamDeserializer::new(SliceInput::new(input))
+    }
+}
+
+impl<'de, R, T> StreamDeserializer<'de, R, T>
+where
+    R: Input<'de>,
+    T: Deserialize<'de>,
+{
+    pub fn new(input: R) -> Self {
+        StreamDeserializer::with_config(input, DeserializerConfig::default())
+    }
+
+    pub fn with_config(input: R, config: DeserializerConfig) -> Self {
+        StreamDeserializer {
+            de: Deserializer::before_root(input, config),
+            failed: false,
+            marker: std::marker::PhantomData,
+        }
+    }
+
+    /// the name of the root tag that was read last
+    pub fn root_name(&self) -> &str {
+        self.de.root_name()
+    }
+
+    fn read_root(&mut self) -> Result<T> {
+        let value = T::deserialize(&mut self.de)?;
+        if !self.de.parser.is_between_roots() {
+            return Err(Error::InvalidParserStateError);
+        }
+        Ok(value)
+    }
+}
+
+impl<'de, R, T> Iterator for StreamDeserializer<'de, R, T>
+where
+    R: Input<'de>,
+    T: Deserialize<'de>,
+{
+    type Item = Result<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        #[cfg(debug_assertions)]
+        trace!("StreamDeserializer::next");
+
+        if self.failed {
+            return None;
+        }
+
+        let result = match self.de.begin_root() {
+            // the input ending between root tags is the regular end
+            Err(Error::Eof) => {
+                self.failed = true;
+                return None;
+            },
+            Err(e) => Err(e),
+            Ok(()) => self.read_root(),
+        };
+        self.failed = result.is_err();
+        Some(result)
+    }
+}
+
+impl<'de, R> de::Deserializer<'de> for &mut Deserializer<'de, R>
+where
+    R: Input<'de>,
 {
     type Error = Error;
 
@@ -52,51 +330,16 @@ where
         #[cfg(debug_assertions)]
         trace!("Deserializer::deserialize_any");
 
-        // FIXME: use the deserialize_* functions instead of reimplementing them
         match self.parser.get_value_type() {
-            ValueType::I8 => {
-                let value = self.parser.get_i8_value()?;
-                self.parser.next()?;
-                visitor.visit_i8(value)
-            }
-            ValueType::I16 => {
-                let value = self.parser.get_i16_value()?;
-                self.parser.next()?;
-                visitor.visit_i16(value)
-            }
-            ValueType::I32 => {
-                let value = self.parser.get_i32_value()?;
-                self.parser.next()?;
-                visitor.visit_i32(value)
-            }
-            ValueType::I64 => {
-                let value = self.parser.get_i64_value()?;
-                self.parser.next()?;
-                visitor.visit_i64(value)
-            }
-            ValueType::F32 => {
-                let value = self.parser.get_f32_value()?;
-                self.parser.next()?;
-                visitor.visit_f32(value)
-            }
-            ValueType::F64 => {
-                let value = self.parser.get_f64_value()?;
-                self.parser.next()?;
-                visitor.visit_f64(value)
-            }
-            ValueType::String => {
-                let value = self.parser.get_string_value()?;
-                self.parser.next()?;
-                visitor.visit_string(value)
-            }
-            ValueType::SeqBegin => {
-                self.parser.next()?;
-                visitor.visit_seq(self)
-            }
-            ValueType::MapBegin => {
-                self.parser.next()?;
-                visitor.visit_map(self)
-            }
+            ValueType::I8 => self.deserialize_i8(visitor),
+            ValueType::I16 => self.deserialize_i16(visitor),
+            ValueType::I32 => self.deserialize_i32(visitor),
+            ValueType::I64 => self.deserialize_i64(visitor),
+            ValueType::F32 => self.deserialize_f32(visitor),
+            ValueType::F64 => self.deserialize_f64(visitor),
+            ValueType::String => self.deserialize_str(visitor),
+            ValueType::SeqBegin => self.deserialize_seq(visitor),
+            ValueType::MapBegin => self.deserialize_map(visitor),
             ValueType::Invalid => Err(Error::InvalidParserStateError),
             ValueType::SeqEnd => Err(Error::InvalidParserStateError),
             ValueType::MapEnd => Err(Error::InvalidParserStateError),
@@ -110,15 +353,12 @@ where
         #[cfg(debug_assertions)]
         trace!("Deserializer::deserialize_bool");
 
-        let value = match self.parser.get_i8_value()? {
-            0 => false,
-            _ => true,
-        };
+        let value = self.get_bool_value()?;
 
         #[cfg(debug_assertions)]
         trace!("Deserializer::deserialize_bool(visitor) -> {:?}", value);
 
-        self.parser.next()?;
+        self.parser.advance()?;
 
         visitor.visit_bool(value)
     }
@@ -130,12 +370,12 @@ where
         #[cfg(debug_assertions)]
         trace!("Deserializer::deserialize_i8");
 
-        let value = self.parser.get_i8_value()?;
+        let value = self.get_i8_value()?;
 
         #[cfg(debug_assertions)]
         trace!("Deserializer::deserialize_i8(visitor) -> {:?}", value);
 
-        self.parser.next()?;
+        self.parser.advance()?;
 
         visitor.visit_i8(value)
     }
@@ -147,12 +387,12 @@ where
         #[cfg(debug_assertions)]
         trace!("Deserializer::deserialize_i16");
 
-        let value = self.parser.get_i16_value()?;
+        let value = self.get_i16_value()?;
 
         #[cfg(debug_assertions)]
         trace!("Deserializer::deserialize_i16(visitor) -> {:?}", value);
 
-        self.parser.next()?;
+        self.parser.advance()?;
 
         visitor.visit_i16(value)
     }
@@ -164,12 +404,12 @@ where
         #[cfg(debug_assertions)]
         trace!("Deserializer::deserialize_i32");
 
-        let value = self.parser.get_i32_value()?;
+        let value = self.get_i32_value()?;
 
         #[cfg(debug_assertions)]
         trace!("Deserializer::deserialize_i32(visitor) -> {:?}", value);
 
-        self.parser.next()?;
+        self.parser.advance()?;
 
         visitor.visit_i32(value)
     }
@@ -181,42 +421,72 @@ where
         #[cfg(debug_assertions)]
         trace!("Deserializer::deserialize_i64");
 
-        let value = self.parser.get_i64_value()?;
+        let value = self.get_i64_value()?;
 
         #[cfg(debug_assertions)]
         trace!("Deserializer::deserialize_i64(visitor) -> {:?}", value);
 
-        self.parser.next()?;
+        self.parser.advance()?;
 
         visitor.visit_i64(value)
     }
 
-    fn deserialize_u8<V>(self, _visitor: V) -> Result<V::Value>
+    fn deserialize_u8<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        todo!()
+        #[cfg(debug_assertions)]
+        trace!("Deserializer::deserialize_u8");
+
+        // the same bits as the `TAG_I8`
+        let value = self.get_i8_value()? as u8;
+
+        self.parser.advance()?;
+
+        visitor.visit_u8(value)
     }
 
-    fn deserialize_u16<V>(self, _visitor: V) -> Result<V::Value>
+    fn deserialize_u16<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        todo!()
+        #[cfg(debug_assertions)]
+        trace!("Deserializer::deserialize_u16");
+
+        // the same bits as the `TAG_I16`
+        let value = self.get_i16_value()? as u16;
+
+        self.parser.advance()?;
+
+        visitor.visit_u16(value)
     }
 
-    fn deserialize_u32<V>(self, _visitor: V) -> Result<V::Value>
+    fn deserialize_u32<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        todo!()
+        #[cfg(debug_assertions)]
+        trace!("Deserializer::deserialize_u32");
+
+        let value = self.config.unsigned_mode.u32_from_i32(self.get_i32_value()?)?;
+
+        self.parser.advance()?;
+
+        visitor.visit_u32(value)
     }
 
-    fn deserialize_u64<V>(self, _visitor: V) -> Result<V::Value>
+    fn deserialize_u64<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        todo!()
+        #[cfg(debug_assertions)]
+        trace!("Deserializer::deserialize_u64");
+
+        let value = self.config.unsigned_mode.u64_from_i64(self.get_i64_value()?)?;
+
+        self.parser.advance()?;
+
+        visitor.visit_u64(value)
     }
 
     fn deserialize_f32<V>(self, visitor: V) -> Result<V::Value>
@@ -226,12 +496,12 @@ where
         #[cfg(debug_assertions)]
         trace!("Deserializer::deserialize_f32");
 
-        let value = self.parser.get_f32_value()?;
+        let value = self.get_f32_value()?;
 
         #[cfg(debug_assertions)]
         trace!("Deserializer::deserialize_f32(visitor) -> {:?}", value);
 
-        self.parser.next()?;
+        self.parser.advance()?;
 
         visitor.visit_f32(value)
     }
@@ -243,28 +513,53 @@ where
         #[cfg(debug_assertions)]
         trace!("Deserializer::deserialize_f64");
 
-        let value = self.parser.get_f64_value()?;
+        let value = self.get_f64_value()?;
 
         #[cfg(debug_assertions)]
         trace!("Deserializer::deserialize_f64(visitor) -> {:?}", value);
 
-        self.parser.next()?;
+        self.parser.advance()?;
 
         visitor.visit_f64(value)
     }
 
-    fn deserialize_char<V>(self, _visitor: V) -> Result<V::Value>
+    fn deserialize_char<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        todo!()
+        #[cfg(debug_assertions)]
+        trace!("Deserializer::deserialize_char");
+
+        // a `TAG_STRING` holding exactly one character
+        let value = {
+            let value = self.parser.get_str_value()?;
+            let mut chars = value.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => c,
+                _ => return Err(de::Error::invalid_value(de::Unexpected::Str(&value), &"a single character")),
+            }
+        };
+
+        self.parser.advance()?;
+
+        visitor.visit_char(value)
     }
 
-    fn deserialize_str<V>(self, _visitor: V) -> Result<V::Value>
+    fn deserialize_str<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        todo!()
+        #[cfg(debug_assertions)]
+        trace!("Deserializer::deserialize_str");
+
+        let value = match self.parser.get_str_value()? {
+            Reference::Borrowed(value) => visitor.visit_borrowed_str::<Error>(value)?,
+            Reference::Copied(value) => visitor.visit_str::<Error>(value)?,
+        };
+
+        self.parser.advance()?;
+
+        Ok(value)
     }
 
     fn deserialize_string<V>(self, visitor: V) -> Result<V::Value>
@@ -279,51 +574,107 @@ where
         #[cfg(debug_assertions)]
         trace!("Deserializer::deserialize_f64(visitor) -> {:?}", value);
 
-        self.parser.next()?;
+        self.parser.advance()?;
 
         visitor.visit_string(value)
     }
 
-    fn deserialize_bytes<V>(self, _visitor: V) -> Result<V::Value>
+    fn deserialize_bytes<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        todo!()
+        #[cfg(debug_assertions)]
+        trace!("Deserializer::deserialize_bytes");
+
+        // lists of bytes are read element by element
+        if self.parser.get_tag_type() != nbt::TAG_I8_ARRAY {
+            return self.deserialize_seq(visitor);
+        }
+
+        let value = match self.parser.read_byte_array()? {
+            Cow::Borrowed(value) => visitor.visit_borrowed_bytes::<Error>(value)?,
+            Cow::Owned(value) => visitor.visit_byte_buf::<Error>(value)?,
+        };
+
+        self.parser.advance()?;
+
+        Ok(value)
     }
 
-    fn deserialize_byte_buf<V>(self, _visitor: V) -> Result<V::Value>
+    fn deserialize_byte_buf<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        todo!()
+        #[cfg(debug_assertions)]
+        trace!("Deserializer::deserialize_byte_buf");
+
+        self.deserialize_bytes(visitor)
     }
 
-    fn deserialize_option<V>(self, _visitor: V) -> Result<V::Value>
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        todo!()
+        #[cfg(debug_assertions)]
+        trace!("Deserializer::deserialize_option");
+
+        // `None` is written by leaving out the compound's tag, serde treats
+        // missing `Option` fields as `None` so any value that is here is `Some`
+        visitor.visit_some(self)
     }
 
-    fn deserialize_unit<V>(self, _visitor: V) -> Result<V::Value>
+    fn deserialize_unit<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        todo!()
+        #[cfg(debug_assertions)]
+        trace!("Deserializer::deserialize_unit");
+
+        // unit is written as an empty compound
+        if self.parser.get_value_type() != ValueType::MapBegin {
+            return Err(Error::InvalidTagTypeError);
+        }
+        self.parser.advance()?;
+        if self.parser.get_value_type() != ValueType::MapEnd {
+            return Err(de::Error::invalid_length(1, &"an empty compound"));
+        }
+        self.parser.advance()?;
+
+        visitor.visit_unit()
     }
 
-    fn deserialize_unit_struct<V>(self, _name: &'static str, _visitor: V) -> Result<V::Value>
+    fn deserialize_unit_struct<V>(self, _name: &'static str, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        todo!()
+        #[cfg(debug_assertions)]
+        trace!("Deserializer::deserialize_unit_struct");
+
+        self.deserialize_unit(visitor)
     }
 
-    fn deserialize_newtype_struct<V>(self, _name: &'static str, _visitor: V) -> Result<V::Value>
+    fn deserialize_newtype_struct<V>(self, name: &'static str, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        todo!()
+        #[cfg(debug_assertions)]
+        trace!("Deserializer::deserialize_newtype_struct({:?})", name);
+
+        // the array wrapper types only accept their own array type, other
+        // newtype structs are the same as the value they wrap
+        let array_type = match name {
+            array::I8_ARRAY_NAME => nbt::TAG_I8_ARRAY,
+            array::I32_ARRAY_NAME => nbt::TAG_I32_ARRAY,
+            array::I64_ARRAY_NAME => nbt::TAG_I64_ARRAY,
+            value::VALUE_NAME => return self.deserialize_value(visitor),
+            _ => return visitor.visit_newtype_struct(self),
+        };
+
+        if self.parser.get_tag_type() != array_type {
+            return Err(Error::InvalidTagTypeError);
+        }
+
+        visitor.visit_newtype_struct(self)
     }
 
     fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value>
@@ -333,11 +684,41 @@ where
         #[cfg(debug_assertions)]
         trace!("Deserializer::deserialize_seq");
 
-        self.parser.next()?;
-
-        // FIXME: assert that the parser is reading a sequence type
+        if self.parser.get_value_type() != ValueType::SeqBegin {
+            return Err(Error::InvalidTagTypeError);
+        }
 
-        visitor.visit_seq(self)
+        // arrays are read all at once instead of element by element
+        match self.parser.get_tag_type() {
+            nbt::TAG_I8_ARRAY => {
+                let value = self.parser.read_i8_array()?;
+                self.visit_array(value, visitor)
+            },
+            nbt::TAG_I32_ARRAY => {
+                let value = self.parser.read_i32_array()?;
+                self.visit_array(value, visitor)
+            },
+            nbt::TAG_I64_ARRAY => {
+                let value = self.parser.read_i64_array()?;
+                self.visit_array(value, visitor)
+            },
+            _ => {
+                self.parser.advance()?;
+                let mut access = ListAccess { de: self, finished: false };
+                let value = visitor.visit_seq(&mut access)?;
+
+                // tuples stop after their last element without asking for more
+                if !access.finished {
+                    if access.de.parser.get_value_type() != ValueType::SeqEnd {
+                        let len = access.de.parser.remaining_len().unwrap_or(0);
+                        return Err(de::Error::invalid_length(len, &"fewer elements in list"));
+                    }
+                    access.de.parser.advance()?;
+                }
+
+                Ok(value)
+            },
+        }
     }
 
     fn deserialize_tuple<V>(self, _len: usize, visitor: V) -> Result<V::Value>
@@ -354,12 +735,15 @@ where
         self,
         _name: &'static str,
         _len: usize,
-        _visitor: V,
+        visitor: V,
     ) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        todo!()
+        #[cfg(debug_assertions)]
+        trace!("Deserializer::deserialize_tuple_struct");
+
+        self.deserialize_seq(visitor)
     }
 
     fn deserialize_map<V>(self, visitor: V) -> Result<V::Value>
@@ -369,9 +753,11 @@ where
         #[cfg(debug_assertions)]
         trace!("Deserializer::deserialize_map");
 
-        self.parser.next()?;
+        if self.parser.get_value_type() != ValueType::MapBegin {
+            return Err(Error::InvalidTagTypeError);
+        }
 
-        // FIXME: assert that the parser is reading a map type
+        self.parser.advance()?;
 
         visitor.visit_map(self)
     }
@@ -379,7 +765,7 @@ where
     fn deserialize_struct<V>(
         self,
         _name: &'static str,
-        _fields: &'static [&'static str],
+        fields: &'static [&'static str],
         visitor: V,
     ) -> Result<V::Value>
     where
@@ -388,19 +774,63 @@ where
         #[cfg(debug_assertions)]
         trace!("Deserializer::deserialize_struct");
 
-        self.deserialize_map(visitor)
+        if !self.config.deny_unknown_fields {
+            return self.deserialize_map(visitor);
+        }
+
+        if self.parser.get_value_type() != ValueType::MapBegin {
+            return Err(Error::InvalidTagTypeError);
+        }
+
+        self.parser.advance()?;
+
+        visitor.visit_map(KnownFields { de: self, fields })
     }
 
     fn deserialize_enum<V>(
         self,
         _name: &'static str,
         _variants: &'static [&'static str],
-        _visitor: V,
+        visitor: V,
     ) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        todo!()
+        #[cfg(debug_assertions)]
+        trace!("Deserializer::deserialize_enum");
+
+        match self.parser.get_value_type() {
+            // unit variants by name
+            ValueType::String => match self.parser.get_str_value()? {
+                Reference::Borrowed(value) => {
+                    self.parser.advance()?;
+                    visitor.visit_enum(de::value::BorrowedStrDeserializer::new(value))
+                },
+                Reference::Copied(value) => {
+                    let value = value.to_string();
+                    self.parser.advance()?;
+                    visitor.visit_enum(value.into_deserializer())
+                },
+            },
+            // unit variants by index
+            ValueType::I8 | ValueType::I16 | ValueType::I32 | ValueType::I64 => {
+                let value = self.get_number_value()?.as_i64();
+                let index = u32::try_from(value).map_err(|_| {
+                    <Error as de::Error>::invalid_value(de::Unexpected::Signed(value), &"a variant index")
+                })?;
+                self.parser.advance()?;
+                visitor.visit_enum(index.into_deserializer())
+            },
+            // other variants as a compound with the variant as its only tag
+            ValueType::MapBegin => {
+                self.parser.advance()?;
+                if self.parser.get_value_type() == ValueType::MapEnd {
+                    return Err(Error::Message("enum variants can not be an empty compound".to_string()));
+                }
+                visitor.visit_enum(CompoundVariant { de: self })
+            },
+            _ => Err(Error::InvalidTagTypeError),
+        }
     }
 
     fn deserialize_identifier<V>(self, visitor: V) -> Result<V::Value>
@@ -410,7 +840,7 @@ where
         #[cfg(debug_assertions)]
         trace!("Deserializer::deserialize_identifier");
 
-        self.deserialize_string(visitor)
+        self.deserialize_str(visitor)
     }
 
     fn deserialize_ignored_any<V>(self, visitor: V) -> Result<V::Value>
@@ -420,13 +850,24 @@ where
         #[cfg(debug_assertions)]
         trace!("Deserializer::deserialize_ignored_any");
 
-        self.deserialize_any(visitor)
+        // skip nested values without visiting any of their contents
+        self.parser.skip_value()?;
+        self.parser.advance()?;
+
+        visitor.visit_unit()
     }
 }
 
-impl<'de, 'a, R> SeqAccess<'de> for &'a mut Deserializer<R>
+/// accesses the elements of a `TAG_LIST`
+struct ListAccess<'a, 'de, R> {
+    de: &'a mut Deserializer<'de, R>,
+    // set once the end of the list has been read
+    finished: bool,
+}
+
+impl<'a, 'de, R> SeqAccess<'de> for ListAccess<'a, 'de, R>
 where
-    R: std::io::Read,
+    R: Input<'de>,
 {
     type Error = Error;
 
@@ -437,22 +878,33 @@ where
         #[cfg(debug_assertions)]
         trace!("SeqAccess::next_element_seed");
 
-        if let ValueType::SeqEnd = self.parser.get_value_type() {
-            self.parser.next()?;
+        if self.finished {
             return Ok(None);
         }
 
-        seed.deserialize(&mut **self).map(Some)
+        if let ValueType::SeqEnd = self.de.parser.get_value_type() {
+            self.finished = true;
+            self.de.parser.advance()?;
+            return Ok(None);
+        }
+
+        seed.deserialize(&mut *self.de).map(Some)
     }
 
-    // fn size_hint(&self) -> Option<usize> {
-    //     self.parser.get_size_hint()
-    // }
+    fn size_hint(&self) -> Option<usize> {
+        if self.finished {
+            return Some(0);
+        }
+
+        // the length is read from the input, capped so that a bogus length
+        // can not make the visitor allocate a huge amount of memory up front
+        self.de.parser.remaining_len().map(|len| len.min(SIZE_HINT_MAX))
+    }
 }
 
-impl<'de, 'a, R> MapAccess<'de> for &'a mut Deserializer<R>
+impl<'de, R> MapAccess<'de> for &mut Deserializer<'de, R>
 where
-    R: std::io::Read,
+    R: Input<'de>,
 {
     type Error = Error;
 
@@ -463,16 +915,10 @@ where
         #[cfg(debug_assertions)]
         trace!("MapAccess::next_key_seed");
 
+        // after the root compound the parser stops between root tags, so
+        // running out of input here means that the input was truncated
         if let ValueType::MapEnd = self.parser.get_value_type() {
-            match self.parser.next() {
-                Ok(()) => {},
-                Err(Error::Eof) => {
-                    return Ok(None);
-                },
-                Err(e) => {
-                    return Err(From::from(e));
-                },
-            }
+            self.parser.advance()?;
             return Ok(None);
         }
 
@@ -489,3 +935,1415 @@ where
         seed.deserialize(&mut **self)
     }
 }
+
+
+/// accesses the tags of a compound which is read into a struct, tags that
+/// are not one of the struct's fields are an error
+struct KnownFields<'a, 'de, R> {
+    de: &'a mut Deserializer<'de, R>,
+    fields: &'static [&'static str],
+}
+
+impl<'a, 'de, R> MapAccess<'de> for KnownFields<'a, 'de, R>
+where
+    R: Input<'de>,
+{
+    type Error = Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        #[cfg(debug_assertions)]
+        trace!("KnownFields::next_key_seed");
+
+        if self.de.parser.get_value_type() == ValueType::String {
+            let name = self.de.parser.get_str_value()?;
+            if !self.fields.contains(&&*name) {
+                return Err(de::Error::unknown_field(&name, self.fields));
+            }
+        }
+
+        let mut de = &mut *self.de;
+        de.next_key_seed(seed)
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        #[cfg(debug_assertions)]
+        trace!("KnownFields::next_value_seed");
+
+        let mut de = &mut *self.de;
+        de.next_value_seed(seed)
+    }
+}
+
+/// accesses an enum variant that is written as a compound with a single tag,
+/// the tag's name is the variant and its value the variant's contents
+struct CompoundVariant<'a, 'de, R> {
+    de: &'a mut Deserializer<'de, R>,
+}
+
+impl<'de, R> CompoundVariant<'_, 'de, R>
+where
+    R: Input<'de>,
+{
+    /// checks that the variant was the compound's only tag and leaves it
+    fn end(self) -> Result<()> {
+        #[cfg(debug_assertions)]
+        trace!("CompoundVariant::end");
+
+        if self.de.parser.get_value_type() != ValueType::MapEnd {
+            return Err(Error::Message("enum variants must be a compound with a single tag".to_string()));
+        }
+        self.de.parser.advance()
+    }
+}
+
+impl<'a, 'de, R> de::EnumAccess<'de> for CompoundVariant<'a, 'de, R>
+where
+    R: Input<'de>,
+{
+    type Error = Error;
+    type Variant = Self;
+
+    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, Self)>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        #[cfg(debug_assertions)]
+        trace!("CompoundVariant::variant_seed");
+
+        // the tag's name
+        let variant = seed.deserialize(&mut *self.de)?;
+        Ok((variant, self))
+    }
+}
+
+impl<'a, 'de, R> de::VariantAccess<'de> for CompoundVariant<'a, 'de, R>
+where
+    R: Input<'de>,
+{
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<()> {
+        #[cfg(debug_assertions)]
+        trace!("CompoundVariant::unit_variant");
+
+        de::IgnoredAny::deserialize(&mut *self.de)?;
+        self.end()
+    }
+
+    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        #[cfg(debug_assertions)]
+        trace!("CompoundVariant::newtype_variant_seed");
+
+        let value = seed.deserialize(&mut *self.de)?;
+        self.end()?;
+        Ok(value)
+    }
+
+    fn tuple_variant<V>(self, _len: usize, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        #[cfg(debug_assertions)]
+        trace!("CompoundVariant::tuple_variant");
+
+        let value = de::Deserializer::deserialize_seq(&mut *self.de, visitor)?;
+        self.end()?;
+        Ok(value)
+    }
+
+    fn struct_variant<V>(self, fields: &'static [&'static str], visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        #[cfg(debug_assertions)]
+        trace!("CompoundVariant::struct_variant");
+
+        let value = de::Deserializer::deserialize_struct(&mut *self.de, "", fields, visitor)?;
+        self.end()?;
+        Ok(value)
+    }
+}
+
+/// a map with a single entry, from the name of an array wrapper type to the
+/// array's elements
+struct ArrayEntry<'a, 'de, R> {
+    de: &'a mut Deserializer<'de, R>,
+    name: Option<&'static str>,
+}
+
+impl<'a, 'de, R> MapAccess<'de> for ArrayEntry<'a, 'de, R>
+where
+    R: Input<'de>,
+{
+    type Error = Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        match self.name.take() {
+            Some(name) => seed.deserialize(de::value::BorrowedStrDeserializer::new(name)).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        seed.deserialize(&mut *self.de)
+    }
+}
+
+/// element types of NBT's array types
+trait ArrayElement: Copy {
+    const TAG_TYPE: u8;
+
+    fn to_number(self) -> Number;
+
+    fn visit<'de, V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>;
+}
+
+impl ArrayElement for i8 {
+    const TAG_TYPE: u8 = nbt::TAG_I8;
+
+    fn to_number(self) -> Number {
+        Number::I8(self)
+    }
+
+    fn visit<'de, V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_i8(self)
+    }
+}
+
+impl ArrayElement for i32 {
+    const TAG_TYPE: u8 = nbt::TAG_I32;
+
+    fn to_number(self) -> Number {
+        Number::I32(self)
+    }
+
+    fn visit<'de, V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_i32(self)
+    }
+}
+
+impl ArrayElement for i64 {
+    const TAG_TYPE: u8 = nbt::TAG_I64;
+
+    fn to_number(self) -> Number {
+        Number::I64(self)
+    }
+
+    fn visit<'de, V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_i64(self)
+    }
+}
+
+/// gives access to the elements of an array which was read all at once
+struct ArrayAccess<T> {
+    iter: std::vec::IntoIter<T>,
+    unsigned_mode: UnsignedMode,
+    lenient_numbers: bool,
+}
+
+impl<'de, T> SeqAccess<'de> for ArrayAccess<T>
+where
+    T: ArrayElement,
+{
+    type Error = Error;
+
+    fn next_element_seed<S>(&mut self, seed: S) -> Result<Option<S::Value>>
+    where
+        S: DeserializeSeed<'de>,
+    {
+        match self.iter.next() {
+            Some(value) => {
+                let unsigned_mode = self.unsigned_mode;
+                let lenient_numbers = self.lenient_numbers;
+                seed.deserialize(ArrayElementDeserializer { value, unsigned_mode, lenient_numbers }).map(Some)
+            },
+            None => Ok(None),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.iter.len())
+    }
+}
+
+/// deserializes a single element of an array, with the same type checks as
+/// the elements of a `TAG_LIST`
+struct ArrayElementDeserializer<T> {
+    value: T,
+    unsigned_mode: UnsignedMode,
+    lenient_numbers: bool,
+}
+
+impl<T> ArrayElementDeserializer<T>
+where
+    T: ArrayElement,
+{
+    /// returns the element's value if it is of type `tag_type` or numbers
+    /// are read leniently
+    fn value_of_tag(&self, tag_type: u8) -> Result<Number> {
+        if tag_type != T::TAG_TYPE && !self.lenient_numbers {
+            return Err(Error::InvalidTagTypeError);
+        }
+
+        Ok(self.value.to_number())
+    }
+
+    /// returns the element's value if numbers are read leniently
+    fn lenient_value(&self) -> Result<Number> {
+        if !self.lenient_numbers {
+            return Err(Error::InvalidTagTypeError);
+        }
+
+        Ok(self.value.to_number())
+    }
+}
+
+impl<'de, T> de::Deserializer<'de> for ArrayElementDeserializer<T>
+where
+    T: ArrayElement,
+{
+    type Error = Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.value.visit(visitor)
+    }
+
+    fn deserialize_bool<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_bool(self.lenient_value()?.as_bool())
+    }
+
+    fn deserialize_i8<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_i8(self.value_of_tag(nbt::TAG_I8)?.as_i8())
+    }
+
+    fn deserialize_i16<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_i16(self.value_of_tag(nbt::TAG_I16)?.as_i16())
+    }
+
+    fn deserialize_i32<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_i32(self.value_of_tag(nbt::TAG_I32)?.as_i32())
+    }
+
+    fn deserialize_i64<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_i64(self.value_of_tag(nbt::TAG_I64)?.as_i64())
+    }
+
+    fn deserialize_u8<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        let value = self.value_of_tag(nbt::TAG_I8)?.as_i8();
+        visitor.visit_u8(value as u8)
+    }
+
+    fn deserialize_u16<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        let value = self.value_of_tag(nbt::TAG_I16)?.as_i16();
+        visitor.visit_u16(value as u16)
+    }
+
+    fn deserialize_u32<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        let value = self.value_of_tag(nbt::TAG_I32)?.as_i32();
+        visitor.visit_u32(self.unsigned_mode.u32_from_i32(value)?)
+    }
+
+    fn deserialize_u64<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        let value = self.value_of_tag(nbt::TAG_I64)?.as_i64();
+        visitor.visit_u64(self.unsigned_mode.u64_from_i64(value)?)
+    }
+
+    fn deserialize_f32<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_f32(self.lenient_value()?.as_f32())
+    }
+
+    fn deserialize_f64<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_f64(self.lenient_value()?.as_f64())
+    }
+
+    serde::forward_to_deserialize_any! {
+        i128 u128 char str string bytes byte_buf option unit unit_struct
+        newtype_struct seq tuple tuple_struct map struct enum identifier
+        ignored_any
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use serde::Deserialize;
+    use crate::{to_writer, ByteOrder, RootMode, StringEncoding};
+    use super::*;
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Item {
+        id: String,
+        count: i8,
+    }
+
+    #[test]
+    fn test_list_of_lists() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Test {
+            v: Vec<Vec<i32>>,
+            after: i32,
+        }
+
+        // `{"v": [[1, 2], [], [3]], "after": 4}`
+        let buffer = b"\x0a\x00\x00\
+            \x09\x00\x01v\x09\x00\x00\x00\x03\
+                \x03\x00\x00\x00\x02\x00\x00\x00\x01\x00\x00\x00\x02\
+                \x00\x00\x00\x00\x00\
+                \x03\x00\x00\x00\x01\x00\x00\x00\x03\
+            \x03\x00\x05after\x00\x00\x00\x04\
+            \x00";
+
+        let value: Test = from_reader(&buffer[..]).unwrap();
+        assert_eq!(value, Test { v: vec![vec![1, 2], vec![], vec![3]], after: 4 });
+    }
+
+    #[test]
+    fn test_list_of_compounds() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Test {
+            #[serde(rename = "Inventory")]
+            inventory: Vec<Item>,
+            after: i32,
+        }
+
+        // `{"Inventory": [{"id": "a", "count": 1b}, {"id": "b", "count": 2b}], "after": 4}`
+        let buffer = b"\x0a\x00\x00\
+            \x09\x00\x09Inventory\x0a\x00\x00\x00\x02\
+                \x08\x00\x02id\x00\x01a\x01\x00\x05count\x01\x00\
+                \x08\x00\x02id\x00\x01b\x01\x00\x05count\x02\x00\
+            \x03\x00\x05after\x00\x00\x00\x04\
+            \x00";
+
+        let value: Test = from_reader(&buffer[..]).unwrap();
+        assert_eq!(value, Test {
+            inventory: vec![
+                Item { id: "a".to_string(), count: 1 },
+                Item { id: "b".to_string(), count: 2 },
+            ],
+            after: 4,
+        });
+    }
+
+    #[test]
+    fn test_list_of_i8_arrays() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Test {
+            v: Vec<Vec<i8>>,
+            after: i32,
+        }
+
+        // `{"v": [[B; 1b, 2b], [B;]], "after": 4}`
+        let buffer = b"\x0a\x00\x00\
+            \x09\x00\x01v\x07\x00\x00\x00\x02\
+                \x00\x00\x00\x02\x01\x02\
+                \x00\x00\x00\x00\
+            \x03\x00\x05after\x00\x00\x00\x04\
+            \x00";
+
+        let value: Test = from_reader(&buffer[..]).unwrap();
+        assert_eq!(value, Test { v: vec![vec![1, 2], vec![]], after: 4 });
+    }
+
+    #[test]
+    fn test_list_of_i32_arrays() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Test {
+            v: Vec<Vec<i32>>,
+            after: i32,
+        }
+
+        // `{"v": [[I; 1, 2], [I;]], "after": 4}`
+        let buffer = b"\x0a\x00\x00\
+            \x09\x00\x01v\x0b\x00\x00\x00\x02\
+                \x00\x00\x00\x02\x00\x00\x00\x01\x00\x00\x00\x02\
+                \x00\x00\x00\x00\
+            \x03\x00\x05after\x00\x00\x00\x04\
+            \x00";
+
+        let value: Test = from_reader(&buffer[..]).unwrap();
+        assert_eq!(value, Test { v: vec![vec![1, 2], vec![]], after: 4 });
+    }
+
+    #[test]
+    fn test_list_of_i64_arrays() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Test {
+            v: Vec<Vec<i64>>,
+            after: i32,
+        }
+
+        // `{"v": [[L; 1L], [L;], [L; 2L, 3L]], "after": 4}`
+        let buffer = b"\x0a\x00\x00\
+            \x09\x00\x01v\x0c\x00\x00\x00\x03\
+                \x00\x00\x00\x01\x00\x00\x00\x00\x00\x00\x00\x01\
+                \x00\x00\x00\x00\
+                \x00\x00\x00\x02\x00\x00\x00\x00\x00\x00\x00\x02\x00\x00\x00\x00\x00\x00\x00\x03\
+            \x03\x00\x05after\x00\x00\x00\x04\
+            \x00";
+
+        let value: Test = from_reader(&buffer[..]).unwrap();
+        assert_eq!(value, Test { v: vec![vec![1], vec![], vec![2, 3]], after: 4 });
+    }
+
+    #[test]
+    fn test_list_of_lists_of_compounds() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Test {
+            v: Vec<Vec<Item>>,
+            after: i32,
+        }
+
+        // `{"v": [[{"id": "a", "count": 1b}], []], "after": 4}`
+        let buffer = b"\x0a\x00\x00\
+            \x09\x00\x01v\x09\x00\x00\x00\x02\
+                \x0a\x00\x00\x00\x01\
+                    \x08\x00\x02id\x00\x01a\x01\x00\x05count\x01\x00\
+                \x00\x00\x00\x00\x00\
+            \x03\x00\x05after\x00\x00\x00\x04\
+            \x00";
+
+        let value: Test = from_reader(&buffer[..]).unwrap();
+        assert_eq!(value, Test {
+            v: vec![vec![Item { id: "a".to_string(), count: 1 }], vec![]],
+            after: 4,
+        });
+    }
+
+    #[test]
+    fn test_ignored_nested_lists() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Test {
+            after: i32,
+        }
+
+        // `{"ignored": [[[B; 1b]], [{"x": [I; 2]}]], "after": 4}`
+        let buffer = b"\x0a\x00\x00\
+            \x09\x00\x07ignored\x09\x00\x00\x00\x02\
+                \x07\x00\x00\x00\x01\x00\x00\x00\x01\x01\
+                \x0a\x00\x00\x00\x01\
+                    \x0b\x00\x01x\x00\x00\x00\x01\x00\x00\x00\x02\x00\
+            \x03\x00\x05after\x00\x00\x00\x04\
+            \x00";
+
+        let value: Test = from_reader(&buffer[..]).unwrap();
+        assert_eq!(value, Test { after: 4 });
+    }
+
+    #[test]
+    fn test_array_wrappers() {
+        use crate::{ByteArray, IntArray, LongArray};
+
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Test {
+            b: ByteArray,
+            i: IntArray,
+            l: LongArray,
+            #[serde(rename = "UUID", with = "crate::int_array")]
+            uuid: [i32; 4],
+        }
+
+        // `{"b": [B; 1b, 2b], "i": [I; 3], "l": [L;], "UUID": [I; 1, 2, 3, 4]}`
+        let buffer = b"\x0a\x00\x00\
+            \x07\x00\x01b\x00\x00\x00\x02\x01\x02\
+            \x0b\x00\x01i\x00\x00\x00\x01\x00\x00\x00\x03\
+            \x0c\x00\x01l\x00\x00\x00\x00\
+            \x0b\x00\x04UUID\x00\x00\x00\x04\x00\x00\x00\x01\x00\x00\x00\x02\x00\x00\x00\x03\x00\x00\x00\x04\
+            \x00";
+
+        let value: Test = from_reader(&buffer[..]).unwrap();
+        assert_eq!(value, Test {
+            b: ByteArray(vec![1, 2]),
+            i: IntArray(vec![3]),
+            l: LongArray(vec![]),
+            uuid: [1, 2, 3, 4],
+        });
+    }
+
+    #[test]
+    fn test_array_wrappers_reject_lists() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Test {
+            #[serde(with = "crate::int_array")]
+            i: Vec<i32>,
+        }
+
+        // `{"i": [3]}`, a TAG_LIST instead of a TAG_I32_ARRAY
+        let buffer = b"\x0a\x00\x00\
+            \x09\x00\x01i\x03\x00\x00\x00\x01\x00\x00\x00\x03\
+            \x00";
+
+        let value: Result<Test> = from_reader(&buffer[..]);
+        assert!(value.is_err());
+
+        // `{"i": [L; 3L]}`, the wrong array type
+        let buffer = b"\x0a\x00\x00\
+            \x0c\x00\x01i\x00\x00\x00\x01\x00\x00\x00\x00\x00\x00\x00\x03\
+            \x00";
+
+        let value: Result<Test> = from_reader(&buffer[..]);
+        assert!(value.is_err());
+    }
+
+    #[test]
+    fn test_array_length_mismatch() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Test {
+            #[serde(with = "crate::int_array")]
+            uuid: [i32; 4],
+        }
+
+        // `{"uuid": [I; 3]}`
+        let buffer = b"\x0a\x00\x00\
+            \x0b\x00\x04uuid\x00\x00\x00\x01\x00\x00\x00\x03\
+            \x00";
+
+        let value: Result<Test> = from_reader(&buffer[..]);
+        assert!(value.is_err());
+    }
+
+    #[test]
+    fn test_from_slice_borrows() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Test<'a> {
+            id: &'a str,
+            bytes: &'a [u8],
+            names: Vec<&'a str>,
+        }
+
+        // `{"id": "minecraft:stone", "bytes": [B; 1b, 2b], "names": ["a", "b"]}`
+        let buffer = b"\x0a\x00\x00\
+            \x08\x00\x02id\x00\x0fminecraft:stone\
+            \x07\x00\x05bytes\x00\x00\x00\x02\x01\x02\
+            \x09\x00\x05names\x08\x00\x00\x00\x02\x00\x01a\x00\x01b\
+            \x00";
+
+        let value: Test = from_slice(&buffer[..]).unwrap();
+        assert_eq!(value, Test { id: "minecraft:stone", bytes: &[1, 2], names: vec!["a", "b"] });
+
+        // the strings point into the input
+        let range = buffer.as_ptr_range();
+        assert!(range.contains(&value.id.as_ptr()));
+        assert!(range.contains(&value.bytes.as_ptr()));
+    }
+
+    #[test]
+    fn test_from_reader_copies() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Test {
+            id: String,
+            #[serde(with = "serde_bytes_buf")]
+            bytes: Vec<u8>,
+        }
+
+        // reads a `Vec<u8>` through `deserialize_byte_buf`
+        mod serde_bytes_buf {
+            use serde::de::{Deserializer, Visitor};
+
+            pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+                struct BytesVisitor;
+
+                impl<'de> Visitor<'de> for BytesVisitor {
+                    type Value = Vec<u8>;
+
+                    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                        formatter.write_str("bytes")
+                    }
+
+                    fn visit_byte_buf<E>(self, v: Vec<u8>) -> Result<Vec<u8>, E> {
+                        Ok(v)
+                    }
+                }
+
+                deserializer.deserialize_byte_buf(BytesVisitor)
+            }
+        }
+
+        // `{"id": "minecraft:stone", "bytes": [B; 1b, 2b]}`
+        let buffer = b"\x0a\x00\x00\
+            \x08\x00\x02id\x00\x0fminecraft:stone\
+            \x07\x00\x05bytes\x00\x00\x00\x02\x01\x02\
+            \x00";
+
+        let value: Test = from_reader(&buffer[..]).unwrap();
+        assert_eq!(value, Test { id: "minecraft:stone".to_string(), bytes: vec![1, 2] });
+    }
+
+    #[test]
+    fn test_bulk_arrays() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Test {
+            heightmap: Vec<i64>,
+            uuid: [i32; 2],
+            bytes: Vec<i8>,
+            after: i32,
+        }
+
+        // `{"heightmap": [L; 1L, 2L], "uuid": [I; 3, 4], "bytes": [B; 5b], "after": 6}`
+        let buffer = b"\x0a\x00\x00\
+            \x0c\x00\x09heightmap\x00\x00\x00\x02\x00\x00\x00\x00\x00\x00\x00\x01\x00\x00\x00\x00\x00\x00\x00\x02\
+            \x0b\x00\x04uuid\x00\x00\x00\x02\x00\x00\x00\x03\x00\x00\x00\x04\
+            \x07\x00\x05bytes\x00\x00\x00\x01\x05\
+            \x03\x00\x05after\x00\x00\x00\x06\
+            \x00";
+
+        let value: Test = from_reader(&buffer[..]).unwrap();
+        assert_eq!(value, Test { heightmap: vec![1, 2], uuid: [3, 4], bytes: vec![5], after: 6 });
+    }
+
+    #[test]
+    fn test_bulk_array_checks() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Ints {
+            v: Vec<i32>,
+        }
+
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Pair {
+            v: [i64; 2],
+        }
+
+        // `{"v": [L; 1L, 2L, 3L]}`
+        let buffer = b"\x0a\x00\x00\
+            \x0c\x00\x01v\x00\x00\x00\x03\
+                \x00\x00\x00\x00\x00\x00\x00\x01\x00\x00\x00\x00\x00\x00\x00\x02\x00\x00\x00\x00\x00\x00\x00\x03\
+            \x00";
+
+        // the elements' type has to match
+        let value: Result<Ints> = from_reader(&buffer[..]);
+        assert!(value.is_err());
+
+        // and all elements have to be used
+        let value: Result<Pair> = from_reader(&buffer[..]);
+        assert!(value.is_err());
+    }
+
+    #[test]
+    fn test_depth_limit() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Inner {}
+
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Test {
+            inner: Inner,
+        }
+
+        // `{"inner": {}}`
+        let buffer = b"\x0a\x00\x00\x0a\x00\x05inner\x00\x00";
+
+        let config = DeserializerConfig::new().max_depth(2);
+        assert_eq!(config.from_slice::<Test>(&buffer[..]).unwrap(), Test { inner: Inner {} });
+
+        let config = DeserializerConfig::new().max_depth(1);
+        assert!(matches!(config.from_slice::<Test>(&buffer[..]), Err(Error::DepthLimitError)));
+    }
+
+    #[test]
+    fn test_size_quota() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Test {
+            s: String,
+        }
+
+        // `{"s": "hello"}`
+        let buffer = b"\x0a\x00\x00\x08\x00\x01s\x00\x05hello\x00";
+
+        let config = DeserializerConfig::new().size_quota(6);
+        assert_eq!(config.from_reader::<_, Test>(&buffer[..]).unwrap(), Test { s: "hello".to_string() });
+
+        let config = DeserializerConfig::new().size_quota(5);
+        assert!(matches!(config.from_reader::<_, Test>(&buffer[..]), Err(Error::SizeLimitError)));
+    }
+
+    #[test]
+    fn test_ignored_fields_are_skipped() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Test {
+            b: i32,
+        }
+
+        // `{"a": {"x": [[1b], []], "y": [I; 2]}, "b": 3, "c": [{"z": "hi"}]}`
+        let buffer = b"\x0a\x00\x00\
+            \x0a\x00\x01a\
+                \x09\x00\x01x\x09\x00\x00\x00\x02\x01\x00\x00\x00\x01\x01\x00\x00\x00\x00\x00\
+                \x0b\x00\x01y\x00\x00\x00\x01\x00\x00\x00\x02\
+            \x00\
+            \x03\x00\x01b\x00\x00\x00\x03\
+            \x09\x00\x01c\x0a\x00\x00\x00\x01\x08\x00\x01z\x00\x02hi\x00\
+            \x00";
+
+        let value: Test = from_slice(&buffer[..]).unwrap();
+        assert_eq!(value, Test { b: 3 });
+
+        let value: Test = from_reader(&buffer[..]).unwrap();
+        assert_eq!(value, Test { b: 3 });
+    }
+
+    /// returns the size hint of a sequence and ignores its elements
+    struct SizeHint;
+
+    impl<'de> Visitor<'de> for SizeHint {
+        type Value = Option<usize>;
+
+        fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+            formatter.write_str("a sequence")
+        }
+
+        fn visit_seq<A>(self, mut seq: A) -> std::result::Result<Self::Value, A::Error>
+        where
+            A: SeqAccess<'de>,
+        {
+            let hint = seq.size_hint();
+            while seq.next_element::<de::IgnoredAny>()?.is_some() {}
+            Ok(hint)
+        }
+    }
+
+    #[test]
+    fn test_size_hint() {
+        #[derive(Debug, Deserialize)]
+        struct Test {
+            #[serde(deserialize_with = "size_hint")]
+            a: Option<usize>,
+            #[serde(deserialize_with = "size_hint")]
+            b: Option<usize>,
+            #[serde(deserialize_with = "size_hint")]
+            c: Option<usize>,
+        }
+
+        fn size_hint<'de, D>(deserializer: D) -> std::result::Result<Option<usize>, D::Error>
+        where
+            D: de::Deserializer<'de>,
+        {
+            deserializer.deserialize_seq(SizeHint)
+        }
+
+        // `{"a": [1s, 2s, 3s], "b": [{}, {}], "c": [...]}` with 5000 bytes in `c`
+        let mut buffer = b"\x0a\x00\x00\
+            \x09\x00\x01a\x02\x00\x00\x00\x03\x00\x01\x00\x02\x00\x03\
+            \x09\x00\x01b\x0a\x00\x00\x00\x02\x00\x00\
+            \x09\x00\x01c\x01\x00\x00\x13\x88".to_vec();
+        buffer.extend_from_slice(&[0; 5000]);
+        buffer.push(0);
+
+        let value: Test = from_slice(&buffer).unwrap();
+        assert_eq!(value.a, Some(3));
+        assert_eq!(value.b, Some(2));
+        assert_eq!(value.c, Some(SIZE_HINT_MAX));
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    enum Facing {
+        #[serde(rename = "minecraft:north")]
+        North,
+        #[serde(rename = "minecraft:east")]
+        East,
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    enum Shape {
+        Point,
+        Circle(f32),
+        Line(i32, i32),
+        Rect { w: i16, h: i16 },
+    }
+
+    #[test]
+    fn test_unit_variants() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Test {
+            a: Facing,
+            b: Facing,
+            c: Vec<Facing>,
+        }
+
+        // `{"a": "minecraft:east", "b": 0b, "c": [1, 0]}`
+        let buffer = b"\x0a\x00\x00\
+            \x08\x00\x01a\x00\x0eminecraft:east\
+            \x01\x00\x01b\x00\
+            \x09\x00\x01c\x03\x00\x00\x00\x02\x00\x00\x00\x01\x00\x00\x00\x00\
+            \x00";
+
+        let value: Test = from_slice(&buffer[..]).unwrap();
+        assert_eq!(value, Test { a: Facing::East, b: Facing::North, c: vec![Facing::East, Facing::North] });
+
+        let value: Test = from_reader(&buffer[..]).unwrap();
+        assert_eq!(value, Test { a: Facing::East, b: Facing::North, c: vec![Facing::East, Facing::North] });
+
+        // `{"a": "minecraft:west", ...}`
+        let buffer = b"\x0a\x00\x00\x08\x00\x01a\x00\x0eminecraft:west\x00";
+        assert!(from_slice::<Test>(&buffer[..]).is_err());
+    }
+
+    #[test]
+    fn test_compound_variants() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Test {
+            shapes: Vec<Shape>,
+            point: Shape,
+        }
+
+        // `{"shapes": [{"Circle": 1f}, {"Line": [2, 3]}, {"Rect": {"w": 4s, "h": 5s}}], "point": "Point"}`
+        let buffer = b"\x0a\x00\x00\
+            \x09\x00\x06shapes\x0a\x00\x00\x00\x03\
+                \x05\x00\x06Circle\x3f\x80\x00\x00\x00\
+                \x09\x00\x04Line\x03\x00\x00\x00\x02\x00\x00\x00\x02\x00\x00\x00\x03\x00\
+                \x0a\x00\x04Rect\x02\x00\x01w\x00\x04\x02\x00\x01h\x00\x05\x00\x00\
+            \x08\x00\x05point\x00\x05Point\
+            \x00";
+
+        let value: Test = from_slice(&buffer[..]).unwrap();
+        assert_eq!(value, Test {
+            shapes: vec![Shape::Circle(1.0), Shape::Line(2, 3), Shape::Rect { w: 4, h: 5 }],
+            point: Shape::Point,
+        });
+    }
+
+    #[test]
+    fn test_compound_variant_with_extra_tags() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Test {
+            shape: Shape,
+        }
+
+        // `{"shape": {"Circle": 1f, "Point": {}}}`
+        let buffer = b"\x0a\x00\x00\
+            \x0a\x00\x05shape\x05\x00\x06Circle\x3f\x80\x00\x00\x0a\x00\x05Point\x00\x00\
+            \x00";
+        assert!(from_slice::<Test>(&buffer[..]).is_err());
+
+        // `{"shape": {}}`
+        let buffer = b"\x0a\x00\x00\x0a\x00\x05shape\x00\x00";
+        assert!(from_slice::<Test>(&buffer[..]).is_err());
+    }
+
+    #[test]
+    fn test_tuples() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Pair(i32, i32);
+
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Test {
+            a: (i16, i16),
+            b: Pair,
+        }
+
+        // `{"a": [1s, 2s], "b": [3, 4]}`
+        let buffer = b"\x0a\x00\x00\
+            \x09\x00\x01a\x02\x00\x00\x00\x02\x00\x01\x00\x02\
+            \x09\x00\x01b\x03\x00\x00\x00\x02\x00\x00\x00\x03\x00\x00\x00\x04\
+            \x00";
+        let value: Test = from_slice(&buffer[..]).unwrap();
+        assert_eq!(value, Test { a: (1, 2), b: Pair(3, 4) });
+
+        // `{"a": [1s, 2s, 3s], ...}`
+        let buffer = b"\x0a\x00\x00\
+            \x09\x00\x01a\x02\x00\x00\x00\x03\x00\x01\x00\x02\x00\x03\
+            \x09\x00\x01b\x03\x00\x00\x00\x02\x00\x00\x00\x03\x00\x00\x00\x04\
+            \x00";
+        assert!(from_slice::<Test>(&buffer[..]).is_err());
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    #[serde(tag = "id")]
+    enum Entity {
+        #[serde(rename = "minecraft:zombie")]
+        Zombie {
+            #[serde(rename = "Health")]
+            health: f32,
+            #[serde(rename = "UUID", with = "crate::int_array")]
+            uuid: [i32; 4],
+            #[serde(rename = "Pos")]
+            pos: Vec<f64>,
+            #[serde(rename = "HandItems")]
+            hand_items: Vec<Item>,
+            #[serde(rename = "IsBaby", with = "crate::byte_bool")]
+            is_baby: bool,
+        },
+        #[serde(rename = "minecraft:item")]
+        Item {
+            #[serde(rename = "Item")]
+            item: Item,
+            #[serde(rename = "PickupDelay")]
+            pickup_delay: i16,
+        },
+        #[serde(rename = "minecraft:falling_block")]
+        FallingBlock {
+            #[serde(rename = "Time")]
+            time: i32,
+            #[serde(rename = "TileEntityData")]
+            tile_entity_data: crate::LongArray,
+        },
+        #[serde(other)]
+        Unknown,
+    }
+
+    #[test]
+    fn test_internally_tagged_entities() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Chunk {
+            #[serde(rename = "Entities")]
+            entities: Vec<Entity>,
+        }
+
+        // `{"Entities": [
+        //     {"Pos": [1d, 2d, 3d], "id": "minecraft:zombie", "Health": 20f, "IsBaby": 1b,
+        //      "UUID": [I; 1, 2, 3, 4], "HandItems": [{"id": "minecraft:stick", "count": 1b}]},
+        //     {"id": "minecraft:item", "Item": {"id": "minecraft:apple", "count": 3b}, "PickupDelay": 10s},
+        //     {"id": "minecraft:falling_block", "Time": 5, "TileEntityData": [L; 6L], "Motion": [B; 7b]},
+        //     {"id": "minecraft:pig", "Saddle": 1b, "Passengers": [{"id": "minecraft:zombie"}]}
+        // ]}`
+        let buffer = b"\x0a\x00\x00\
+            \x09\x00\x08Entities\x0a\x00\x00\x00\x04\
+                \x09\x00\x03Pos\x06\x00\x00\x00\x03\
+                    \x3f\xf0\x00\x00\x00\x00\x00\x00\x40\x00\x00\x00\x00\x00\x00\x00\x40\x08\x00\x00\x00\x00\x00\x00\
+                \x08\x00\x02id\x00\x10minecraft:zombie\
+                \x05\x00\x06Health\x41\xa0\x00\x00\
+                \x01\x00\x06IsBaby\x01\
+                \x0b\x00\x04UUID\x00\x00\x00\x04\x00\x00\x00\x01\x00\x00\x00\x02\x00\x00\x00\x03\x00\x00\x00\x04\
+                \x09\x00\x09HandItems\x0a\x00\x00\x00\x01\
+                    \x08\x00\x02id\x00\x0fminecraft:stick\x01\x00\x05count\x01\x00\
+                \x00\
+                \x08\x00\x02id\x00\x0eminecraft:item\
+                \x0a\x00\x04Item\x08\x00\x02id\x00\x0fminecraft:apple\x01\x00\x05count\x03\x00\
+                \x02\x00\x0bPickupDelay\x00\x0a\
+                \x00\
+                \x08\x00\x02id\x00\x17minecraft:falling_block\
+                \x03\x00\x04Time\x00\x00\x00\x05\
+                \x0c\x00\x0eTileEntityData\x00\x00\x00\x01\x00\x00\x00\x00\x00\x00\x00\x06\
+                \x07\x00\x06Motion\x00\x00\x00\x01\x07\
+                \x00\
+                \x08\x00\x02id\x00\x0dminecraft:pig\
+                \x01\x00\x06Saddle\x01\
+                \x09\x00\x0aPassengers\x0a\x00\x00\x00\x01\x08\x00\x02id\x00\x10minecraft:zombie\x00\
+                \x00\
+            \x00";
+
+        let expected = Chunk {
+            entities: vec![
+                Entity::Zombie {
+                    health: 20.0,
+                    uuid: [1, 2, 3, 4],
+                    pos: vec![1.0, 2.0, 3.0],
+                    hand_items: vec![Item { id: "minecraft:stick".to_string(), count: 1 }],
+                    is_baby: true,
+                },
+                Entity::Item {
+                    item: Item { id: "minecraft:apple".to_string(), count: 3 },
+                    pickup_delay: 10,
+                },
+                Entity::FallingBlock { time: 5, tile_entity_data: crate::LongArray(vec![6]) },
+                Entity::Unknown,
+            ],
+        };
+
+        let value: Chunk = from_slice(&buffer[..]).unwrap();
+        assert_eq!(value, expected);
+
+        let value: Chunk = from_reader(&buffer[..]).unwrap();
+        assert_eq!(value, expected);
+    }
+
+    #[test]
+    fn test_internally_tagged_root() {
+        // `{"id": "minecraft:item", "Item": {"id": "minecraft:apple", "count": 3b}, "PickupDelay": 10s}`
+        let buffer = b"\x0a\x00\x00\
+            \x08\x00\x02id\x00\x0eminecraft:item\
+            \x0a\x00\x04Item\x08\x00\x02id\x00\x0fminecraft:apple\x01\x00\x05count\x03\x00\
+            \x02\x00\x0bPickupDelay\x00\x0a\
+            \x00";
+
+        let value: Entity = from_slice(&buffer[..]).unwrap();
+        assert_eq!(value, Entity::Item {
+            item: Item { id: "minecraft:apple".to_string(), count: 3 },
+            pickup_delay: 10,
+        });
+
+        // a compound without an `id`
+        let buffer = b"\x0a\x00\x00\x02\x00\x0bPickupDelay\x00\x0a\x00";
+        assert!(from_slice::<Entity>(&buffer[..]).is_err());
+    }
+
+    #[test]
+    fn test_options_units_and_newtypes() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Count(i8);
+
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Marker;
+
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Test {
+            #[serde(rename = "CustomName")]
+            custom_name: Option<String>,
+            tag: Option<Item>,
+            count: Count,
+            marker: Marker,
+            unit: (),
+            counts: Vec<Option<Count>>,
+        }
+
+        // `{"tag": {"id": "a", "count": 1b}, "count": 2b, "marker": {}, "unit": {}, "counts": [3b]}`
+        let buffer = b"\x0a\x00\x00\
+            \x0a\x00\x03tag\x08\x00\x02id\x00\x01a\x01\x00\x05count\x01\x00\
+            \x01\x00\x05count\x02\
+            \x0a\x00\x06marker\x00\
+            \x0a\x00\x04unit\x00\
+            \x09\x00\x06counts\x01\x00\x00\x00\x01\x03\
+            \x00";
+
+        let value: Test = from_slice(&buffer[..]).unwrap();
+        assert_eq!(value, Test {
+            custom_name: None,
+            tag: Some(Item { id: "a".to_string(), count: 1 }),
+            count: Count(2),
+            marker: Marker,
+            unit: (),
+            counts: vec![Some(Count(3))],
+        });
+
+        // `{..., "marker": {"a": 1b}, ...}`
+        let buffer = b"\x0a\x00\x00\
+            \x01\x00\x05count\x02\
+            \x0a\x00\x06marker\x01\x00\x01a\x01\x00\
+            \x0a\x00\x04unit\x00\
+            \x09\x00\x06counts\x00\x00\x00\x00\x00\
+            \x00";
+        assert!(from_slice::<Test>(&buffer[..]).is_err());
+    }
+
+    #[test]
+    fn test_unsigned_and_char() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Test {
+            a: u8,
+            b: u16,
+            c: u32,
+            d: u64,
+            e: char,
+            bytes: Vec<u8>,
+            colors: Vec<u32>,
+        }
+
+        // `{"a": -1b, "b": -1s, "c": -1, "d": 1L, "e": "é", "bytes": [B; -2b], "colors": [I; -16711936]}`
+        let buffer = b"\x0a\x00\x00\
+            \x01\x00\x01a\xff\
+            \x02\x00\x01b\xff\xff\
+            \x03\x00\x01c\xff\xff\xff\xff\
+            \x04\x00\x01d\x00\x00\x00\x00\x00\x00\x00\x01\
+            \x08\x00\x01e\x00\x02\xc3\xa9\
+            \x07\x00\x05bytes\x00\x00\x00\x01\xfe\
+            \x0b\x00\x06colors\x00\x00\x00\x01\xff\x00\xff\x00\
+            \x00";
+
+        let value: Test = from_slice(&buffer[..]).unwrap();
+        assert_eq!(value, Test {
+            a: 255,
+            b: 65535,
+            c: u32::MAX,
+            d: 1,
+            e: 'é',
+            bytes: vec![254],
+            colors: vec![0xff00ff00],
+        });
+
+        // negative values are an error when range checked
+        let config = DeserializerConfig::new().unsigned_mode(UnsignedMode::RangeChecked);
+        assert!(config.from_slice::<Test>(&buffer[..]).is_err());
+
+        // `{"e": "ab"}`
+        let buffer = b"\x0a\x00\x00\x08\x00\x01e\x00\x02ab\x00";
+        assert!(from_slice::<HashMap<String, char>>(&buffer[..]).is_err());
+    }
+
+    #[test]
+    fn test_lenient_numbers() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Test {
+            a: i32,
+            b: i8,
+            c: i64,
+            d: f32,
+            e: bool,
+            f: Vec<i64>,
+            g: Vec<f64>,
+        }
+
+        // `{"a": 300s, "b": 300, "c": -2.5d, "d": 7L, "e": 2.0f, "f": [I; 1, -1], "g": [1b, 2b]}`
+        let buffer = b"\x0a\x00\x00\
+            \x02\x00\x01a\x01\x2c\
+            \x03\x00\x01b\x00\x00\x01\x2c\
+            \x06\x00\x01c\xc0\x04\x00\x00\x00\x00\x00\x00\
+            \x04\x00\x01d\x00\x00\x00\x00\x00\x00\x00\x07\
+            \x05\x00\x01e\x40\x00\x00\x00\
+            \x0b\x00\x01f\x00\x00\x00\x02\x00\x00\x00\x01\xff\xff\xff\xff\
+            \x09\x00\x01g\x01\x00\x00\x00\x02\x01\x02\
+            \x00";
+
+        // the tag types have to match by default
+        assert!(from_slice::<Test>(&buffer[..]).is_err());
+
+        let config = DeserializerConfig::new().lenient_numbers(true);
+        let value: Test = config.from_slice(&buffer[..]).unwrap();
+        assert_eq!(value, Test {
+            a: 300,
+            b: 44,
+            c: -3,
+            d: 7.0,
+            e: true,
+            f: vec![1, -1],
+            g: vec![1.0, 2.0],
+        });
+
+        // strings are still no numbers
+        // `{"a": "1"}`
+        let buffer = b"\x0a\x00\x00\x08\x00\x01a\x00\x011\x00";
+        assert!(config.from_slice::<HashMap<String, i32>>(&buffer[..]).is_err());
+    }
+
+    #[test]
+    fn test_config() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Test {
+            s: String,
+            v: Vec<i32>,
+        }
+
+        // `{"s": "a\0", "v": [I; 1]}`, little endian in modified UTF-8 without
+        // the root tag's name
+        let buffer = b"\x0a\
+            \x08\x01\x00s\x03\x00a\xc0\x80\
+            \x0b\x01\x00v\x01\x00\x00\x00\x01\x00\x00\x00\
+            \x00";
+
+        let config = DeserializerConfig::new()
+            .byte_order(ByteOrder::LittleEndian)
+            .string_encoding(StringEncoding::ModifiedUtf8)
+            .root_mode(RootMode::UnnamedCompound);
+        let value: Test = config.from_slice(&buffer[..]).unwrap();
+        assert_eq!(value, Test { s: "a\0".to_string(), v: vec![1] });
+
+        // the root tag's name is expected by default
+        assert!(from_slice::<Test>(&buffer[..]).is_err());
+    }
+
+    #[test]
+    fn test_root_mode() {
+        // `"": 7`, with and without a name
+        let named = b"\x03\x00\x00\x00\x00\x00\x07";
+        let unnamed = b"\x03\x00\x00\x00\x07";
+
+        // only compounds are accepted at the root by default
+        assert!(matches!(from_slice::<i32>(&named[..]), Err(Error::InvalidTagTypeError)));
+
+        let config = DeserializerConfig::new().root_mode(RootMode::UnnamedAny);
+        assert_eq!(config.from_slice::<i32>(&unnamed[..]).unwrap(), 7);
+    }
+
+    #[test]
+    fn test_deny_unknown_fields() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Test {
+            a: i8,
+        }
+
+        // `{"a": 1b, "b": 2b}`
+        let buffer = b"\x0a\x00\x00\x01\x00\x01a\x01\x01\x00\x01b\x02\x00";
+
+        assert_eq!(from_slice::<Test>(&buffer[..]).unwrap(), Test { a: 1 });
+
+        let config = DeserializerConfig::new().deny_unknown_fields(true);
+        let error = config.from_slice::<Test>(&buffer[..]).unwrap_err();
+        assert_eq!(error.to_string(), "unknown field `b`, expected `a`");
+
+        // maps have no fields to check against
+        let value: HashMap<String, i8> = config.from_slice(&buffer[..]).unwrap();
+        assert_eq!(value.len(), 2);
+    }
+
+    #[test]
+    fn test_deny_trailing_data() {
+        // `{}` followed by a stray byte
+        let buffer = b"\x0a\x00\x00\x00\xff";
+
+        let value: HashMap<String, i8> = from_slice(&buffer[..]).unwrap();
+        assert!(value.is_empty());
+
+        let config = DeserializerConfig::new().deny_trailing_data(true);
+        assert!(config.from_slice::<HashMap<String, i8>>(&buffer[..4]).is_ok());
+        assert!(matches!(
+            config.from_slice::<HashMap<String, i8>>(&buffer[..]),
+            Err(Error::TrailingDataError)
+        ));
+        assert!(matches!(
+            config.from_reader::<_, HashMap<String, i8>>(&buffer[..]),
+            Err(Error::TrailingDataError)
+        ));
+    }
+
+    #[test]
+    fn test_root_name() {
+        // `"Level": {"a": 1b}`
+        let buffer = b"\x0a\x00\x05Level\x01\x00\x01a\x01\x00";
+
+        let (name, value): (String, HashMap<String, i8>) = from_slice_named(&buffer[..]).unwrap();
+        assert_eq!(name, "Level");
+        assert_eq!(value["a"], 1);
+
+        let (name, _): (String, HashMap<String, i8>) = from_reader_named(&buffer[..]).unwrap();
+        assert_eq!(name, "Level");
+
+        // `{"a": 1b}` without the root tag's name
+        let buffer = b"\x0a\x01\x00\x01a\x01\x00";
+        let config = DeserializerConfig::new().root_mode(RootMode::UnnamedCompound);
+        let (name, _): (String, HashMap<String, i8>) = config.from_slice_named(&buffer[..]).unwrap();
+        assert_eq!(name, "");
+    }
+
+    #[test]
+    fn test_truncated_compound() {
+        // `{"a": {}` without the root compound's end tag
+        let buffer = b"\x0a\x00\x00\x0a\x00\x01a\x00";
+        assert!(from_slice::<HashMap<String, HashMap<String, i8>>>(&buffer[..]).is_err());
+        assert!(from_reader::<_, HashMap<String, HashMap<String, i8>>>(&buffer[..]).is_err());
+    }
+
+    #[test]
+    fn test_stream() {
+        // `"a": {"b": 1b}`, `"c": {"b": 2b}`
+        let buffer = b"\x0a\x00\x01a\x01\x00\x01b\x01\x00\x0a\x00\x01c\x01\x00\x01b\x02\x00";
+
+        let mut stream = StreamDeserializer::<_, HashMap<String, i8>>::from_slice(&buffer[..]);
+        assert_eq!(stream.next().unwrap().unwrap()["b"], 1);
+        assert_eq!(stream.root_name(), "a");
+        assert_eq!(stream.next().unwrap().unwrap()["b"], 2);
+        assert_eq!(stream.root_name(), "c");
+        assert!(stream.next().is_none());
+
+        let config = DeserializerConfig::new();
+        let values: Vec<HashMap<String, i8>> = config.stream_from_reader(&buffer[..])
+            .collect::<Result<_>>()
+            .unwrap();
+        assert_eq!(values.len(), 2);
+
+        // an empty input holds no root tags
+        assert!(config.stream_from_slice::<HashMap<String, i8>>(b"").next().is_none());
+
+        // a truncated root tag is an error, after which the stream ends
+        let mut stream = config.stream_from_slice::<HashMap<String, i8>>(&buffer[..buffer.len() - 2]);
+        assert!(stream.next().unwrap().is_ok());
+        assert!(stream.next().unwrap().is_err());
+        assert!(stream.next().is_none());
+
+        // `{"b": 3b}` twice without names
+        let buffer = b"\x0a\x01\x00\x01b\x03\x00\x0a\x01\x00\x01b\x03\x00";
+        let config = DeserializerConfig::new().root_mode(RootMode::UnnamedCompound);
+        assert_eq!(config.stream_from_slice::<HashMap<String, i8>>(&buffer[..]).count(), 2);
+    }
+
+    #[test]
+    fn test_heterogeneous_lists() {
+        #[derive(Deserialize)]
+        struct Test {
+            l: Vec<i32>,
+        }
+
+        // `{"l": [{"": 1}, {"": 2}], "m": [{"": 3b}, {"": [I; 4]}]}`
+        let buffer = b"\x0a\x00\x00\
+            \x09\x00\x01l\x0a\x00\x00\x00\x02\
+                \x03\x00\x00\x00\x00\x00\x01\x00\
+                \x03\x00\x00\x00\x00\x00\x02\x00\
+            \x09\x00\x01m\x0a\x00\x00\x00\x02\
+                \x01\x00\x00\x03\x00\
+                \x0b\x00\x00\x00\x00\x00\x01\x00\x00\x00\x04\x00\
+            \x00";
+
+        // the elements are unwrapped, and skipped along with unknown fields
+        let config = DeserializerConfig::new().heterogeneous_lists(true);
+        let value: Test = config.from_slice(&buffer[..]).unwrap();
+        assert_eq!(value.l, vec![1, 2]);
+        assert!(from_slice::<Test>(&buffer[..]).is_err());
+
+        // `{"l": [{"": 1}]}`
+        let buffer = b"\x0a\x00\x00\x09\x00\x01l\x0a\x00\x00\x00\x01\x03\x00\x00\x00\x00\x00\x01\x00\x00";
+        let value: HashMap<String, Vec<HashMap<String, i32>>> = from_slice(&buffer[..]).unwrap();
+        assert_eq!(value["l"][0][""], 1);
+    }
+
+    #[test]
+    fn test_empty_key_round_trip() {
+        #[derive(Debug, Deserialize, serde::Serialize, PartialEq)]
+        struct Test {
+            l: Vec<HashMap<String, i32>>,
+        }
+
+        let mut map = HashMap::new();
+        map.insert(String::new(), 1);
+        let value = Test { l: vec![map.clone(), map] };
+
+        let mut buffer = Vec::new();
+        to_writer(&mut buffer, &value).unwrap();
+        assert_eq!(from_slice::<Test>(&buffer).unwrap(), value);
+    }
+}
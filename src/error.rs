@@ -1,14 +1,26 @@
 use serde::{de, ser};
+use crate::nbt;
 
 pub type Result<T> = std::result::Result<T, Error>;
 
 #[derive(Debug)]
 pub enum Error {
     Eof,
+    DepthLimitError,
+    SizeLimitError,
     InvalidParserStateError,
+    InvalidEmitterStateError,
     InvalidTagTypeError,
+    ModifiedUtf8Error,
+    TrailingDataError,
+    BufferLimitError,
+    ListElementTypeError { list: u8, element: u8 },
+    ListLengthError { expected: usize, actual: usize },
+    MissingListTypeError,
+    SyntaxError { offset: usize, message: String },
     IoError(std::io::Error),
     FromUtf8Error(std::string::FromUtf8Error),
+    Utf8Error(std::str::Utf8Error),
     Message(String),
 }
 
@@ -37,8 +49,33 @@ impl ser::Error for Error {
 }
 
 impl std::fmt::Display for Error {
-    fn fmt(&self, _f: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
-        todo!()
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
+        match self {
+            Error::Eof => f.write_str("unexpected end of input"),
+            Error::DepthLimitError => f.write_str("compounds and lists are nested too deeply"),
+            Error::SizeLimitError => f.write_str("size quota exceeded"),
+            Error::InvalidParserStateError => f.write_str("invalid parser state"),
+            Error::InvalidEmitterStateError => f.write_str("invalid emitter state"),
+            Error::InvalidTagTypeError => f.write_str("invalid tag type"),
+            Error::ModifiedUtf8Error => f.write_str("invalid modified UTF-8"),
+            Error::TrailingDataError => f.write_str("trailing data after the root tag"),
+            Error::BufferLimitError => f.write_str("list of unknown length exceeds the buffer limit"),
+            Error::ListElementTypeError { list, element } => write!(
+                f,
+                "list of {} can not contain a {}",
+                nbt::tag_type_name(*list),
+                nbt::tag_type_name(*element)
+            ),
+            Error::ListLengthError { expected, actual } => {
+                write!(f, "list of length {} has {} elements", expected, actual)
+            },
+            Error::MissingListTypeError => f.write_str("non-empty list has no element type"),
+            Error::SyntaxError { offset, message } => write!(f, "{} at offset {}", message, offset),
+            Error::IoError(e) => e.fmt(f),
+            Error::FromUtf8Error(e) => e.fmt(f),
+            Error::Utf8Error(e) => e.fmt(f),
+            Error::Message(msg) => f.write_str(msg),
+        }
     }
 }
 
@@ -46,6 +83,11 @@ impl std::error::Error for Error {}
 
 impl From<std::io::Error> for Error {
     fn from(e: std::io::Error) -> Self {
+        // errors such as the emitter's `BufferLimitError` which were passed
+        // through a writer
+        if e.get_ref().is_some_and(|inner| inner.is::<Error>()) {
+            return *e.into_inner().and_then(|inner| inner.downcast().ok()).unwrap();
+        }
         Error::IoError(e)
     }
 }
@@ -56,3 +98,8 @@ impl From<std::string::FromUtf8Error> for Error {
     }
 }
 
+impl From<std::str::Utf8Error> for Error {
+    fn from(e: std::str::Utf8Error) -> Self {
+        Error::Utf8Error(e)
+    }
+}
@@ -0,0 +1,431 @@
+// Conversion between NBT and JSON, enabled by the `json` feature
+//
+// The lossy mode writes plain JSON numbers, strings, arrays and objects,
+// which loses the tag types. The tagged mode writes every value as an object
+// such as `{"type": "long", "value": "123"}`, from which it can be read back
+// the same. Longs are written as strings in the tagged mode since many JSON
+// readers can not hold them exactly, and floats which JSON can not express as
+// the strings "NaN", "Infinity" and "-Infinity".
+
+use std::cell::RefCell;
+use std::convert::TryFrom;
+use serde::ser::{self, Serialize, SerializeMap, SerializeSeq, Serializer};
+use serde_json::{Map, Number};
+use crate::error::{Error, Result};
+use crate::nbt;
+use crate::nbt::input::Input;
+use crate::nbt::parser::{Event, Parser};
+use crate::value::{Compound, Value};
+
+/// how values are written as JSON
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum JsonMode {
+    /// plain JSON values, the tag types are lost
+    #[default]
+    Lossy,
+    /// objects holding each value's tag type and its value
+    Tagged,
+}
+
+/// the names of the tag types in the tagged mode
+fn type_name(tag_type: u8) -> &'static str {
+    match tag_type {
+        nbt::TAG_I8 => "byte",
+        nbt::TAG_I16 => "short",
+        nbt::TAG_I32 => "int",
+        nbt::TAG_I64 => "long",
+        nbt::TAG_F32 => "float",
+        nbt::TAG_F64 => "double",
+        nbt::TAG_I8_ARRAY => "byte_array",
+        nbt::TAG_STRING => "string",
+        nbt::TAG_LIST => "list",
+        nbt::TAG_COMPOUND => "compound",
+        nbt::TAG_I32_ARRAY => "int_array",
+        _ => "long_array",
+    }
+}
+
+/// converts a value to JSON
+pub fn to_json_value(value: &Value, mode: JsonMode) -> serde_json::Value {
+    let tagged = mode == JsonMode::Tagged;
+    let json = match value {
+        Value::Byte(v) => serde_json::Value::from(*v),
+        Value::Short(v) => serde_json::Value::from(*v),
+        Value::Int(v) => serde_json::Value::from(*v),
+        Value::Long(v) if tagged => serde_json::Value::String(v.to_string()),
+        Value::Long(v) => serde_json::Value::from(*v),
+        // the shortest decimal of a float, not all the digits of its `f64`
+        Value::Float(v) => float_to_json(v.to_string().parse().unwrap_or(f64::NAN), tagged),
+        Value::Double(v) => float_to_json(*v, tagged),
+        Value::ByteArray(v) => v.iter().copied().collect(),
+        Value::String(v) => serde_json::Value::String(v.clone()),
+        Value::List(v) => v.iter().map(|v| to_json_value(v, mode)).collect(),
+        Value::Compound(v) => {
+            serde_json::Value::Object(v.iter().map(|(name, v)| (name.clone(), to_json_value(v, mode))).collect())
+        },
+        Value::IntArray(v) => v.iter().copied().collect(),
+        Value::LongArray(v) if tagged => v.iter().map(|v| v.to_string()).collect(),
+        Value::LongArray(v) => v.iter().copied().collect(),
+    };
+
+    if !tagged {
+        return json;
+    }
+    let mut object = Map::new();
+    object.insert("type".to_string(), serde_json::Value::from(type_name(value.tag_type())));
+    object.insert("value".to_string(), json);
+    serde_json::Value::Object(object)
+}
+
+/// JSON has no NaN or infinities, they are `null` in the lossy mode
+fn float_to_json(value: f64, tagged: bool) -> serde_json::Value {
+    match Number::from_f64(value) {
+        Some(number) => serde_json::Value::Number(number),
+        None if !tagged => serde_json::Value::Null,
+        None if value.is_nan() => serde_json::Value::from("NaN"),
+        None if value > 0.0 => serde_json::Value::from("Infinity"),
+        None => serde_json::Value::from("-Infinity"),
+    }
+}
+
+/// converts JSON to a value
+///
+/// In the lossy mode the types are guessed: booleans become bytes, integers
+/// ints or longs if they do not fit, other numbers doubles, and `null` is an
+/// error. In the tagged mode every value has to be an object holding its type
+/// and its value as `to_json_value` writes them.
+pub fn from_json_value(json: &serde_json::Value, mode: JsonMode) -> Result<Value> {
+    match mode {
+        JsonMode::Lossy => from_lossy(json),
+        JsonMode::Tagged => from_tagged(json),
+    }
+}
+
+fn from_lossy(json: &serde_json::Value) -> Result<Value> {
+    let value = match json {
+        serde_json::Value::Null => return Err(Error::Message("null has no NBT type".to_string())),
+        serde_json::Value::Bool(v) => Value::Byte(*v as i8),
+        serde_json::Value::Number(v) => match (v.as_i64(), v.as_f64()) {
+            (Some(v), _) => match i32::try_from(v) {
+                Ok(v) => Value::Int(v),
+                Err(_) => Value::Long(v),
+            },
+            (None, Some(_)) if v.is_u64() => return Err(Error::Message(format!("{} does not fit in a TAG_Long", v))),
+            (None, Some(v)) => Value::Double(v),
+            (None, None) => return Err(Error::Message(format!("{} is not a number NBT can hold", v))),
+        },
+        serde_json::Value::String(v) => Value::String(v.clone()),
+        serde_json::Value::Array(v) => Value::List(v.iter().map(from_lossy).collect::<Result<_>>()?),
+        serde_json::Value::Object(v) => Value::Compound(
+            v.iter().map(|(name, v)| Ok((name.clone(), from_lossy(v)?))).collect::<Result<Compound>>()?,
+        ),
+    };
+    Ok(value)
+}
+
+fn from_tagged(json: &serde_json::Value) -> Result<Value> {
+    let invalid = || Error::Message(format!("{} is not a tagged NBT value", json));
+    let object = json.as_object().filter(|object| object.len() == 2).ok_or_else(invalid)?;
+    let tag_type = object.get("type").and_then(serde_json::Value::as_str).ok_or_else(invalid)?;
+    let json = object.get("value").ok_or_else(invalid)?;
+
+    let invalid = || Error::Message(format!("{} is not a valid {} value", json, tag_type));
+    let array = || json.as_array().ok_or_else(invalid);
+    let value = match tag_type {
+        "byte" => Value::Byte(json_integer(json).ok_or_else(invalid)?),
+        "short" => Value::Short(json_integer(json).ok_or_else(invalid)?),
+        "int" => Value::Int(json_integer(json).ok_or_else(invalid)?),
+        "long" => Value::Long(json_integer(json).ok_or_else(invalid)?),
+        "float" => Value::Float(json_float(json).ok_or_else(invalid)? as f32),
+        "double" => Value::Double(json_float(json).ok_or_else(invalid)?),
+        "byte_array" => {
+            Value::ByteArray(array()?.iter().map(json_integer).collect::<Option<_>>().ok_or_else(invalid)?)
+        },
+        "string" => Value::String(json.as_str().ok_or_else(invalid)?.to_string()),
+        "list" => Value::List(array()?.iter().map(from_tagged).collect::<Result<_>>()?),
+        "compound" => Value::Compound(
+            json.as_object()
+                .ok_or_else(invalid)?
+                .iter()
+                .map(|(name, v)| Ok((name.clone(), from_tagged(v)?)))
+                .collect::<Result<Compound>>()?,
+        ),
+        "int_array" => {
+            Value::IntArray(array()?.iter().map(json_integer).collect::<Option<_>>().ok_or_else(invalid)?)
+        },
+        "long_array" => {
+            Value::LongArray(array()?.iter().map(json_integer).collect::<Option<_>>().ok_or_else(invalid)?)
+        },
+        _ => return Err(Error::Message(format!("unknown tag type \"{}\"", tag_type))),
+    };
+    Ok(value)
+}
+
+/// reads an integer written as a number or as a string
+fn json_integer<T>(json: &serde_json::Value) -> Option<T>
+where
+    T: TryFrom<i64>,
+{
+    let value = match json {
+        serde_json::Value::Number(v) => v.as_i64()?,
+        serde_json::Value::String(v) => v.parse().ok()?,
+        _ => return None,
+    };
+    T::try_from(value).ok()
+}
+
+fn json_float(json: &serde_json::Value) -> Option<f64> {
+    match json {
+        serde_json::Value::Number(v) => v.as_f64(),
+        serde_json::Value::String(v) => match v.as_str() {
+            "NaN" => Some(f64::NAN),
+            "Infinity" => Some(f64::INFINITY),
+            "-Infinity" => Some(f64::NEG_INFINITY),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// writes the next root tag read by `parser` to `serializer`, such as a
+/// `serde_json::Serializer`, as it is read instead of keeping it in memory
+///
+/// The root tag's name is not written. Errors of the parser fail through
+/// `serde::ser::Error::custom`.
+pub fn transcode<'de, R, S>(parser: &mut Parser<'de, R>, serializer: S, mode: JsonMode) -> std::result::Result<S::Ok, S::Error>
+where
+    R: Input<'de>,
+    S: Serializer,
+{
+    let transcoder = Transcoder { parser: RefCell::new(parser), mode };
+    match transcoder.next_event()? {
+        Event::TagStart { .. } => transcoder.serialize(serializer),
+        _ => Err(ser::Error::custom(Error::InvalidParserStateError)),
+    }
+}
+
+/// serializes the next value of the parser each time it is serialized
+struct Transcoder<'a, 'de, R> {
+    parser: RefCell<&'a mut Parser<'de, R>>,
+    mode: JsonMode,
+}
+
+impl<'a, 'de, R> Transcoder<'a, 'de, R>
+where
+    R: Input<'de>,
+{
+    fn next_event<E>(&self) -> std::result::Result<Event<'de>, E>
+    where
+        E: ser::Error,
+    {
+        match self.parser.borrow_mut().next() {
+            Some(Ok(event)) => Ok(event),
+            Some(Err(e)) => Err(E::custom(e)),
+            None => Err(E::custom(Error::Eof)),
+        }
+    }
+
+    fn expect_end<E>(&self) -> std::result::Result<(), E>
+    where
+        E: ser::Error,
+    {
+        match self.next_event()? {
+            Event::End => Ok(()),
+            _ => Err(E::custom(Error::InvalidParserStateError)),
+        }
+    }
+
+    /// writes `value`, inside an object holding its type in the tagged mode
+    fn write<T, S>(&self, tag_type: u8, value: T, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        T: Serialize,
+        S: Serializer,
+    {
+        if self.mode == JsonMode::Lossy {
+            return value.serialize(serializer);
+        }
+        let mut map = serializer.serialize_map(Some(2))?;
+        map.serialize_entry("type", type_name(tag_type))?;
+        map.serialize_entry("value", &value)?;
+        map.end()
+    }
+}
+
+impl<'a, 'de, R> Serialize for Transcoder<'a, 'de, R>
+where
+    R: Input<'de>,
+{
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let tagged = self.mode == JsonMode::Tagged;
+        match self.next_event()? {
+            Event::I8(v) => self.write(nbt::TAG_I8, v, serializer),
+            Event::I16(v) => self.write(nbt::TAG_I16, v, serializer),
+            Event::I32(v) => self.write(nbt::TAG_I32, v, serializer),
+            Event::I64(v) if tagged => self.write(nbt::TAG_I64, v.to_string(), serializer),
+            Event::I64(v) => self.write(nbt::TAG_I64, v, serializer),
+            Event::F32(v) if v.is_finite() => self.write(nbt::TAG_F32, v, serializer),
+            Event::F32(v) => self.write(nbt::TAG_F32, float_to_json(v as f64, tagged), serializer),
+            Event::F64(v) if v.is_finite() => self.write(nbt::TAG_F64, v, serializer),
+            Event::F64(v) => self.write(nbt::TAG_F64, float_to_json(v, tagged), serializer),
+            Event::String(v) => self.write(nbt::TAG_STRING, &*v, serializer),
+            Event::CompoundStart => self.write(nbt::TAG_COMPOUND, Nested { transcoder: self, len: None }, serializer),
+            Event::ListStart { len, .. } => {
+                self.write(nbt::TAG_LIST, Nested { transcoder: self, len: Some(len) }, serializer)
+            },
+            Event::ArrayStart { ty, len } => self.write(ty, Array { transcoder: self, len }, serializer),
+            Event::TagStart { .. } | Event::End => Err(ser::Error::custom(Error::InvalidParserStateError)),
+        }
+    }
+}
+
+/// the tags of a compound, or the elements of a list of length `len`
+struct Nested<'t, 'a, 'de, R> {
+    transcoder: &'t Transcoder<'a, 'de, R>,
+    len: Option<usize>,
+}
+
+impl<'t, 'a, 'de, R> Serialize for Nested<'t, 'a, 'de, R>
+where
+    R: Input<'de>,
+{
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        if let Some(len) = self.len {
+            let mut seq = serializer.serialize_seq(Some(len))?;
+            for _ in 0..len {
+                seq.serialize_element(self.transcoder)?;
+            }
+            self.transcoder.expect_end()?;
+            return seq.end();
+        }
+
+        let mut map = serializer.serialize_map(None)?;
+        loop {
+            match self.transcoder.next_event()? {
+                Event::TagStart { name, .. } => map.serialize_entry(&*name, self.transcoder)?,
+                Event::End => return map.end(),
+                _ => return Err(ser::Error::custom(Error::InvalidParserStateError)),
+            }
+        }
+    }
+}
+
+/// the elements of an array, which are not tagged themselves
+struct Array<'t, 'a, 'de, R> {
+    transcoder: &'t Transcoder<'a, 'de, R>,
+    len: usize,
+}
+
+impl<'t, 'a, 'de, R> Serialize for Array<'t, 'a, 'de, R>
+where
+    R: Input<'de>,
+{
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let tagged = self.transcoder.mode == JsonMode::Tagged;
+        let mut seq = serializer.serialize_seq(Some(self.len))?;
+        for _ in 0..self.len {
+            match self.transcoder.next_event()? {
+                Event::I8(v) => seq.serialize_element(&v)?,
+                Event::I32(v) => seq.serialize_element(&v)?,
+                Event::I64(v) if tagged => seq.serialize_element(&v.to_string())?,
+                Event::I64(v) => seq.serialize_element(&v)?,
+                _ => return Err(ser::Error::custom(Error::InvalidParserStateError)),
+            }
+        }
+        self.transcoder.expect_end()?;
+        seq.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use crate::from_slice;
+    use super::*;
+
+    // `{"a": 1b, "b": 2L, "c": 0.1f, "d": [L; 3L], "e": ["x"], "f": {}}`
+    const BUFFER: &[u8] = b"\x0a\x00\x00\
+        \x01\x00\x01a\x01\
+        \x04\x00\x01b\x00\x00\x00\x00\x00\x00\x00\x02\
+        \x05\x00\x01c\x3d\xcc\xcc\xcd\
+        \x0c\x00\x01d\x00\x00\x00\x01\x00\x00\x00\x00\x00\x00\x00\x03\
+        \x09\x00\x01e\x08\x00\x00\x00\x01\x00\x01x\
+        \x0a\x00\x01f\x00\
+        \x00";
+
+    #[test]
+    fn test_lossy() {
+        let value: Value = from_slice(BUFFER).unwrap();
+        let expected = json!({"a": 1, "b": 2, "c": 0.1, "d": [3], "e": ["x"], "f": {}});
+        assert_eq!(to_json_value(&value, JsonMode::Lossy), expected);
+
+        let mut parser = Parser::from_slice(BUFFER);
+        let mut output = Vec::new();
+        transcode(&mut parser, &mut serde_json::Serializer::new(&mut output), JsonMode::Lossy).unwrap();
+        assert_eq!(serde_json::from_slice::<serde_json::Value>(&output).unwrap(), expected);
+
+        // the types are guessed when converting back
+        let value = from_json_value(&json!({"a": 1, "b": 5000000000i64, "c": 0.5, "d": true}), JsonMode::Lossy).unwrap();
+        let expected: Compound = vec![
+            ("a".to_string(), Value::Int(1)),
+            ("b".to_string(), Value::Long(5000000000)),
+            ("c".to_string(), Value::Double(0.5)),
+            ("d".to_string(), Value::Byte(1)),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(value, Value::Compound(expected));
+        assert!(from_json_value(&json!([null]), JsonMode::Lossy).is_err());
+        assert!(from_json_value(&json!(u64::MAX), JsonMode::Lossy).is_err());
+    }
+
+    #[test]
+    fn test_tagged() {
+        let value: Value = from_slice(BUFFER).unwrap();
+        let expected = json!({"type": "compound", "value": {
+            "a": {"type": "byte", "value": 1},
+            "b": {"type": "long", "value": "2"},
+            "c": {"type": "float", "value": 0.1},
+            "d": {"type": "long_array", "value": ["3"]},
+            "e": {"type": "list", "value": [{"type": "string", "value": "x"}]},
+            "f": {"type": "compound", "value": {}},
+        }});
+        assert_eq!(to_json_value(&value, JsonMode::Tagged), expected);
+        assert_eq!(from_json_value(&expected, JsonMode::Tagged).unwrap(), value);
+
+        let mut parser = Parser::from_slice(BUFFER);
+        let mut output = Vec::new();
+        transcode(&mut parser, &mut serde_json::Serializer::new(&mut output), JsonMode::Tagged).unwrap();
+        assert_eq!(serde_json::from_slice::<serde_json::Value>(&output).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_tagged_special_values() {
+        let value = Value::List(vec![Value::Double(f64::INFINITY), Value::Double(f64::NAN)]);
+        let json = to_json_value(&value, JsonMode::Tagged);
+        assert_eq!(json["value"][0], json!({"type": "double", "value": "Infinity"}));
+        match from_json_value(&json, JsonMode::Tagged).unwrap() {
+            Value::List(v) => assert!(matches!(v[1], Value::Double(v) if v.is_nan())),
+            _ => panic!(),
+        }
+
+        assert!(from_json_value(&json!({"type": "byte", "value": 128}), JsonMode::Tagged).is_err());
+        assert!(from_json_value(&json!({"type": "bytes", "value": 1}), JsonMode::Tagged).is_err());
+        assert!(from_json_value(&json!({"type": "byte"}), JsonMode::Tagged).is_err());
+        assert!(from_json_value(&json!(1), JsonMode::Tagged).is_err());
+    }
+
+    #[test]
+    fn test_transcode_truncated() {
+        let mut parser = Parser::from_slice(&BUFFER[..BUFFER.len() - 5]);
+        assert!(transcode(&mut parser, &mut serde_json::Serializer::new(Vec::new()), JsonMode::Lossy).is_err());
+    }
+}
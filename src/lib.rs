@@ -1,12 +1,36 @@
 // Serde library for reading/writing to Minecraft NBT format as
 // documented at https://minecraft.gamepedia.com/NBT_format
 
+mod array;
+mod boolean;
+mod config;
 mod de;
 mod error;
+#[cfg(feature = "json")]
+mod json;
+mod list;
 // FIXME: this module should be made private
 pub mod nbt;
-// mod ser;
+mod number;
+mod ser;
+mod snbt;
+mod value;
+mod variant;
 
+pub use crate::array::{byte_array, int_array, long_array, ByteArray, IntArray, LongArray};
+pub use crate::boolean::byte_bool;
+pub use crate::config::{
+    ByteOrder, DeserializerConfig, RootMode, SerializerConfig, SnbtConfig, SnbtDialect, StringEncoding, UnsignedMode,
+};
 pub use crate::error::{Error, Result};
-pub use crate::de::{from_reader, Deserializer};
-// pub use ser::{to_writer, Serializer};
+#[cfg(feature = "json")]
+pub use crate::json::{from_json_value, to_json_value, transcode, JsonMode};
+pub use crate::list::{
+    byte_array_list, byte_list, compound_list, double_list, float_list, int_array_list, int_list, list_list,
+    long_array_list, long_list, short_list, string_list,
+};
+pub use crate::de::{from_reader, from_reader_named, from_slice, from_slice_named, Deserializer, StreamDeserializer};
+pub use crate::ser::{to_writer, to_writer_named, Serializer};
+pub use crate::snbt::{from_snbt, to_snbt};
+pub use crate::value::{Compound, NamedTag, Value};
+pub use crate::variant::{byte_enum, int_enum};
@@ -0,0 +1,101 @@
+// `#[serde(with = "...")]` modules which give a list field the element type
+// that is written when the list is empty
+//
+// Vanilla writes empty lists with `TAG_END` as their element type, which the
+// serializer does too unless `SerializerConfig::typed_empty_lists` is enabled,
+// it then writes the element type of these modules for readers that expect
+// it. The serializer recognizes the newtype struct names below, other serde
+// data formats see an ordinary newtype around the sequence.
+
+const LIST_NAME_PREFIX: &str = "__serde_nbt_list_";
+
+/// returns the element type of a list module's newtype struct name
+pub(crate) fn list_type(name: &str) -> Option<u8> {
+    name.strip_prefix(LIST_NAME_PREFIX)?.parse().ok()
+}
+
+macro_rules! list_module {
+    ($(#[$attr:meta])* $module:ident, $tag_type:literal) => {
+        $(#[$attr])*
+        pub mod $module {
+            use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+            pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+            where
+                T: ?Sized + Serialize,
+                S: Serializer,
+            {
+                serializer.serialize_newtype_struct(concat!("__serde_nbt_list_", $tag_type), value)
+            }
+
+            pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+            where
+                T: Deserialize<'de>,
+                D: Deserializer<'de>,
+            {
+                T::deserialize(deserializer)
+            }
+        }
+    };
+}
+
+list_module!(
+    /// use with `#[serde(with = "serde_nbt::byte_list")]` to give an empty
+    /// list the element type `TAG_I8`
+    byte_list, 1
+);
+list_module!(
+    /// use with `#[serde(with = "serde_nbt::short_list")]` to give an empty
+    /// list the element type `TAG_I16`
+    short_list, 2
+);
+list_module!(
+    /// use with `#[serde(with = "serde_nbt::int_list")]` to give an empty
+    /// list the element type `TAG_I32`
+    int_list, 3
+);
+list_module!(
+    /// use with `#[serde(with = "serde_nbt::long_list")]` to give an empty
+    /// list the element type `TAG_I64`
+    long_list, 4
+);
+list_module!(
+    /// use with `#[serde(with = "serde_nbt::float_list")]` to give an empty
+    /// list the element type `TAG_F32`
+    float_list, 5
+);
+list_module!(
+    /// use with `#[serde(with = "serde_nbt::double_list")]` to give an empty
+    /// list the element type `TAG_F64`
+    double_list, 6
+);
+list_module!(
+    /// use with `#[serde(with = "serde_nbt::byte_array_list")]` to give an
+    /// empty list the element type `TAG_I8_ARRAY`
+    byte_array_list, 7
+);
+list_module!(
+    /// use with `#[serde(with = "serde_nbt::string_list")]` to give an empty
+    /// list the element type `TAG_STRING`
+    string_list, 8
+);
+list_module!(
+    /// use with `#[serde(with = "serde_nbt::list_list")]` to give an empty
+    /// list the element type `TAG_LIST`
+    list_list, 9
+);
+list_module!(
+    /// use with `#[serde(with = "serde_nbt::compound_list")]` to give an
+    /// empty list the element type `TAG_COMPOUND`
+    compound_list, 10
+);
+list_module!(
+    /// use with `#[serde(with = "serde_nbt::int_array_list")]` to give an
+    /// empty list the element type `TAG_I32_ARRAY`
+    int_array_list, 11
+);
+list_module!(
+    /// use with `#[serde(with = "serde_nbt::long_array_list")]` to give an
+    /// empty list the element type `TAG_I64_ARRAY`
+    long_array_list, 12
+);
@@ -1,3 +1,6 @@
+pub mod emitter;
+pub mod input;
+pub(crate) mod mutf8;
 pub mod parser;
 
 // https://minecraft.gamepedia.com/NBT_format#TAG_definition
@@ -18,3 +21,56 @@ pub const TAG_I64_ARRAY: u8 = 12;
 // maximum number of elements in a list
 // https://minecraft.gamepedia.com/NBT_format#TAG_definition
 pub const LIST_MAX_LEN: usize = 2_147_483_639;
+
+// maximum number of nested compounds and lists, vanilla uses the same limit
+// https://minecraft.gamepedia.com/NBT_format#Binary_format
+pub const DEFAULT_MAX_DEPTH: usize = 512;
+
+// size quota vanilla uses for NBT received over the network
+pub const NETWORK_SIZE_QUOTA: usize = 2 * 1024 * 1024;
+
+// number of bytes of a list of unknown length which are kept in memory
+// until its length is known
+pub const DEFAULT_BUFFER_LIMIT: usize = 16 * 1024 * 1024;
+
+/// the name of the tag type `tag_type`, as used by vanilla
+pub fn tag_type_name(tag_type: u8) -> &'static str {
+    match tag_type {
+        TAG_END => "TAG_End",
+        TAG_I8 => "TAG_Byte",
+        TAG_I16 => "TAG_Short",
+        TAG_I32 => "TAG_Int",
+        TAG_I64 => "TAG_Long",
+        TAG_F32 => "TAG_Float",
+        TAG_F64 => "TAG_Double",
+        TAG_I8_ARRAY => "TAG_Byte_Array",
+        TAG_STRING => "TAG_String",
+        TAG_LIST => "TAG_List",
+        TAG_COMPOUND => "TAG_Compound",
+        TAG_I32_ARRAY => "TAG_Int_Array",
+        TAG_I64_ARRAY => "TAG_Long_Array",
+        _ => "unknown tag type",
+    }
+}
+
+/// the smallest number of bytes that a value of type `tag_type` takes up
+pub(crate) fn min_value_size(tag_type: u8) -> usize {
+    match tag_type {
+        TAG_I8 => 1,
+        TAG_I16 => 2,
+        TAG_I32 => 4,
+        TAG_I64 => 8,
+        TAG_F32 => 4,
+        TAG_F64 => 8,
+        // the length prefixes of arrays and strings
+        TAG_I8_ARRAY => 4,
+        TAG_STRING => 2,
+        // a list's element type and length
+        TAG_LIST => 5,
+        // an empty compound's `TAG_END`
+        TAG_COMPOUND => 1,
+        TAG_I32_ARRAY => 4,
+        TAG_I64_ARRAY => 4,
+        _ => 0,
+    }
+}
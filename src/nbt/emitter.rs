@@ -0,0 +1,735 @@
+use std::io::{self, Write};
+use byteorder::{BigEndian, LittleEndian, WriteBytesExt};
+use log::trace;
+use crate::config::{ByteOrder, StringEncoding};
+use crate::error::{Result, Error};
+use crate::nbt;
+use crate::nbt::mutf8;
+
+/// writes NBT one tag at a time, the counterpart of `Parser`
+///
+/// Every value is given a name, which is written as part of the tag's header
+/// inside compounds and at the root. Values inside lists have no header and
+/// their names are ignored. Compounds and lists are closed with `end`, and
+/// `finish` checks that all of them were closed.
+///
+/// With sorted keys, the tags of a compound are kept in memory until it is
+/// ended and then written sorted by name. The same goes for the elements of
+/// lists begun with `begin_buffered_list`, whose length is written once they
+/// are ended, and for all lists with heterogeneous lists, whose element types
+/// are only known once they are ended.
+pub struct Emitter<W> {
+    output: W,
+    stack: Vec<EmitterState>,
+    byte_order: ByteOrder,
+    string_encoding: StringEncoding,
+    // whether the root tag's header has a name
+    named_root: bool,
+    // maximum number of nested compounds and lists
+    max_depth: usize,
+    // number of bytes of strings, arrays and lists written so far, and the
+    // limit, counted the same way as by the parser
+    size: usize,
+    size_quota: usize,
+    sort_keys: bool,
+    // maximum number of bytes of a buffered list which are kept in memory
+    buffer_limit: usize,
+    // whether lists may hold elements of different types, which are then
+    // wrapped in compounds
+    heterogeneous_lists: bool,
+}
+
+#[derive(Debug, PartialEq)]
+enum EmitterState {
+    // with sorted keys, `entries` holds the names and bytes of the tags
+    // written so far, the last one is the tag currently being written.
+    // `tags` counts the tags, to tell whether the compound holds only a tag
+    // named "" as the wrapper of a heterogeneous list's element would
+    Compound { entries: Option<Vec<(String, Vec<u8>)>>, tags: usize, first_unnamed: bool },
+    List { elem_type: u8, len: usize, count: usize },
+    // a list which is written once it ends, holding the bytes of its
+    // elements, and with heterogeneous lists the type and offset of each and
+    // whether any element is a compound that looks like a wrapper
+    BufferedList {
+        elem_type: u8,
+        len: Option<usize>,
+        count: usize,
+        buffer: ListBuffer,
+        elements: Vec<(u8, usize)>,
+        wrap: bool,
+    },
+}
+
+/// the bytes of a buffered list, which fails writes that would exceed the
+/// buffer limit
+#[derive(Debug, PartialEq)]
+struct ListBuffer {
+    bytes: Vec<u8>,
+    limit: usize,
+}
+
+impl Write for ListBuffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if buf.len() > self.limit - self.bytes.len() {
+            // turned back into `Error::BufferLimitError` when converted
+            return Err(io::Error::other(Error::BufferLimitError));
+        }
+        self.bytes.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<W> Emitter<W>
+where
+    W: Write,
+{
+    pub fn new(output: W) -> Self {
+        #[cfg(debug_assertions)]
+        trace!("Emitter::new");
+
+        Emitter {
+            output,
+            stack: Vec::new(),
+            byte_order: ByteOrder::default(),
+            string_encoding: StringEncoding::default(),
+            named_root: true,
+            max_depth: nbt::DEFAULT_MAX_DEPTH,
+            size: 0,
+            size_quota: usize::MAX,
+            sort_keys: false,
+            buffer_limit: nbt::DEFAULT_BUFFER_LIMIT,
+            heterogeneous_lists: false,
+        }
+    }
+
+    pub fn set_byte_order(&mut self, byte_order: ByteOrder) {
+        self.byte_order = byte_order;
+    }
+
+    pub fn set_string_encoding(&mut self, string_encoding: StringEncoding) {
+        self.string_encoding = string_encoding;
+    }
+
+    /// sets whether root tags are written with a name
+    pub fn set_named_root(&mut self, named_root: bool) {
+        self.named_root = named_root;
+    }
+
+    /// sets the maximum number of nested compounds and lists, beginning more
+    /// fails with `Error::DepthLimitError`
+    pub fn set_max_depth(&mut self, max_depth: usize) {
+        self.max_depth = max_depth;
+    }
+
+    /// sets the maximum number of bytes of strings, arrays and lists which
+    /// are written, exceeding it fails with `Error::SizeLimitError`
+    pub fn set_size_quota(&mut self, size_quota: usize) {
+        self.size_quota = size_quota;
+    }
+
+    /// sets whether the tags of compounds are written sorted by name
+    pub fn set_sort_keys(&mut self, sort_keys: bool) {
+        self.sort_keys = sort_keys;
+    }
+
+    /// sets the maximum number of bytes of a buffered list which are kept in
+    /// memory, exceeding it fails with `Error::BufferLimitError`
+    pub fn set_buffer_limit(&mut self, buffer_limit: usize) {
+        self.buffer_limit = buffer_limit;
+    }
+
+    /// sets whether lists may hold elements of different types, such lists
+    /// are written as a list of compounds which hold each element as a tag
+    /// named ""
+    pub fn set_heterogeneous_lists(&mut self, heterogeneous_lists: bool) {
+        self.heterogeneous_lists = heterogeneous_lists;
+    }
+
+    /// returns the output, fails if a compound or list has not been ended
+    pub fn finish(self) -> Result<W> {
+        #[cfg(debug_assertions)]
+        trace!("Emitter::finish");
+
+        if !self.stack.is_empty() {
+            return Err(Error::InvalidEmitterStateError);
+        }
+        Ok(self.output)
+    }
+
+    pub fn begin_compound(&mut self, name: &str) -> Result<()> {
+        #[cfg(debug_assertions)]
+        trace!("Emitter::begin_compound({:?})", name);
+
+        self.check_depth()?;
+        self.begin_value(name, nbt::TAG_COMPOUND)?;
+        let entries = if self.sort_keys { Some(Vec::new()) } else { None };
+        self.stack.push(EmitterState::Compound { entries, tags: 0, first_unnamed: false });
+        Ok(())
+    }
+
+    /// begins a list of `len` values of type `elem_type`, empty lists may use
+    /// `TAG_END` as their element type
+    pub fn begin_list(&mut self, name: &str, elem_type: u8, len: usize) -> Result<()> {
+        #[cfg(debug_assertions)]
+        trace!("Emitter::begin_list({:?}, {:?}, {:?})", name, elem_type, len);
+
+        if elem_type > nbt::TAG_I64_ARRAY || (elem_type == nbt::TAG_END && len != 0) {
+            return Err(Error::InvalidTagTypeError);
+        }
+
+        // the element types are only known once the list ends
+        if self.heterogeneous_lists && len != 0 {
+            return self.begin_buffered(name, elem_type, Some(len));
+        }
+
+        self.check_depth()?;
+        self.account(len, nbt::min_value_size(elem_type))?;
+        self.begin_value(name, nbt::TAG_LIST)?;
+        self.writer()?.write_u8(elem_type)?;
+        self.write_i32_list_len(len)?;
+        self.stack.push(EmitterState::List { elem_type, len, count: 0 });
+        Ok(())
+    }
+
+    /// begins a list of values of type `elem_type` whose length is not known
+    /// yet, the elements are kept in memory until the list is ended
+    pub fn begin_buffered_list(&mut self, name: &str, elem_type: u8) -> Result<()> {
+        #[cfg(debug_assertions)]
+        trace!("Emitter::begin_buffered_list({:?}, {:?})", name, elem_type);
+
+        if elem_type == nbt::TAG_END || elem_type > nbt::TAG_I64_ARRAY {
+            return Err(Error::InvalidTagTypeError);
+        }
+
+        self.begin_buffered(name, elem_type, None)
+    }
+
+    /// ends the innermost compound or list
+    pub fn end(&mut self) -> Result<()> {
+        #[cfg(debug_assertions)]
+        trace!("Emitter::end");
+
+        match self.stack.pop() {
+            Some(EmitterState::Compound { entries, tags, first_unnamed }) => {
+                if let Some(mut entries) = entries {
+                    // the sort is stable, so tags with the same name keep
+                    // their order
+                    entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+                    for (_, bytes) in entries {
+                        self.writer()?.write_all(&bytes)?;
+                    }
+                }
+                self.writer()?.write_u8(nbt::TAG_END)?;
+
+                // such a compound would be unwrapped when read, so it has to
+                // be wrapped itself
+                let looks_wrapped = self.heterogeneous_lists && tags == 1 && first_unnamed;
+                if let Some(EmitterState::BufferedList { wrap, .. }) = self.stack.last_mut() {
+                    *wrap |= looks_wrapped;
+                }
+                Ok(())
+            },
+            Some(EmitterState::List { len, count, .. }) => {
+                if count != len {
+                    return Err(Error::ListLengthError { expected: len, actual: count });
+                }
+                Ok(())
+            },
+            Some(EmitterState::BufferedList { elem_type, len, count, buffer, elements, wrap }) => {
+                let bytes = buffer.bytes;
+                if let Some(len) = len.filter(|&len| len != count) {
+                    return Err(Error::ListLengthError { expected: len, actual: count });
+                }
+
+                if !wrap && elements.iter().all(|&(tag_type, _)| tag_type == elem_type) {
+                    self.writer()?.write_u8(elem_type)?;
+                    self.write_i32_list_len(count)?;
+                    self.writer()?.write_all(&bytes)?;
+                    return Ok(());
+                }
+
+                // each element is wrapped in a compound as a tag named ""
+                self.writer()?.write_u8(nbt::TAG_COMPOUND)?;
+                self.write_i32_list_len(count)?;
+                for (i, &(tag_type, start)) in elements.iter().enumerate() {
+                    let end = elements.get(i + 1).map_or(bytes.len(), |&(_, end)| end);
+                    self.writer()?.write_u8(tag_type)?;
+                    self.write_nbt_string("")?;
+                    self.writer()?.write_all(&bytes[start..end])?;
+                    self.writer()?.write_u8(nbt::TAG_END)?;
+                }
+                Ok(())
+            },
+            None => Err(Error::InvalidEmitterStateError),
+        }
+    }
+
+    pub fn write_i8(&mut self, name: &str, value: i8) -> Result<()> {
+        #[cfg(debug_assertions)]
+        trace!("Emitter::write_i8({:?}, {:?})", name, value);
+
+        self.begin_value(name, nbt::TAG_I8)?;
+        self.writer()?.write_i8(value)?;
+        Ok(())
+    }
+
+    pub fn write_i16(&mut self, name: &str, value: i16) -> Result<()> {
+        #[cfg(debug_assertions)]
+        trace!("Emitter::write_i16({:?}, {:?})", name, value);
+
+        self.begin_value(name, nbt::TAG_I16)?;
+        self.write_i16_value(value)
+    }
+
+    pub fn write_i32(&mut self, name: &str, value: i32) -> Result<()> {
+        #[cfg(debug_assertions)]
+        trace!("Emitter::write_i32({:?}, {:?})", name, value);
+
+        self.begin_value(name, nbt::TAG_I32)?;
+        self.write_i32_value(value)
+    }
+
+    pub fn write_i64(&mut self, name: &str, value: i64) -> Result<()> {
+        #[cfg(debug_assertions)]
+        trace!("Emitter::write_i64({:?}, {:?})", name, value);
+
+        self.begin_value(name, nbt::TAG_I64)?;
+        self.write_i64_value(value)
+    }
+
+    pub fn write_f32(&mut self, name: &str, value: f32) -> Result<()> {
+        #[cfg(debug_assertions)]
+        trace!("Emitter::write_f32({:?}, {:?})", name, value);
+
+        self.begin_value(name, nbt::TAG_F32)?;
+        self.write_f32_value(value)
+    }
+
+    pub fn write_f64(&mut self, name: &str, value: f64) -> Result<()> {
+        #[cfg(debug_assertions)]
+        trace!("Emitter::write_f64({:?}, {:?})", name, value);
+
+        self.begin_value(name, nbt::TAG_F64)?;
+        self.write_f64_value(value)
+    }
+
+    pub fn write_string(&mut self, name: &str, value: &str) -> Result<()> {
+        #[cfg(debug_assertions)]
+        trace!("Emitter::write_string({:?}, {:?})", name, value);
+
+        self.begin_value(name, nbt::TAG_STRING)?;
+        self.write_nbt_string(value)
+    }
+
+    /// writes a `TAG_I8_ARRAY` from unsigned bytes
+    pub fn write_byte_array(&mut self, name: &str, value: &[u8]) -> Result<()> {
+        #[cfg(debug_assertions)]
+        trace!("Emitter::write_byte_array({:?})", name);
+
+        self.account(value.len(), 1)?;
+        self.begin_value(name, nbt::TAG_I8_ARRAY)?;
+        self.write_i32_list_len(value.len())?;
+        self.writer()?.write_all(value)?;
+        Ok(())
+    }
+
+    pub fn write_i8_array(&mut self, name: &str, value: &[i8]) -> Result<()> {
+        #[cfg(debug_assertions)]
+        trace!("Emitter::write_i8_array({:?})", name);
+
+        self.account(value.len(), 1)?;
+        self.begin_value(name, nbt::TAG_I8_ARRAY)?;
+        self.write_i32_list_len(value.len())?;
+        for v in value {
+            self.writer()?.write_i8(*v)?;
+        }
+        Ok(())
+    }
+
+    pub fn write_i32_array(&mut self, name: &str, value: &[i32]) -> Result<()> {
+        #[cfg(debug_assertions)]
+        trace!("Emitter::write_i32_array({:?})", name);
+
+        self.account(value.len(), 4)?;
+        self.begin_value(name, nbt::TAG_I32_ARRAY)?;
+        self.write_i32_list_len(value.len())?;
+        for v in value {
+            self.write_i32_value(*v)?;
+        }
+        Ok(())
+    }
+
+    pub fn write_i64_array(&mut self, name: &str, value: &[i64]) -> Result<()> {
+        #[cfg(debug_assertions)]
+        trace!("Emitter::write_i64_array({:?})", name);
+
+        self.account(value.len(), 8)?;
+        self.begin_value(name, nbt::TAG_I64_ARRAY)?;
+        self.write_i32_list_len(value.len())?;
+        for v in value {
+            self.write_i64_value(*v)?;
+        }
+        Ok(())
+    }
+
+    /// writes the tag header for a value of type `tag_type`, or checks the
+    /// value against the element type when inside a list
+    fn begin_value(&mut self, name: &str, tag_type: u8) -> Result<()> {
+        #[cfg(debug_assertions)]
+        trace!("Emitter::begin_value({:?}, {:?})", name, tag_type);
+
+        match self.stack.last_mut() {
+            None if !self.named_root => {
+                self.writer()?.write_u8(tag_type)?;
+                Ok(())
+            },
+            None => {
+                self.writer()?.write_u8(tag_type)?;
+                self.write_nbt_string(name)
+            },
+            Some(EmitterState::Compound { entries, tags, first_unnamed }) => {
+                *first_unnamed |= *tags == 0 && name.is_empty();
+                *tags += 1;
+                // with sorted keys the tag is written to its own buffer,
+                // starting with its header
+                if let Some(entries) = entries {
+                    entries.push((name.to_string(), Vec::new()));
+                }
+                self.writer()?.write_u8(tag_type)?;
+                self.write_nbt_string(name)
+            },
+            Some(EmitterState::List { elem_type, len, count }) => {
+                if *elem_type != tag_type {
+                    return Err(Error::ListElementTypeError { list: *elem_type, element: tag_type });
+                }
+                if *count >= *len {
+                    return Err(Error::ListLengthError { expected: *len, actual: *count + 1 });
+                }
+                *count += 1;
+                Ok(())
+            },
+            Some(EmitterState::BufferedList { elem_type, len, count, buffer, elements, .. }) => {
+                if *elem_type != tag_type && !self.heterogeneous_lists {
+                    return Err(Error::ListElementTypeError { list: *elem_type, element: tag_type });
+                }
+                if let Some(len) = len.filter(|&len| *count >= len) {
+                    return Err(Error::ListLengthError { expected: len, actual: *count + 1 });
+                }
+                if self.heterogeneous_lists {
+                    elements.push((tag_type, buffer.bytes.len()));
+                }
+                *count += 1;
+                self.account(1, nbt::min_value_size(tag_type))
+            },
+        }
+    }
+
+    /// returns where to write to, which is the innermost buffered list or
+    /// tag of a compound with sorted keys, if any
+    fn writer(&mut self) -> Result<&mut dyn Write> {
+        for state in self.stack.iter_mut().rev() {
+            match state {
+                EmitterState::BufferedList { buffer, .. } => return Ok(buffer),
+                EmitterState::Compound { entries: Some(entries), .. } => {
+                    if let Some((_, bytes)) = entries.last_mut() {
+                        return Ok(bytes);
+                    }
+                },
+                _ => {},
+            }
+        }
+        Ok(&mut self.output)
+    }
+
+    /// begins a list which is written once it ends
+    fn begin_buffered(&mut self, name: &str, elem_type: u8, len: Option<usize>) -> Result<()> {
+        self.check_depth()?;
+        self.begin_value(name, nbt::TAG_LIST)?;
+        self.stack.push(EmitterState::BufferedList {
+            elem_type,
+            len,
+            count: 0,
+            buffer: ListBuffer { bytes: Vec::new(), limit: self.buffer_limit },
+            elements: Vec::new(),
+            wrap: false,
+        });
+        Ok(())
+    }
+
+    /// helper function to write NBT strings
+    fn write_nbt_string(&mut self, value: &str) -> Result<()> {
+        #[cfg(debug_assertions)]
+        trace!("Emitter::write_nbt_string({:?})", value);
+
+        let value = match self.string_encoding {
+            StringEncoding::Utf8 => value.as_bytes().into(),
+            StringEncoding::ModifiedUtf8 => mutf8::encode(value),
+        };
+
+        // u16 prefixed length
+        if value.len() > u16::MAX as usize {
+            return Err(Error::Message("string is too long".to_string()));
+        }
+        self.account(value.len(), 1)?;
+        self.write_u16_value(value.len() as u16)?;
+        self.writer()?.write_all(&value)?;
+        Ok(())
+    }
+
+    /// helper function to write NBT i32 length fields
+    fn write_i32_list_len(&mut self, len: usize) -> Result<()> {
+        #[cfg(debug_assertions)]
+        trace!("Emitter::write_i32_list_len({:?})", len);
+
+        if len > nbt::LIST_MAX_LEN {
+            return Err(Error::Message("sequence is too long".to_string()));
+        }
+        self.write_i32_value(len as i32)
+    }
+
+    /// fails if beginning another compound or list would exceed the depth
+    /// limit
+    fn check_depth(&mut self) -> Result<()> {
+        if self.stack.len() >= self.max_depth {
+            return Err(Error::DepthLimitError);
+        }
+        Ok(())
+    }
+
+    /// counts `len` values of `size` bytes each against the size quota
+    fn account(&mut self, len: usize, size: usize) -> Result<()> {
+        self.size = len.checked_mul(size)
+            .and_then(|bytes| self.size.checked_add(bytes))
+            .filter(|&total| total <= self.size_quota)
+            .ok_or(Error::SizeLimitError)?;
+        Ok(())
+    }
+
+    fn write_u16_value(&mut self, value: u16) -> Result<()> {
+        match self.byte_order {
+            ByteOrder::BigEndian => self.writer()?.write_u16::<BigEndian>(value)?,
+            ByteOrder::LittleEndian => self.writer()?.write_u16::<LittleEndian>(value)?,
+        }
+        Ok(())
+    }
+
+    fn write_i16_value(&mut self, value: i16) -> Result<()> {
+        match self.byte_order {
+            ByteOrder::BigEndian => self.writer()?.write_i16::<BigEndian>(value)?,
+            ByteOrder::LittleEndian => self.writer()?.write_i16::<LittleEndian>(value)?,
+        }
+        Ok(())
+    }
+
+    fn write_i32_value(&mut self, value: i32) -> Result<()> {
+        match self.byte_order {
+            ByteOrder::BigEndian => self.writer()?.write_i32::<BigEndian>(value)?,
+            ByteOrder::LittleEndian => self.writer()?.write_i32::<LittleEndian>(value)?,
+        }
+        Ok(())
+    }
+
+    fn write_i64_value(&mut self, value: i64) -> Result<()> {
+        match self.byte_order {
+            ByteOrder::BigEndian => self.writer()?.write_i64::<BigEndian>(value)?,
+            ByteOrder::LittleEndian => self.writer()?.write_i64::<LittleEndian>(value)?,
+        }
+        Ok(())
+    }
+
+    fn write_f32_value(&mut self, value: f32) -> Result<()> {
+        match self.byte_order {
+            ByteOrder::BigEndian => self.writer()?.write_f32::<BigEndian>(value)?,
+            ByteOrder::LittleEndian => self.writer()?.write_f32::<LittleEndian>(value)?,
+        }
+        Ok(())
+    }
+
+    fn write_f64_value(&mut self, value: f64) -> Result<()> {
+        match self.byte_order {
+            ByteOrder::BigEndian => self.writer()?.write_f64::<BigEndian>(value)?,
+            ByteOrder::LittleEndian => self.writer()?.write_f64::<LittleEndian>(value)?,
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::nbt::parser::Parser;
+    use super::*;
+
+    #[test]
+    fn test_tags() {
+        let mut emitter = Emitter::new(Vec::new());
+        emitter.begin_compound("").unwrap();
+        emitter.write_i32("a", 1).unwrap();
+        emitter.write_string("b", "hi").unwrap();
+        emitter.begin_list("c", nbt::TAG_I16, 2).unwrap();
+        emitter.write_i16("", 2).unwrap();
+        emitter.write_i16("", 3).unwrap();
+        emitter.end().unwrap();
+        emitter.begin_list("d", nbt::TAG_END, 0).unwrap();
+        emitter.end().unwrap();
+        emitter.write_i64_array("e", &[4]).unwrap();
+        emitter.end().unwrap();
+        let buffer = emitter.finish().unwrap();
+
+        // `{"a": 1, "b": "hi", "c": [2s, 3s], "d": [], "e": [L; 4L]}`
+        let expected = b"\x0a\x00\x00\
+            \x03\x00\x01a\x00\x00\x00\x01\
+            \x08\x00\x01b\x00\x02hi\
+            \x09\x00\x01c\x02\x00\x00\x00\x02\x00\x02\x00\x03\
+            \x09\x00\x01d\x00\x00\x00\x00\x00\
+            \x0c\x00\x01e\x00\x00\x00\x01\x00\x00\x00\x00\x00\x00\x00\x04\
+            \x00";
+        assert_eq!(buffer, &expected[..]);
+
+        // the parser reads back what was written
+        let mut parser = Parser::from_slice(&buffer);
+        for _ in 0..4 {
+            parser.advance().unwrap();
+        }
+        assert_eq!(parser.get_i32_value().unwrap(), 1);
+    }
+
+    #[test]
+    fn test_nested_lists() {
+        let mut emitter = Emitter::new(Vec::new());
+        emitter.begin_compound("").unwrap();
+        emitter.begin_list("a", nbt::TAG_LIST, 1).unwrap();
+        emitter.begin_list("", nbt::TAG_COMPOUND, 1).unwrap();
+        emitter.begin_compound("").unwrap();
+        emitter.write_i8("b", 1).unwrap();
+        emitter.end().unwrap();
+        emitter.end().unwrap();
+        emitter.end().unwrap();
+        emitter.end().unwrap();
+        let buffer = emitter.finish().unwrap();
+
+        // `{"a": [[{"b": 1b}]]}`
+        let expected = b"\x0a\x00\x00\
+            \x09\x00\x01a\x09\x00\x00\x00\x01\
+                \x0a\x00\x00\x00\x01\
+                    \x01\x00\x01b\x01\x00\
+            \x00";
+        assert_eq!(buffer, &expected[..]);
+    }
+
+    #[test]
+    fn test_sort_keys() {
+        let mut emitter = Emitter::new(Vec::new());
+        emitter.set_sort_keys(true);
+        emitter.begin_compound("").unwrap();
+        emitter.write_i8("c", 1).unwrap();
+        emitter.begin_list("b", nbt::TAG_COMPOUND, 1).unwrap();
+        emitter.begin_compound("").unwrap();
+        emitter.write_i8("y", 2).unwrap();
+        emitter.write_i8("x", 3).unwrap();
+        emitter.end().unwrap();
+        emitter.end().unwrap();
+        emitter.write_string("a", "hi").unwrap();
+        emitter.end().unwrap();
+        let buffer = emitter.finish().unwrap();
+
+        // `{"a": "hi", "b": [{"x": 3b, "y": 2b}], "c": 1b}`
+        let expected = b"\x0a\x00\x00\
+            \x08\x00\x01a\x00\x02hi\
+            \x09\x00\x01b\x0a\x00\x00\x00\x01\
+                \x01\x00\x01x\x03\x01\x00\x01y\x02\x00\
+            \x01\x00\x01c\x01\
+            \x00";
+        assert_eq!(buffer, &expected[..]);
+    }
+
+    #[test]
+    fn test_heterogeneous_lists() {
+        // lists with a single element type are written as usual
+        let mut emitter = Emitter::new(Vec::new());
+        emitter.set_heterogeneous_lists(true);
+        emitter.begin_list("", nbt::TAG_I8, 2).unwrap();
+        emitter.write_i8("", 1).unwrap();
+        emitter.write_i8("", 2).unwrap();
+        emitter.end().unwrap();
+        assert_eq!(emitter.finish().unwrap(), b"\x09\x00\x00\x01\x00\x00\x00\x02\x01\x02");
+
+        // `[{"": 1b}, {"": 2s}]`
+        let mut emitter = Emitter::new(Vec::new());
+        emitter.set_heterogeneous_lists(true);
+        emitter.begin_list("", nbt::TAG_I8, 2).unwrap();
+        emitter.write_i8("", 1).unwrap();
+        emitter.write_i16("", 2).unwrap();
+        emitter.end().unwrap();
+        let expected = b"\x09\x00\x00\x0a\x00\x00\x00\x02\x01\x00\x00\x01\x00\x02\x00\x00\x00\x02\x00";
+        assert_eq!(emitter.finish().unwrap(), &expected[..]);
+
+        let mut emitter = Emitter::new(Vec::new());
+        emitter.set_heterogeneous_lists(true);
+        emitter.begin_list("", nbt::TAG_I8, 2).unwrap();
+        emitter.write_i8("", 1).unwrap();
+        assert!(matches!(emitter.end(), Err(Error::ListLengthError { expected: 2, actual: 1 })));
+    }
+
+    #[test]
+    fn test_buffer_limit() {
+        let mut emitter = Emitter::new(Vec::new());
+        emitter.set_buffer_limit(16);
+        emitter.begin_buffered_list("", nbt::TAG_I8_ARRAY).unwrap();
+        emitter.write_i8_array("", &[0; 12]).unwrap();
+        emitter.end().unwrap();
+        assert!(emitter.finish().is_ok());
+
+        // a single write larger than the limit
+        let mut emitter = Emitter::new(Vec::new());
+        emitter.set_buffer_limit(16);
+        emitter.begin_buffered_list("", nbt::TAG_I8_ARRAY).unwrap();
+        assert!(matches!(emitter.write_i8_array("", &[0; 100]), Err(Error::BufferLimitError)));
+    }
+
+    #[test]
+    fn test_wrong_element_type() {
+        let mut emitter = Emitter::new(Vec::new());
+        emitter.begin_list("", nbt::TAG_I32, 1).unwrap();
+        assert!(matches!(
+            emitter.write_i64("", 1),
+            Err(Error::ListElementTypeError { list: nbt::TAG_I32, element: nbt::TAG_I64 })
+        ));
+
+        // empty lists are the only ones without an element type
+        let mut emitter = Emitter::new(Vec::new());
+        assert!(matches!(emitter.begin_list("", nbt::TAG_END, 1), Err(Error::InvalidTagTypeError)));
+    }
+
+    #[test]
+    fn test_list_length() {
+        let mut emitter = Emitter::new(Vec::new());
+        emitter.begin_list("", nbt::TAG_I8, 1).unwrap();
+        emitter.write_i8("", 1).unwrap();
+        assert!(matches!(emitter.write_i8("", 2), Err(Error::ListLengthError { expected: 1, actual: 2 })));
+
+        let mut emitter = Emitter::new(Vec::new());
+        emitter.begin_list("", nbt::TAG_I8, 2).unwrap();
+        emitter.write_i8("", 1).unwrap();
+        assert!(matches!(emitter.end(), Err(Error::ListLengthError { expected: 2, actual: 1 })));
+
+        // the same for lists which are buffered
+        let mut emitter = Emitter::new(Vec::new());
+        emitter.set_heterogeneous_lists(true);
+        emitter.begin_list("", nbt::TAG_I8, 1).unwrap();
+        emitter.write_i8("", 1).unwrap();
+        assert!(matches!(emitter.write_i16("", 2), Err(Error::ListLengthError { expected: 1, actual: 2 })));
+    }
+
+    #[test]
+    fn test_missing_end() {
+        let mut emitter = Emitter::new(Vec::new());
+        emitter.begin_compound("").unwrap();
+        assert!(matches!(emitter.finish(), Err(Error::InvalidEmitterStateError)));
+
+        let mut emitter = Emitter::new(Vec::new());
+        assert!(matches!(emitter.end(), Err(Error::InvalidEmitterStateError)));
+    }
+}
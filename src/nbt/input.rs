@@ -0,0 +1,169 @@
+use std::borrow::Cow;
+use std::convert::TryFrom;
+use std::io::{self, Read};
+
+/// source of the bytes read by the parser
+///
+/// Any `std::io::Read` can be used as an input, the data it returns is always
+/// copied. `SliceInput` reads from a byte slice and lends out borrowed strings
+/// and byte arrays for as long as the slice lives. `SeekInput` seeks over
+/// skipped values instead of reading them.
+pub trait Input<'de> {
+    /// fills `buf` with the next bytes from the input
+    fn read_exact(&mut self, buf: &mut [u8]) -> io::Result<()>;
+
+    /// reads the next `len` bytes, borrowing them from the input if possible
+    fn read_bytes(&mut self, len: usize) -> io::Result<Cow<'de, [u8]>>;
+
+    /// moves past the next `len` bytes without keeping them
+    fn skip(&mut self, len: usize) -> io::Result<()>;
+}
+
+impl<'de, R> Input<'de> for R
+where
+    R: io::Read,
+{
+    fn read_exact(&mut self, buf: &mut [u8]) -> io::Result<()> {
+        io::Read::read_exact(self, buf)
+    }
+
+    fn read_bytes(&mut self, len: usize) -> io::Result<Cow<'de, [u8]>> {
+        read_bytes_from(self, len).map(Cow::Owned)
+    }
+
+    fn skip(&mut self, len: usize) -> io::Result<()> {
+        skip_from(self, len)
+    }
+}
+
+/// input which seeks over skipped values instead of reading them
+///
+/// Seeking past the end of the input does not fail, truncated input is only
+/// noticed by the next read.
+pub struct SeekInput<R> {
+    reader: R,
+}
+
+impl<R> SeekInput<R>
+where
+    R: io::Read + io::Seek,
+{
+    pub fn new(reader: R) -> Self {
+        SeekInput { reader }
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<'de, R> Input<'de> for SeekInput<R>
+where
+    R: io::Read + io::Seek,
+{
+    fn read_exact(&mut self, buf: &mut [u8]) -> io::Result<()> {
+        io::Read::read_exact(&mut self.reader, buf)
+    }
+
+    fn read_bytes(&mut self, len: usize) -> io::Result<Cow<'de, [u8]>> {
+        read_bytes_from(&mut self.reader, len).map(Cow::Owned)
+    }
+
+    fn skip(&mut self, len: usize) -> io::Result<()> {
+        let offset = i64::try_from(len).map_err(|_| io::Error::from(io::ErrorKind::InvalidInput))?;
+        self.reader.seek(io::SeekFrom::Current(offset))?;
+        Ok(())
+    }
+}
+
+/// reads `len` bytes into a new buffer
+fn read_bytes_from<R>(reader: R, len: usize) -> io::Result<Vec<u8>>
+where
+    R: io::Read,
+{
+    // grow the buffer as data arrives instead of trusting `len` up front,
+    // a bogus length then fails at the end of the input
+    let mut value = Vec::new();
+    reader.take(len as u64).read_to_end(&mut value)?;
+    if value.len() != len {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+    Ok(value)
+}
+
+/// reads and discards `len` bytes through a small scratch buffer
+fn skip_from<R>(reader: R, len: usize) -> io::Result<()>
+where
+    R: io::Read,
+{
+    let skipped = io::copy(&mut reader.take(len as u64), &mut io::sink())?;
+    if skipped != len as u64 {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+    Ok(())
+}
+
+/// input which borrows strings and byte arrays from a byte slice
+pub struct SliceInput<'de> {
+    slice: &'de [u8],
+}
+
+impl<'de> SliceInput<'de> {
+    pub fn new(slice: &'de [u8]) -> Self {
+        SliceInput { slice }
+    }
+
+    /// the part of the slice which has not been read yet
+    pub fn remaining(&self) -> &'de [u8] {
+        self.slice
+    }
+
+    fn take(&mut self, len: usize) -> io::Result<&'de [u8]> {
+        if len > self.slice.len() {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+
+        let (value, rest) = self.slice.split_at(len);
+        self.slice = rest;
+        Ok(value)
+    }
+}
+
+impl<'de> Input<'de> for SliceInput<'de> {
+    fn read_exact(&mut self, buf: &mut [u8]) -> io::Result<()> {
+        let value = self.take(buf.len())?;
+        buf.copy_from_slice(value);
+        Ok(())
+    }
+
+    fn read_bytes(&mut self, len: usize) -> io::Result<Cow<'de, [u8]>> {
+        self.take(len).map(Cow::Borrowed)
+    }
+
+    fn skip(&mut self, len: usize) -> io::Result<()> {
+        self.take(len).map(|_| ())
+    }
+}
+
+/// a value that was either borrowed from the input or from the parser's state
+pub enum Reference<'b, 'c, T>
+where
+    T: ?Sized,
+{
+    Borrowed(&'b T),
+    Copied(&'c T),
+}
+
+impl<'b, 'c, T> std::ops::Deref for Reference<'b, 'c, T>
+where
+    T: ?Sized,
+{
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        match self {
+            Reference::Borrowed(value) => value,
+            Reference::Copied(value) => value,
+        }
+    }
+}
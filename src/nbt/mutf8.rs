@@ -0,0 +1,114 @@
+// Java's modified UTF-8, as read by `DataInput.readUTF` and written by
+// `DataOutput.writeUTF`
+//
+// It differs from UTF-8 in writing `\0` as the two bytes `0xc0 0x80` and
+// characters outside of the basic multilingual plane as a surrogate pair of
+// three bytes each. Any other string has the same bytes in both encodings.
+
+use std::borrow::Cow;
+use crate::error::{Error, Result};
+
+/// decodes a modified UTF-8 string, which is only copied if it is not also
+/// valid UTF-8
+pub fn decode(bytes: Cow<'_, [u8]>) -> Result<Cow<'_, str>> {
+    match bytes {
+        Cow::Borrowed(bytes) => match std::str::from_utf8(bytes) {
+            Ok(value) => Ok(Cow::Borrowed(value)),
+            Err(_) => decode_utf16(bytes).map(Cow::Owned),
+        },
+        Cow::Owned(bytes) => match String::from_utf8(bytes) {
+            Ok(value) => Ok(Cow::Owned(value)),
+            Err(e) => decode_utf16(e.as_bytes()).map(Cow::Owned),
+        },
+    }
+}
+
+/// encodes `value` as modified UTF-8, which is only copied if it contains
+/// `\0` or characters outside of the basic multilingual plane
+pub fn encode(value: &str) -> Cow<'_, [u8]> {
+    if !value.chars().any(|c| c == '\0' || c.len_utf16() > 1) {
+        return Cow::Borrowed(value.as_bytes());
+    }
+
+    let mut bytes = Vec::with_capacity(value.len() + 2);
+    for unit in value.encode_utf16() {
+        match unit {
+            0x0001..=0x007f => bytes.push(unit as u8),
+            0x0000 | 0x0080..=0x07ff => {
+                bytes.push(0xc0 | (unit >> 6) as u8);
+                bytes.push(0x80 | (unit & 0x3f) as u8);
+            },
+            _ => {
+                bytes.push(0xe0 | (unit >> 12) as u8);
+                bytes.push(0x80 | ((unit >> 6) & 0x3f) as u8);
+                bytes.push(0x80 | (unit & 0x3f) as u8);
+            },
+        }
+    }
+    Cow::Owned(bytes)
+}
+
+/// decodes the UTF-16 code units of a modified UTF-8 string one at a time
+fn decode_utf16(bytes: &[u8]) -> Result<String> {
+    let mut units = Vec::with_capacity(bytes.len());
+    let mut iter = bytes.iter();
+
+    while let Some(&first) = iter.next() {
+        let unit = match first {
+            0x00..=0x7f => first as u16,
+            0xc0..=0xdf => {
+                let second = continuation(iter.next())?;
+                (first as u16 & 0x1f) << 6 | second
+            },
+            0xe0..=0xef => {
+                let second = continuation(iter.next())?;
+                let third = continuation(iter.next())?;
+                (first as u16 & 0x0f) << 12 | second << 6 | third
+            },
+            _ => return Err(Error::ModifiedUtf8Error),
+        };
+        units.push(unit);
+    }
+
+    // unpaired surrogates are valid in Java strings but not in Rust's
+    String::from_utf16(&units).map_err(|_| Error::ModifiedUtf8Error)
+}
+
+/// returns the six bits of a continuation byte
+fn continuation(byte: Option<&u8>) -> Result<u16> {
+    match byte {
+        Some(&byte) if byte & 0xc0 == 0x80 => Ok(byte as u16 & 0x3f),
+        _ => Err(Error::ModifiedUtf8Error),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plain_strings_are_not_copied() {
+        assert!(matches!(encode("Level"), Cow::Borrowed(b"Level")));
+        assert!(matches!(encode("é"), Cow::Borrowed(_)));
+        assert!(matches!(decode(Cow::Borrowed(b"Level")).unwrap(), Cow::Borrowed("Level")));
+    }
+
+    #[test]
+    fn test_nul_and_supplementary_characters() {
+        // `\0` and U+1F600 as a surrogate pair
+        let bytes = b"a\xc0\x80\xed\xa0\xbd\xed\xb8\x80";
+        assert_eq!(encode("a\0\u{1f600}"), &bytes[..]);
+        assert_eq!(decode(Cow::Borrowed(&bytes[..])).unwrap(), "a\0\u{1f600}");
+        assert_eq!(decode(Cow::Owned(bytes.to_vec())).unwrap(), "a\0\u{1f600}");
+    }
+
+    #[test]
+    fn test_invalid_input() {
+        // truncated sequence
+        assert!(decode(Cow::Borrowed(b"\xe0\x80")).is_err());
+        // unpaired surrogate
+        assert!(decode(Cow::Borrowed(b"\xed\xa0\xbd")).is_err());
+        // four byte UTF-8 mixed with modified UTF-8
+        assert!(decode(Cow::Borrowed(b"\xc0\x80\xf0\x9f\x98\x80")).is_err());
+    }
+}
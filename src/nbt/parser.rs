@@ -1,12 +1,38 @@
-use byteorder::{BigEndian, ReadBytesExt};
+use std::borrow::Cow;
+use byteorder::{BigEndian, ByteOrder as _, LittleEndian};
 use log::trace;
+use crate::config::{ByteOrder, StringEncoding};
 use crate::error::{Result, Error};
 use crate::nbt;
+use crate::nbt::input::{Input, Reference, SliceInput};
+use crate::nbt::mutf8;
 
-pub struct Parser<R> {
+pub struct Parser<'de, R> {
     input: R,
-    state: ParserState,
-    stack: Vec<ParserState>,
+    state: ParserState<'de>,
+    stack: Vec<ParserState<'de>>,
+    byte_order: ByteOrder,
+    string_encoding: StringEncoding,
+    // whether the root tag's header has a name
+    named_root: bool,
+    // maximum number of nested compounds and lists
+    max_depth: usize,
+    // number of bytes of strings, arrays and lists read so far, and the limit
+    size: usize,
+    size_quota: usize,
+    // whether compounds in lists holding only a tag named "" are read as
+    // that tag's value
+    heterogeneous_lists: bool,
+    // the header of a list element's first tag, which was read to tell
+    // whether the element is wrapped, for when the compound is entered
+    peeked_header: Option<ParserState<'de>>,
+    // bytes which were read ahead to tell whether a list element is wrapped,
+    // they are read again before the rest of the input. each read ahead adds
+    // a segment, along with how much of it has been read again, and the last
+    // segment is read first
+    replay: Vec<(Vec<u8>, usize)>,
+    // the bytes read while reading ahead
+    recording: Option<Vec<u8>>,
 }
 
 #[derive(Debug, PartialEq)]
@@ -25,12 +51,38 @@ pub enum ValueType {
     MapEnd,
 }
 
+/// an item read by iterating over a `Parser`
+#[derive(Clone, Debug, PartialEq)]
+pub enum Event<'de> {
+    /// the header of a tag inside a compound or at the root, its value follows
+    TagStart { name: Cow<'de, str>, ty: u8 },
+    I8(i8),
+    I16(i16),
+    I32(i32),
+    I64(i64),
+    F32(f32),
+    F64(f64),
+    String(Cow<'de, str>),
+    CompoundStart,
+    ListStart { ty: u8, len: usize },
+    /// one of the array types, its elements follow as `I8`, `I32` or `I64`
+    ArrayStart { ty: u8, len: usize },
+    /// the end of a compound, list or array
+    End,
+}
+
+/// compounds and lists that are being skipped over by `skip_value`
+enum Skipping {
+    Compound,
+    List { elem_type: u8, remaining: usize },
+}
+
 #[derive(Debug, PartialEq)]
-enum ParserState {
+enum ParserState<'de> {
     InvalidState,
     // states for tags
     ExpectingTag,
-    TagHeader { value_type: u8, name: String },
+    TagHeader { value_type: u8, name: Cow<'de, str> },
     TagEnd,
     TagValueI8 { value: i8 },
     TagValueI16 { value: i16 },
@@ -38,7 +90,7 @@ enum ParserState {
     TagValueI64 { value: i64 },
     TagValueF32 { value: f32 },
     TagValueF64 { value: f64 },
-    TagValueString { value: String },
+    TagValueString { value: Cow<'de, str> },
     // marker state, indicates that the parser will enter a compound type
     Compound,
     // states for array types
@@ -59,16 +111,28 @@ enum ParserState {
     ListValueI64 { remaining: usize, value: i64 },
     ListValueF32 { remaining: usize, value: f32 },
     ListValueF64 { remaining: usize, value: f64 },
+    // the `ListValue*` states for nested values are only kept on the stack, they
+    // mark where to resume the outer list after the nested value has been read
     ListValueI8Array { remaining: usize },
-    ListValueString { remaining: usize, value: String },
+    ListValueString { remaining: usize, value: Cow<'de, str> },
     ListValueList { remaining: usize },
     ListValueCompound { remaining: usize },
     ListValueI32Array { remaining: usize },
     ListValueI64Array { remaining: usize },
+    // an element of a heterogeneous list, whose wrapper compound has to end
+    // after the element's value
+    ListValueWrapped { remaining: usize },
     ListEnd,
 }
 
-impl<R> Parser<R> where R: std::io::Read {
+impl<'de> Parser<'de, SliceInput<'de>> {
+    /// creates a parser which borrows strings and byte arrays from `input`
+    pub fn from_slice(input: &'de [u8]) -> Self {
+        Parser::new(SliceInput::new(input))
+    }
+}
+
+impl<'de, R> Parser<'de, R> where R: Input<'de> {
     pub fn new(input: R) -> Self {
         #[cfg(debug_assertions)]
         trace!("Parser::new");
@@ -77,9 +141,65 @@ impl<R> Parser<R> where R: std::io::Read {
             input,
             state: ParserState::ExpectingTag,
             stack: Vec::new(),
+            byte_order: ByteOrder::default(),
+            string_encoding: StringEncoding::default(),
+            named_root: true,
+            max_depth: nbt::DEFAULT_MAX_DEPTH,
+            size: 0,
+            size_quota: usize::MAX,
+            heterogeneous_lists: false,
+            peeked_header: None,
+            replay: Vec::new(),
+            recording: None,
         }
     }
 
+    pub fn set_byte_order(&mut self, byte_order: ByteOrder) {
+        self.byte_order = byte_order;
+    }
+
+    pub fn set_string_encoding(&mut self, string_encoding: StringEncoding) {
+        self.string_encoding = string_encoding;
+    }
+
+    /// sets whether root tags have a name, network NBT leaves it out since
+    /// 1.20.2
+    pub fn set_named_root(&mut self, named_root: bool) {
+        self.named_root = named_root;
+    }
+
+    /// sets the maximum number of nested compounds and lists, more deeply
+    /// nested input fails with `Error::DepthLimitError`
+    pub fn set_max_depth(&mut self, max_depth: usize) {
+        self.max_depth = max_depth;
+    }
+
+    /// sets the maximum number of bytes of strings, arrays and lists which
+    /// are read, exceeding it fails with `Error::SizeLimitError`
+    ///
+    /// The lengths of values are checked before reading them, so input with
+    /// bogus lengths fails before large amounts of memory are allocated.
+    pub fn set_size_quota(&mut self, size_quota: usize) {
+        self.size_quota = size_quota;
+    }
+
+    /// sets whether compounds in lists holding only a tag named "" are read
+    /// as that tag's value, the encoding of lists with elements of different
+    /// types since 1.21.5
+    ///
+    /// To tell whether a compound whose first tag is named "" holds any other
+    /// tags, the first tag's value is read ahead and kept in memory until it
+    /// is read again.
+    pub fn set_heterogeneous_lists(&mut self, heterogeneous_lists: bool) {
+        self.heterogeneous_lists = heterogeneous_lists;
+    }
+
+    /// returns `true` before the first root tag and after the value of each
+    /// root tag, where `advance` reads the header of the next root tag
+    pub fn is_between_roots(&self) -> bool {
+        self.state == ParserState::ExpectingTag && self.stack.is_empty()
+    }
+
     pub fn get_value_type(&self) -> ValueType {
         #[cfg(debug_assertions)]
         trace!("Parser::get_value_type");
@@ -97,7 +217,7 @@ impl<R> Parser<R> where R: std::io::Read {
             ParserState::TagValueF64 { value: _ } => ValueType::F64,
             ParserState::TagValueString { value: _ } => ValueType::String,
             ParserState::Compound => ValueType::MapBegin,
-            ParserState::I8Array { len: _ } => ValueType::SeqEnd,
+            ParserState::I8Array { len: _ } => ValueType::SeqBegin,
             ParserState::I8ArrayValue { remaining: _, value: _ } => ValueType::I8,
             ParserState::I8ArrayEnd => ValueType::SeqEnd,
             ParserState::I32Array { len: _ } => ValueType::SeqBegin,
@@ -119,6 +239,7 @@ impl<R> Parser<R> where R: std::io::Read {
             ParserState::ListValueCompound { remaining: _ } => ValueType::Invalid,
             ParserState::ListValueI32Array { remaining: _ } => ValueType::Invalid,
             ParserState::ListValueI64Array { remaining: _ } => ValueType::Invalid,
+            ParserState::ListValueWrapped { remaining: _ } => ValueType::Invalid,
             ParserState::ListEnd => ValueType::SeqEnd,
         };
 
@@ -128,6 +249,93 @@ impl<R> Parser<R> where R: std::io::Read {
         value_type
     }
 
+    /// returns the NBT tag type of the current value, or of the value that
+    /// follows when the parser is positioned on a tag's header
+    pub fn get_tag_type(&self) -> u8 {
+        #[cfg(debug_assertions)]
+        trace!("Parser::get_tag_type");
+
+        match self.state {
+            ParserState::InvalidState => nbt::TAG_END,
+            ParserState::ExpectingTag => nbt::TAG_END,
+            ParserState::TagHeader { value_type, name: _ } => value_type,
+            ParserState::TagEnd => nbt::TAG_END,
+            ParserState::TagValueI8 { value: _ } => nbt::TAG_I8,
+            ParserState::TagValueI16 { value: _ } => nbt::TAG_I16,
+            ParserState::TagValueI32 { value: _ } => nbt::TAG_I32,
+            ParserState::TagValueI64 { value: _ } => nbt::TAG_I64,
+            ParserState::TagValueF32 { value: _ } => nbt::TAG_F32,
+            ParserState::TagValueF64 { value: _ } => nbt::TAG_F64,
+            ParserState::TagValueString { value: _ } => nbt::TAG_STRING,
+            ParserState::Compound => nbt::TAG_COMPOUND,
+            ParserState::I8Array { len: _ } => nbt::TAG_I8_ARRAY,
+            ParserState::I8ArrayValue { remaining: _, value: _ } => nbt::TAG_I8,
+            ParserState::I8ArrayEnd => nbt::TAG_END,
+            ParserState::I32Array { len: _ } => nbt::TAG_I32_ARRAY,
+            ParserState::I32ArrayValue { remaining: _, value: _ } => nbt::TAG_I32,
+            ParserState::I32ArrayEnd => nbt::TAG_END,
+            ParserState::I64Array { len: _ } => nbt::TAG_I64_ARRAY,
+            ParserState::I64ArrayValue { remaining: _, value: _ } => nbt::TAG_I64,
+            ParserState::I64ArrayEnd => nbt::TAG_END,
+            ParserState::List { len: _, elem_type: _ } => nbt::TAG_LIST,
+            ParserState::ListValueI8 { remaining: _, value: _ } => nbt::TAG_I8,
+            ParserState::ListValueI16 { remaining: _, value: _ } => nbt::TAG_I16,
+            ParserState::ListValueI32 { remaining: _, value: _ } => nbt::TAG_I32,
+            ParserState::ListValueI64 { remaining: _, value: _ } => nbt::TAG_I64,
+            ParserState::ListValueF32 { remaining: _, value: _ } => nbt::TAG_F32,
+            ParserState::ListValueF64 { remaining: _, value: _ } => nbt::TAG_F64,
+            ParserState::ListValueI8Array { remaining: _ } => nbt::TAG_I8_ARRAY,
+            ParserState::ListValueString { remaining: _, value: _ } => nbt::TAG_STRING,
+            ParserState::ListValueList { remaining: _ } => nbt::TAG_LIST,
+            ParserState::ListValueCompound { remaining: _ } => nbt::TAG_COMPOUND,
+            ParserState::ListValueI32Array { remaining: _ } => nbt::TAG_I32_ARRAY,
+            ParserState::ListValueI64Array { remaining: _ } => nbt::TAG_I64_ARRAY,
+            ParserState::ListValueWrapped { remaining: _ } => nbt::TAG_COMPOUND,
+            ParserState::ListEnd => nbt::TAG_END,
+        }
+    }
+
+    /// returns the number of values left in the list or array the parser is
+    /// in, including the current value, or `None` outside of lists and arrays
+    ///
+    /// The length comes from the input and has not been checked against the
+    /// amount of data that actually follows.
+    pub fn remaining_len(&self) -> Option<usize> {
+        #[cfg(debug_assertions)]
+        trace!("Parser::remaining_len");
+
+        match self.state {
+            ParserState::I8ArrayValue { remaining, value: _ }
+            | ParserState::I32ArrayValue { remaining, value: _ }
+            | ParserState::I64ArrayValue { remaining, value: _ }
+            | ParserState::ListValueI8 { remaining, value: _ }
+            | ParserState::ListValueI16 { remaining, value: _ }
+            | ParserState::ListValueI32 { remaining, value: _ }
+            | ParserState::ListValueI64 { remaining, value: _ }
+            | ParserState::ListValueF32 { remaining, value: _ }
+            | ParserState::ListValueF64 { remaining, value: _ }
+            | ParserState::ListValueString { remaining, value: _ } => Some(remaining + 1),
+            ParserState::I8ArrayEnd
+            | ParserState::I32ArrayEnd
+            | ParserState::I64ArrayEnd
+            | ParserState::ListEnd => Some(0),
+            // the start of a nested value, the position in the outer list is on the stack
+            ParserState::Compound
+            | ParserState::List { len: _, elem_type: _ }
+            | ParserState::I8Array { len: _ }
+            | ParserState::I32Array { len: _ }
+            | ParserState::I64Array { len: _ } => match self.stack.last() {
+                Some(ParserState::ListValueI8Array { remaining })
+                | Some(ParserState::ListValueList { remaining })
+                | Some(ParserState::ListValueCompound { remaining })
+                | Some(ParserState::ListValueI32Array { remaining })
+                | Some(ParserState::ListValueI64Array { remaining }) => Some(remaining + 1),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
     pub fn get_i8_value(&self) -> Result<i8> {
         #[cfg(debug_assertions)]
         trace!("Parser::get_i8_value");
@@ -202,43 +410,244 @@ impl<R> Parser<R> where R: std::io::Read {
         trace!("Parser::get_string_value");
 
         match &self.state {
-            ParserState::TagHeader { value_type: _, name } => Ok(name.clone()),
-            ParserState::TagValueString { value } => Ok(value.clone()),
-            ParserState::ListValueString { remaining: _, value } => Ok(value.clone()),
+            ParserState::TagHeader { value_type: _, name } => Ok(name.to_string()),
+            ParserState::TagValueString { value } => Ok(value.to_string()),
+            ParserState::ListValueString { remaining: _, value } => Ok(value.to_string()),
             _ => Err(Error::InvalidTagTypeError),
         }
     }
 
+    /// like `get_string_value`, but avoids copying the string, which is
+    /// borrowed from the input when the parser reads from a slice
+    pub fn get_str_value(&self) -> Result<Reference<'de, '_, str>> {
+        #[cfg(debug_assertions)]
+        trace!("Parser::get_str_value");
+
+        let value = match &self.state {
+            ParserState::TagHeader { value_type: _, name } => name,
+            ParserState::TagValueString { value } => value,
+            ParserState::ListValueString { remaining: _, value } => value,
+            _ => return Err(Error::InvalidTagTypeError),
+        };
+
+        match value {
+            Cow::Borrowed(value) => Ok(Reference::Borrowed(value)),
+            Cow::Owned(value) => Ok(Reference::Copied(value)),
+        }
+    }
+
+    /// reads all of a `TAG_I8_ARRAY`'s elements at once, borrowing them from
+    /// the input when the parser reads from a slice
+    ///
+    /// The parser must be positioned at the start of the array, afterwards it
+    /// is positioned at the array's end.
+    pub fn read_byte_array(&mut self) -> Result<Cow<'de, [u8]>> {
+        #[cfg(debug_assertions)]
+        trace!("Parser::read_byte_array");
+
+        let len = match self.state {
+            ParserState::I8Array { len } => len,
+            _ => return Err(Error::InvalidTagTypeError),
+        };
+
+        let value = self.read_input_bytes(len)?;
+        self.state = ParserState::I8ArrayEnd;
+        Ok(value)
+    }
+
+    /// reads all of a `TAG_I8_ARRAY`'s elements at once
+    ///
+    /// The parser must be positioned at the start of the array, afterwards it
+    /// is positioned at the array's end.
+    pub fn read_i8_array(&mut self) -> Result<Vec<i8>> {
+        let mut value = Vec::new();
+        self.read_i8_array_into(&mut value)?;
+        Ok(value)
+    }
+
+    /// like `read_i8_array`, but replaces the contents of `buf` instead of
+    /// allocating a new `Vec`
+    pub fn read_i8_array_into(&mut self, buf: &mut Vec<i8>) -> Result<()> {
+        #[cfg(debug_assertions)]
+        trace!("Parser::read_i8_array_into");
+
+        let value = self.read_byte_array()?;
+        buf.clear();
+        buf.extend(value.iter().map(|&value| value as i8));
+        Ok(())
+    }
+
+    /// reads all of a `TAG_I32_ARRAY`'s elements with a single read from the
+    /// input
+    ///
+    /// The parser must be positioned at the start of the array, afterwards it
+    /// is positioned at the array's end.
+    pub fn read_i32_array(&mut self) -> Result<Vec<i32>> {
+        let mut value = Vec::new();
+        self.read_i32_array_into(&mut value)?;
+        Ok(value)
+    }
+
+    /// like `read_i32_array`, but replaces the contents of `buf` instead of
+    /// allocating a new `Vec`
+    pub fn read_i32_array_into(&mut self, buf: &mut Vec<i32>) -> Result<()> {
+        #[cfg(debug_assertions)]
+        trace!("Parser::read_i32_array_into");
+
+        let len = match self.state {
+            ParserState::I32Array { len } => len,
+            _ => return Err(Error::InvalidTagTypeError),
+        };
+
+        let value = self.read_input_bytes(len.checked_mul(4).ok_or(Error::SizeLimitError)?)?;
+        buf.clear();
+        buf.resize(len, 0);
+        match self.byte_order {
+            ByteOrder::BigEndian => BigEndian::read_i32_into(&value, buf),
+            ByteOrder::LittleEndian => LittleEndian::read_i32_into(&value, buf),
+        }
+
+        self.state = ParserState::I32ArrayEnd;
+        Ok(())
+    }
+
+    /// reads all of a `TAG_I64_ARRAY`'s elements with a single read from the
+    /// input
+    ///
+    /// The parser must be positioned at the start of the array, afterwards it
+    /// is positioned at the array's end.
+    pub fn read_i64_array(&mut self) -> Result<Vec<i64>> {
+        let mut value = Vec::new();
+        self.read_i64_array_into(&mut value)?;
+        Ok(value)
+    }
+
+    /// like `read_i64_array`, but replaces the contents of `buf` instead of
+    /// allocating a new `Vec`
+    pub fn read_i64_array_into(&mut self, buf: &mut Vec<i64>) -> Result<()> {
+        #[cfg(debug_assertions)]
+        trace!("Parser::read_i64_array_into");
+
+        let len = match self.state {
+            ParserState::I64Array { len } => len,
+            _ => return Err(Error::InvalidTagTypeError),
+        };
+
+        let value = self.read_input_bytes(len.checked_mul(8).ok_or(Error::SizeLimitError)?)?;
+        buf.clear();
+        buf.resize(len, 0);
+        match self.byte_order {
+            ByteOrder::BigEndian => BigEndian::read_i64_into(&value, buf),
+            ByteOrder::LittleEndian => LittleEndian::read_i64_into(&value, buf),
+        }
+
+        self.state = ParserState::I64ArrayEnd;
+        Ok(())
+    }
+
+    /// skips the current value, including everything nested inside of it
+    ///
+    /// The parser is left at the end of the value, `advance` then moves on to
+    /// whatever follows it. Nested values are skipped without being parsed or
+    /// allocated. On a tag's header the tag's value is skipped.
+    pub fn skip_value(&mut self) -> Result<()> {
+        #[cfg(debug_assertions)]
+        trace!("Parser::skip_value");
+
+        if let ParserState::TagHeader { .. } = self.state {
+            self.advance()?;
+        }
+
+        match self.state {
+            ParserState::InvalidState | ParserState::ExpectingTag => Err(Error::InvalidParserStateError),
+            ParserState::Compound => {
+                // the first tag's header may have been read already
+                let value_type = match self.peeked_header.take() {
+                    Some(ParserState::TagHeader { value_type, name: _ }) => Some(value_type),
+                    Some(_) => {
+                        self.state = ParserState::TagEnd;
+                        return Ok(());
+                    },
+                    None => None,
+                };
+                self.skip_nested(Skipping::Compound, value_type)?;
+                self.state = ParserState::TagEnd;
+                Ok(())
+            },
+            ParserState::List { len, elem_type } => {
+                self.skip_nested(Skipping::List { elem_type, remaining: len }, None)?;
+                self.state = ParserState::ListEnd;
+                Ok(())
+            },
+            ParserState::I8Array { len } => {
+                self.skip_bytes(len, 1)?;
+                self.state = ParserState::I8ArrayEnd;
+                Ok(())
+            },
+            ParserState::I32Array { len } => {
+                self.skip_bytes(len, 4)?;
+                self.state = ParserState::I32ArrayEnd;
+                Ok(())
+            },
+            ParserState::I64Array { len } => {
+                self.skip_bytes(len, 8)?;
+                self.state = ParserState::I64ArrayEnd;
+                Ok(())
+            },
+            // any other value has already been read
+            _ => Ok(()),
+        }
+    }
+
     /// reads the next value from the parser's input
-    pub fn next(&mut self) -> Result<()> {
+    ///
+    /// After the value of a root tag the parser stops between root tags, the
+    /// next call then reads the following root tag's header or fails with
+    /// `Error::Eof` at the end of the input.
+    pub fn advance(&mut self) -> Result<()> {
         #[cfg(debug_assertions)]
-        trace!("Parser::next");
+        trace!("Parser::advance");
+
+        // leaving a compound, list or array restores the state from before it
+        // was entered, the next value is then read from the restored state
+        if let ParserState::TagEnd
+            | ParserState::I8ArrayEnd
+            | ParserState::I32ArrayEnd
+            | ParserState::I64ArrayEnd
+            | ParserState::ListEnd = self.state
+        {
+            self.restore_state_from_stack()?;
+            if self.is_between_roots() {
+                return Ok(());
+            }
+        }
 
         match self.state {
             ParserState::InvalidState => Err(Error::InvalidParserStateError),
             // states for tags
             ParserState::ExpectingTag => self.next_tag_header(),
             ParserState::TagHeader { value_type, name: _ } => self.next_tag_value(value_type),
-            ParserState::TagEnd => self.next_state_from_stack(),
-            ParserState::TagValueI8 { value: _ } => self.next_tag_header(),
-            ParserState::TagValueI16 { value: _ } => self.next_tag_header(),
-            ParserState::TagValueI32 { value: _ } => self.next_tag_header(),
-            ParserState::TagValueI64 { value: _ } => self.next_tag_header(),
-            ParserState::TagValueF32 { value: _ } => self.next_tag_header(),
-            ParserState::TagValueF64 { value: _ } => self.next_tag_header(),
-            ParserState::TagValueString { value: _ } => self.next_tag_header(),
+            // end states have already been replaced by the state from the stack
+            ParserState::TagEnd => Err(Error::InvalidParserStateError),
+            ParserState::TagValueI8 { value: _ } => self.next_tag(),
+            ParserState::TagValueI16 { value: _ } => self.next_tag(),
+            ParserState::TagValueI32 { value: _ } => self.next_tag(),
+            ParserState::TagValueI64 { value: _ } => self.next_tag(),
+            ParserState::TagValueF32 { value: _ } => self.next_tag(),
+            ParserState::TagValueF64 { value: _ } => self.next_tag(),
+            ParserState::TagValueString { value: _ } => self.next_tag(),
             // states for compound types
             ParserState::Compound => self.next_compound(),
             // states for array types
             ParserState::I8Array { len } => self.next_i8_array_value(len),
             ParserState::I8ArrayValue { remaining, value: _ } => self.next_i8_array_value(remaining),
-            ParserState::I8ArrayEnd => self.next_state_from_stack(),
+            ParserState::I8ArrayEnd => Err(Error::InvalidParserStateError),
             ParserState::I32Array { len } => self.next_i32_array_value(len),
             ParserState::I32ArrayValue { remaining, value: _ } => self.next_i32_array_value(remaining),
-            ParserState::I32ArrayEnd => self.next_state_from_stack(),
+            ParserState::I32ArrayEnd => Err(Error::InvalidParserStateError),
             ParserState::I64Array { len } => self.next_i64_array_value(len),
             ParserState::I64ArrayValue { remaining, value: _ } => self.next_i64_array_value(remaining),
-            ParserState::I64ArrayEnd => self.next_state_from_stack(),
+            ParserState::I64ArrayEnd => Err(Error::InvalidParserStateError),
             // states for lists and positions within lists
             ParserState::List { len, elem_type } => self.next_list(len, elem_type),
             ParserState::ListValueI8 { remaining, value: _ } => self.next_list_value_i8(remaining),
@@ -253,16 +662,31 @@ impl<R> Parser<R> where R: std::io::Read {
             ParserState::ListValueCompound { remaining } => self.next_list_value_compound(remaining),
             ParserState::ListValueI32Array { remaining } => self.next_list_value_i32_array(remaining),
             ParserState::ListValueI64Array { remaining } => self.next_list_value_i64_array(remaining),
-            ParserState::ListEnd => self.next_state_from_stack(),
+            ParserState::ListValueWrapped { remaining: _ } => Err(Error::InvalidParserStateError),
+            ParserState::ListEnd => Err(Error::InvalidParserStateError),
+        }
+    }
+
+    /// reads the header of the next tag inside a compound, at the root the
+    /// parser stops between tags instead
+    fn next_tag(&mut self) -> Result<()> {
+        #[cfg(debug_assertions)]
+        trace!("Parser::next_tag");
+
+        if self.stack.is_empty() {
+            self.state = ParserState::ExpectingTag;
+            return Ok(());
         }
+        self.next_tag_header()
     }
 
     fn next_tag_header(&mut self) -> Result<()> {
         #[cfg(debug_assertions)]
         trace!("Parser::next_tag_header");
 
-        let value_type = match self.input.read_u8() {
-            Ok(value) => value,
+        let mut buf = [0; 1];
+        let value_type = match self.read_input(&mut buf) {
+            Ok(()) => buf[0],
             Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => {
                 return Err(Error::Eof);
             },
@@ -271,13 +695,28 @@ impl<R> Parser<R> where R: std::io::Read {
             },
         };
 
+        // the wrapper of a heterogeneous list's element holds a single tag,
+        // which was made sure of by reading ahead, the list continues after it
+        if let Some(&ParserState::ListValueWrapped { remaining }) = self.stack.last() {
+            if value_type != nbt::TAG_END {
+                self.state = ParserState::InvalidState;
+                return Err(Error::InvalidParserStateError);
+            }
+            self.stack.pop();
+            return self.next_list_value_compound(remaining);
+        }
+
         // `TAG_END` is a special case, indicates the end of a compound type
         if value_type == nbt::TAG_END {
             self.state = ParserState::TagEnd;
             return Ok(())
         }
 
-        let name = self.read_nbt_string()?;
+        let name = if self.stack.is_empty() && !self.named_root {
+            Cow::Borrowed("")
+        } else {
+            self.read_nbt_string()?
+        };
         self.state = ParserState::TagHeader { value_type, name };
         Ok(())
     }
@@ -309,7 +748,7 @@ impl<R> Parser<R> where R: std::io::Read {
         #[cfg(debug_assertions)]
         trace!("Parser::next_tag_value_i8");
 
-        let value = self.input.read_i8()?;
+        let value = self.read_i8()?;
         self.state = ParserState::TagValueI8 { value };
         Ok(())
     }
@@ -318,7 +757,7 @@ impl<R> Parser<R> where R: std::io::Read {
         #[cfg(debug_assertions)]
         trace!("Parser::next_tag_value_i16");
 
-        let value = self.input.read_i16::<BigEndian>()?;
+        let value = self.read_i16()?;
         self.state = ParserState::TagValueI16 { value };
         Ok(())
     }
@@ -327,7 +766,7 @@ impl<R> Parser<R> where R: std::io::Read {
         #[cfg(debug_assertions)]
         trace!("Parser::next_tag_value_i32");
 
-        let value = self.input.read_i32::<BigEndian>()?;
+        let value = self.read_i32()?;
         self.state = ParserState::TagValueI32 { value };
         Ok(())
     }
@@ -336,7 +775,7 @@ impl<R> Parser<R> where R: std::io::Read {
         #[cfg(debug_assertions)]
         trace!("Parser::next_tag_value_i64");
 
-        let value = self.input.read_i64::<BigEndian>()?;
+        let value = self.read_i64()?;
         self.state = ParserState::TagValueI64 { value };
         Ok(())
     }
@@ -345,7 +784,7 @@ impl<R> Parser<R> where R: std::io::Read {
         #[cfg(debug_assertions)]
         trace!("Parser::next_tag_value_f32");
 
-        let value = self.input.read_f32::<BigEndian>()?;
+        let value = self.read_f32()?;
         self.state = ParserState::TagValueF32 { value };
         Ok(())
     }
@@ -354,7 +793,7 @@ impl<R> Parser<R> where R: std::io::Read {
         #[cfg(debug_assertions)]
         trace!("Parser::next_tag_value_f64");
 
-        let value = self.input.read_f64::<BigEndian>()?;
+        let value = self.read_f64()?;
         self.state = ParserState::TagValueF64 { value };
         Ok(())
     }
@@ -373,7 +812,7 @@ impl<R> Parser<R> where R: std::io::Read {
         trace!("Parser::next_tag_value_list");
 
         // return to parsing tags after parsing the list
-        self.stack.push(ParserState::ExpectingTag);
+        self.enter(ParserState::ExpectingTag)?;
         // parse the list value
         self.next_value_list()
     }
@@ -383,7 +822,7 @@ impl<R> Parser<R> where R: std::io::Read {
         trace!("Parser::next_tag_value_compound");
 
         // the parser should expect another tag after this compound type
-        self.stack.push(ParserState::ExpectingTag);
+        self.enter(ParserState::ExpectingTag)?;
         // "marker" to show that the parser will read tags inside a compound value
         self.state = ParserState::Compound;
         Ok(())
@@ -426,6 +865,13 @@ impl<R> Parser<R> where R: std::io::Read {
         #[cfg(debug_assertions)]
         trace!("Parser::next_tag_value_compound");
 
+        // the first tag's header was read already for list elements that
+        // turned out not to be wrapped
+        if let Some(state) = self.peeked_header.take() {
+            self.state = state;
+            return Ok(());
+        }
+
         // a compound is a bunch of tags, followed by a `TAG_END` tag
         // read the header of the first tag
         self.next_tag_header()
@@ -437,6 +883,7 @@ impl<R> Parser<R> where R: std::io::Read {
         trace!("Parser::next_value_i8_array");
 
         let len = self.read_i32_list_len()?;
+        self.account(len, 1)?;
         self.state = ParserState::I8Array { len };
         Ok(())
     }
@@ -445,13 +892,13 @@ impl<R> Parser<R> where R: std::io::Read {
         #[cfg(debug_assertions)]
         trace!("Parser::next_i8_array_value");
 
-        if remaining <= 0 {
+        if remaining == 0 {
             self.state = ParserState::I8ArrayEnd;
             return Ok(());
         }
 
         let remaining = remaining - 1;
-        let value = self.input.read_i8()?;
+        let value = self.read_i8()?;
         self.state = ParserState::I8ArrayValue { remaining, value };
         Ok(())
     }
@@ -462,6 +909,7 @@ impl<R> Parser<R> where R: std::io::Read {
         trace!("Parser::next_value_i32_array");
 
         let len = self.read_i32_list_len()?;
+        self.account(len, 4)?;
         self.state = ParserState::I32Array { len };
         Ok(())
     }
@@ -470,13 +918,13 @@ impl<R> Parser<R> where R: std::io::Read {
         #[cfg(debug_assertions)]
         trace!("Parser::next_i32_array_value");
 
-        if remaining <= 0 {
+        if remaining == 0 {
             self.state = ParserState::I32ArrayEnd;
             return Ok(());
         }
 
         let remaining = remaining - 1;
-        let value = self.input.read_i32::<BigEndian>()?;
+        let value = self.read_i32()?;
         self.state = ParserState::I32ArrayValue { remaining, value };
         Ok(())
     }
@@ -487,6 +935,7 @@ impl<R> Parser<R> where R: std::io::Read {
         trace!("Parser::next_value_i64_array");
 
         let len = self.read_i32_list_len()?;
+        self.account(len, 8)?;
         self.state = ParserState::I64Array { len };
         Ok(())
     }
@@ -495,13 +944,13 @@ impl<R> Parser<R> where R: std::io::Read {
         #[cfg(debug_assertions)]
         trace!("Parser::next_i64_array_value");
 
-        if remaining <= 0 {
+        if remaining == 0 {
             self.state = ParserState::I64ArrayEnd;
             return Ok(());
         }
 
         let remaining = remaining - 1;
-        let value = self.input.read_i64::<BigEndian>()?;
+        let value = self.read_i64()?;
         self.state = ParserState::I64ArrayValue { remaining, value };
         Ok(())
     }
@@ -511,8 +960,13 @@ impl<R> Parser<R> where R: std::io::Read {
         #[cfg(debug_assertions)]
         trace!("Parser::next_value_list");
 
-        let elem_type = self.input.read_u8()?;
+        let elem_type = self.read_u8()?;
         let len = self.read_i32_list_len()?;
+        if let Err(e) = check_list_header(elem_type, len) {
+            self.state = ParserState::InvalidState;
+            return Err(e);
+        }
+        self.account(len, nbt::min_value_size(elem_type))?;
         self.state = ParserState::List { len, elem_type };
         Ok(())
     }
@@ -521,7 +975,7 @@ impl<R> Parser<R> where R: std::io::Read {
         #[cfg(debug_assertions)]
         trace!("Parser::next_list");
 
-        if len <= 0 {
+        if len == 0 {
             // ignore `elem_type` to avoid issues:
             // https://minecraft.gamepedia.com/NBT_format#Usage
             self.state = ParserState::ListEnd;
@@ -553,13 +1007,13 @@ impl<R> Parser<R> where R: std::io::Read {
         #[cfg(debug_assertions)]
         trace!("Parser::next_list_value_i8");
 
-        if remaining <= 0 {
+        if remaining == 0 {
             self.state = ParserState::ListEnd;
             return Ok(());
         }
 
         let remaining = remaining - 1;
-        let value = self.input.read_i8()?;
+        let value = self.read_i8()?;
         self.state = ParserState::ListValueI8 { remaining, value };
         Ok(())
     }
@@ -568,13 +1022,13 @@ impl<R> Parser<R> where R: std::io::Read {
         #[cfg(debug_assertions)]
         trace!("Parser::next_list_value_i16");
 
-        if remaining <= 0 {
+        if remaining == 0 {
             self.state = ParserState::ListEnd;
             return Ok(());
         }
 
         let remaining = remaining - 1;
-        let value = self.input.read_i16::<BigEndian>()?;
+        let value = self.read_i16()?;
         self.state = ParserState::ListValueI16 { remaining, value };
         Ok(())
     }
@@ -583,13 +1037,13 @@ impl<R> Parser<R> where R: std::io::Read {
         #[cfg(debug_assertions)]
         trace!("Parser::next_list_value_i32");
 
-        if remaining <= 0 {
+        if remaining == 0 {
             self.state = ParserState::ListEnd;
             return Ok(());
         }
 
         let remaining = remaining - 1;
-        let value = self.input.read_i32::<BigEndian>()?;
+        let value = self.read_i32()?;
         self.state = ParserState::ListValueI32 { remaining, value };
         Ok(())
     }
@@ -598,13 +1052,13 @@ impl<R> Parser<R> where R: std::io::Read {
         #[cfg(debug_assertions)]
         trace!("Parser::next_list_value_i64");
 
-        if remaining <= 0 {
+        if remaining == 0 {
             self.state = ParserState::ListEnd;
             return Ok(());
         }
 
         let remaining = remaining - 1;
-        let value = self.input.read_i64::<BigEndian>()?;
+        let value = self.read_i64()?;
         self.state = ParserState::ListValueI64 { remaining, value };
         Ok(())
     }
@@ -613,13 +1067,13 @@ impl<R> Parser<R> where R: std::io::Read {
         #[cfg(debug_assertions)]
         trace!("Parser::next_list_value_f32");
 
-        if remaining <= 0 {
+        if remaining == 0 {
             self.state = ParserState::ListEnd;
             return Ok(());
         }
 
         let remaining = remaining - 1;
-        let value = self.input.read_f32::<BigEndian>()?;
+        let value = self.read_f32()?;
         self.state = ParserState::ListValueF32 { remaining, value };
         Ok(())
     }
@@ -628,13 +1082,13 @@ impl<R> Parser<R> where R: std::io::Read {
         #[cfg(debug_assertions)]
         trace!("Parser::next_list_value_f64");
 
-        if remaining <= 0 {
+        if remaining == 0 {
             self.state = ParserState::ListEnd;
             return Ok(());
         }
 
         let remaining = remaining - 1;
-        let value = self.input.read_f64::<BigEndian>()?;
+        let value = self.read_f64()?;
         self.state = ParserState::ListValueF64 { remaining, value };
         Ok(())
     }
@@ -643,14 +1097,14 @@ impl<R> Parser<R> where R: std::io::Read {
         #[cfg(debug_assertions)]
         trace!("Parser::next_list_value_i8_array");
 
-        if remaining <= 0 {
+        if remaining == 0 {
             self.state = ParserState::ListEnd;
             return Ok(());
         }
 
         // return to parsing this list after parsing the TAG_I8_ARRAY
         let remaining = remaining - 1;
-        self.state = ParserState::ListValueI8Array { remaining };
+        self.stack.push(ParserState::ListValueI8Array { remaining });
 
         // parse the TAG_I8_ARRAY
         self.next_value_i8_array()
@@ -660,7 +1114,7 @@ impl<R> Parser<R> where R: std::io::Read {
         #[cfg(debug_assertions)]
         trace!("Parser::next_list_value_string");
 
-        if remaining <= 0 {
+        if remaining == 0 {
             self.state = ParserState::ListEnd;
             return Ok(());
         }
@@ -675,14 +1129,14 @@ impl<R> Parser<R> where R: std::io::Read {
         #[cfg(debug_assertions)]
         trace!("Parser::next_list_value_list");
 
-        if remaining <= 0 {
+        if remaining == 0 {
             self.state = ParserState::ListEnd;
             return Ok(());
         }
 
         // return to current list after parsing the contained list
         let remaining = remaining - 1;
-        self.stack.push(ParserState::ListValueList { remaining });
+        self.enter(ParserState::ListValueList { remaining })?;
 
         // parse the list (as the current list's value)
         self.next_value_list()
@@ -692,14 +1146,29 @@ impl<R> Parser<R> where R: std::io::Read {
         #[cfg(debug_assertions)]
         trace!("Parser::next_list_value_compound");
 
-        if remaining <= 0 {
+        if remaining == 0 {
             self.state = ParserState::ListEnd;
             return Ok(());
         }
 
         // return to parsing this list after parsing the compound value
         let remaining = remaining - 1;
-        self.stack.push(ParserState::ListValueCompound { remaining });
+        self.enter(ParserState::ListValueCompound { remaining })?;
+
+        if self.heterogeneous_lists {
+            // a compound holding only a tag named "" wraps the element's
+            // value, the value is then read in place of the compound
+            self.next_tag_header()?;
+            let state = std::mem::replace(&mut self.state, ParserState::Compound);
+            match state {
+                ParserState::TagHeader { value_type, name } if name.is_empty() && self.read_ahead(value_type)? => {
+                    self.stack.pop();
+                    self.stack.push(ParserState::ListValueWrapped { remaining });
+                    return self.next_tag_value(value_type);
+                },
+                state => self.peeked_header = Some(state),
+            }
+        }
 
         // parse the compound next
         self.state = ParserState::Compound;
@@ -710,7 +1179,7 @@ impl<R> Parser<R> where R: std::io::Read {
         #[cfg(debug_assertions)]
         trace!("Parser::next_list_value_i32_array");
 
-        if remaining <= 0 {
+        if remaining == 0 {
             self.state = ParserState::ListEnd;
             return Ok(());
         }
@@ -727,7 +1196,7 @@ impl<R> Parser<R> where R: std::io::Read {
         #[cfg(debug_assertions)]
         trace!("Parser::next_list_value_i64_array");
 
-        if remaining <= 0 {
+        if remaining == 0 {
             self.state = ParserState::ListEnd;
             return Ok(());
         }
@@ -740,18 +1209,265 @@ impl<R> Parser<R> where R: std::io::Read {
         self.next_value_i64_array()
     }
 
-    /// restore parser's state from the stack and continue parsing
-    fn next_state_from_stack(&mut self) -> Result<()> {
+    /// counts `len` values of `size` bytes each against the size quota
+    fn account(&mut self, len: usize, size: usize) -> Result<()> {
+        #[cfg(debug_assertions)]
+        trace!("Parser::account({:?}, {:?})", len, size);
+
+        self.size = len.checked_mul(size)
+            .and_then(|bytes| self.size.checked_add(bytes))
+            .filter(|&total| total <= self.size_quota)
+            .ok_or_else(|| {
+                self.state = ParserState::InvalidState;
+                Error::SizeLimitError
+            })?;
+        Ok(())
+    }
+
+    /// skips the contents of a compound or list whose header has been read,
+    /// `first_tag` is the type of the compound's first tag if its header
+    /// has been read too
+    fn skip_nested(&mut self, skipping: Skipping, mut first_tag: Option<u8>) -> Result<()> {
+        #[cfg(debug_assertions)]
+        trace!("Parser::skip_nested");
+
+        // values are skipped in a loop rather than recursively, so that deeply
+        // nested input can not overflow the call stack
+        let mut stack = vec![skipping];
+
+        loop {
+            let value_type = match stack.last_mut() {
+                Some(Skipping::Compound) if first_tag.is_some() => first_tag.take().unwrap_or_default(),
+                None => return Ok(()),
+                Some(Skipping::Compound) => {
+                    let value_type = self.read_u8()?;
+                    if value_type == nbt::TAG_END {
+                        stack.pop();
+                        continue;
+                    }
+                    // the tag's name
+                    let len = self.read_u16()?;
+                    self.skip_bytes(len as usize, 1)?;
+                    value_type
+                },
+                Some(Skipping::List { elem_type, remaining }) => {
+                    if *remaining == 0 {
+                        stack.pop();
+                        continue;
+                    }
+                    *remaining -= 1;
+                    *elem_type
+                },
+            };
+
+            let skipping = match value_type {
+                nbt::TAG_COMPOUND => Skipping::Compound,
+                nbt::TAG_LIST => {
+                    let elem_type = self.read_u8()?;
+                    let len = self.read_i32_list_len()?;
+                    check_list_header(elem_type, len)?;
+                    Skipping::List { elem_type, remaining: len }
+                },
+                nbt::TAG_I8 => {
+                    self.skip_bytes(1, 1)?;
+                    continue;
+                },
+                nbt::TAG_I16 => {
+                    self.skip_bytes(1, 2)?;
+                    continue;
+                },
+                nbt::TAG_I32 | nbt::TAG_F32 => {
+                    self.skip_bytes(1, 4)?;
+                    continue;
+                },
+                nbt::TAG_I64 | nbt::TAG_F64 => {
+                    self.skip_bytes(1, 8)?;
+                    continue;
+                },
+                nbt::TAG_STRING => {
+                    let len = self.read_u16()?;
+                    self.skip_bytes(len as usize, 1)?;
+                    continue;
+                },
+                nbt::TAG_I8_ARRAY => {
+                    let len = self.read_i32_list_len()?;
+                    self.skip_bytes(len, 1)?;
+                    continue;
+                },
+                nbt::TAG_I32_ARRAY => {
+                    let len = self.read_i32_list_len()?;
+                    self.skip_bytes(len, 4)?;
+                    continue;
+                },
+                nbt::TAG_I64_ARRAY => {
+                    let len = self.read_i32_list_len()?;
+                    self.skip_bytes(len, 8)?;
+                    continue;
+                },
+                _ => return Err(Error::InvalidTagTypeError),
+            };
+
+            // the outermost skipped value has already been entered on `self.stack`
+            if self.stack.len() + stack.len() > self.max_depth {
+                self.state = ParserState::InvalidState;
+                return Err(Error::DepthLimitError);
+            }
+            stack.push(skipping);
+        }
+    }
+
+    /// reads ahead past the value of a list element's first tag, returning
+    /// whether the element's compound ends after it
+    fn read_ahead(&mut self, value_type: u8) -> Result<bool> {
+        #[cfg(debug_assertions)]
+        trace!("Parser::read_ahead({:?})", value_type);
+
+        self.recording = Some(Vec::new());
+        let result = self
+            .skip_nested(Skipping::List { elem_type: value_type, remaining: 1 }, None)
+            .and_then(|()| self.read_u8());
+
+        // the bytes read ahead are read again before any which were read
+        // ahead earlier and have not been read again yet
+        let recording = self.recording.take().unwrap_or_default();
+        if !recording.is_empty() {
+            self.replay.push((recording, 0));
+        }
+        Ok(result? == nbt::TAG_END)
+    }
+
+    /// moves past up to `len` bytes which were read ahead, passing them to
+    /// `read`, and returns how many there were
+    fn replay(&mut self, len: usize, mut read: impl FnMut(&[u8])) -> usize {
+        let mut replayed = 0;
+        while let Some((bytes, pos)) = self.replay.last_mut() {
+            let n = (len - replayed).min(bytes.len() - *pos);
+            read(&bytes[*pos..*pos + n]);
+            *pos += n;
+            replayed += n;
+            if *pos == bytes.len() {
+                self.replay.pop();
+            }
+            if replayed == len {
+                break;
+            }
+        }
+        replayed
+    }
+
+    /// fills `buf` with the next bytes, which were read ahead or come from the
+    /// input
+    fn read_input(&mut self, buf: &mut [u8]) -> std::io::Result<()> {
+        let mut filled = 0;
+        let replayed = self.replay(buf.len(), |bytes| {
+            buf[filled..filled + bytes.len()].copy_from_slice(bytes);
+            filled += bytes.len();
+        });
+        self.input.read_exact(&mut buf[replayed..])?;
+
+        if let Some(recording) = &mut self.recording {
+            recording.extend_from_slice(buf);
+        }
+        Ok(())
+    }
+
+    /// reads the next `len` bytes, borrowing them from the input if possible
+    fn read_input_bytes(&mut self, len: usize) -> std::io::Result<Cow<'de, [u8]>> {
+        let mut value = Vec::new();
+        let replayed = self.replay(len, |bytes| value.extend_from_slice(bytes));
+        let value = if replayed == 0 {
+            self.input.read_bytes(len)?
+        } else {
+            value.extend_from_slice(&self.input.read_bytes(len - replayed)?);
+            Cow::Owned(value)
+        };
+
+        if let Some(recording) = &mut self.recording {
+            recording.extend_from_slice(&value);
+        }
+        Ok(value)
+    }
+
+    /// moves past the next `len` bytes, which are kept while reading ahead
+    fn skip_input(&mut self, len: usize) -> std::io::Result<()> {
+        if self.recording.is_some() {
+            return self.read_input_bytes(len).map(|_| ());
+        }
+
+        let replayed = self.replay(len, |_| {});
+        self.input.skip(len - replayed)
+    }
+
+    /// skips `len` values of `size` bytes each
+    fn skip_bytes(&mut self, len: usize, size: usize) -> Result<()> {
         #[cfg(debug_assertions)]
-        trace!("Parser::next_state_from_stack");
+        trace!("Parser::skip_bytes({:?}, {:?})", len, size);
+
+        let len = len.checked_mul(size).ok_or(Error::SizeLimitError)?;
+        self.skip_input(len)?;
+        Ok(())
+    }
+
+    /// returns the event for the value the parser is positioned on
+    fn current_event(&self) -> Result<Event<'de>> {
+        #[cfg(debug_assertions)]
+        trace!("Parser::current_event");
+
+        let event = match &self.state {
+            ParserState::TagHeader { value_type, name } => Event::TagStart { name: name.clone(), ty: *value_type },
+            ParserState::TagValueI8 { value }
+            | ParserState::I8ArrayValue { value, .. }
+            | ParserState::ListValueI8 { value, .. } => Event::I8(*value),
+            ParserState::TagValueI16 { value } | ParserState::ListValueI16 { value, .. } => Event::I16(*value),
+            ParserState::TagValueI32 { value }
+            | ParserState::I32ArrayValue { value, .. }
+            | ParserState::ListValueI32 { value, .. } => Event::I32(*value),
+            ParserState::TagValueI64 { value }
+            | ParserState::I64ArrayValue { value, .. }
+            | ParserState::ListValueI64 { value, .. } => Event::I64(*value),
+            ParserState::TagValueF32 { value } | ParserState::ListValueF32 { value, .. } => Event::F32(*value),
+            ParserState::TagValueF64 { value } | ParserState::ListValueF64 { value, .. } => Event::F64(*value),
+            ParserState::TagValueString { value }
+            | ParserState::ListValueString { value, .. } => Event::String(value.clone()),
+            ParserState::Compound => Event::CompoundStart,
+            ParserState::List { len, elem_type } => Event::ListStart { ty: *elem_type, len: *len },
+            ParserState::I8Array { len } => Event::ArrayStart { ty: nbt::TAG_I8_ARRAY, len: *len },
+            ParserState::I32Array { len } => Event::ArrayStart { ty: nbt::TAG_I32_ARRAY, len: *len },
+            ParserState::I64Array { len } => Event::ArrayStart { ty: nbt::TAG_I64_ARRAY, len: *len },
+            ParserState::TagEnd
+            | ParserState::I8ArrayEnd
+            | ParserState::I32ArrayEnd
+            | ParserState::I64ArrayEnd
+            | ParserState::ListEnd => Event::End,
+            // the remaining states are never current after advancing
+            _ => return Err(Error::InvalidParserStateError),
+        };
+        Ok(event)
+    }
+
+    /// saves the state to return to after a nested compound or list
+    fn enter(&mut self, next_state: ParserState<'de>) -> Result<()> {
+        #[cfg(debug_assertions)]
+        trace!("Parser::enter");
+
+        if self.stack.len() >= self.max_depth {
+            self.state = ParserState::InvalidState;
+            return Err(Error::DepthLimitError);
+        }
+
+        self.stack.push(next_state);
+        Ok(())
+    }
+
+    /// restore parser's state from the stack
+    fn restore_state_from_stack(&mut self) -> Result<()> {
+        #[cfg(debug_assertions)]
+        trace!("Parser::restore_state_from_stack");
 
         match self.stack.pop() {
             Some(next_state) => {
-                // restore previous state from the stack
                 self.state = next_state;
-                // read the next value
-                // FIXME: try to avoid creating new frames on the call stack
-                self.next()
+                Ok(())
             },
             None => {
                 self.state = ParserState::InvalidState;
@@ -762,17 +1478,20 @@ impl<R> Parser<R> where R: std::io::Read {
     }
 
     /// helper function to read NBT strings
-    fn read_nbt_string(&mut self) -> Result<String> {
+    fn read_nbt_string(&mut self) -> Result<Cow<'de, str>> {
         #[cfg(debug_assertions)]
         trace!("Parser::read_nbt_string");
 
         // u16 prefixed length
-        let len = self.input.read_u16::<BigEndian>()?;
+        let len = self.read_u16()?;
+        self.account(len as usize, 1)?;
 
         // read the string's contents
-        let mut value: Vec<u8> = vec![0; len as usize];
-        self.input.read_exact(value.as_mut_slice())?;
-        let value = String::from_utf8(value)?;
+        let value = match (self.string_encoding, self.read_input_bytes(len as usize)?) {
+            (StringEncoding::ModifiedUtf8, value) => mutf8::decode(value)?,
+            (StringEncoding::Utf8, Cow::Borrowed(value)) => Cow::Borrowed(std::str::from_utf8(value)?),
+            (StringEncoding::Utf8, Cow::Owned(value)) => Cow::Owned(String::from_utf8(value)?),
+        };
 
         #[cfg(debug_assertions)]
         trace!("Parser::read_nbt_string() -> {:?}", value);
@@ -785,12 +1504,8 @@ impl<R> Parser<R> where R: std::io::Read {
         #[cfg(debug_assertions)]
         trace!("Parser::read_i32_list_len");
 
-        let len = self.input.read_i32::<BigEndian>()?;
-        if len < 0 {
-            self.state = ParserState::InvalidState;
-            // TODO: create more descriptive errors for malformed input
-            return Err(Error::InvalidParserStateError);
-        } else if len > nbt::LIST_MAX_LEN as i32 {
+        let len = self.read_i32()?;
+        if len < 0 || len > nbt::LIST_MAX_LEN as i32 {
             self.state = ParserState::InvalidState;
             // TODO: create more descriptive errors for malformed input
             return Err(Error::InvalidParserStateError);
@@ -801,11 +1516,124 @@ impl<R> Parser<R> where R: std::io::Read {
 
         Ok(len as usize)
     }
+
+    fn read_u8(&mut self) -> Result<u8> {
+        let mut buf = [0; 1];
+        self.read_input(&mut buf)?;
+        Ok(buf[0])
+    }
+
+    fn read_i8(&mut self) -> Result<i8> {
+        Ok(self.read_u8()? as i8)
+    }
+
+    fn read_i16(&mut self) -> Result<i16> {
+        let mut buf = [0; 2];
+        self.read_input(&mut buf)?;
+        match self.byte_order {
+            ByteOrder::BigEndian => Ok(BigEndian::read_i16(&buf)),
+            ByteOrder::LittleEndian => Ok(LittleEndian::read_i16(&buf)),
+        }
+    }
+
+    fn read_u16(&mut self) -> Result<u16> {
+        let mut buf = [0; 2];
+        self.read_input(&mut buf)?;
+        match self.byte_order {
+            ByteOrder::BigEndian => Ok(BigEndian::read_u16(&buf)),
+            ByteOrder::LittleEndian => Ok(LittleEndian::read_u16(&buf)),
+        }
+    }
+
+    fn read_i32(&mut self) -> Result<i32> {
+        let mut buf = [0; 4];
+        self.read_input(&mut buf)?;
+        match self.byte_order {
+            ByteOrder::BigEndian => Ok(BigEndian::read_i32(&buf)),
+            ByteOrder::LittleEndian => Ok(LittleEndian::read_i32(&buf)),
+        }
+    }
+
+    fn read_i64(&mut self) -> Result<i64> {
+        let mut buf = [0; 8];
+        self.read_input(&mut buf)?;
+        match self.byte_order {
+            ByteOrder::BigEndian => Ok(BigEndian::read_i64(&buf)),
+            ByteOrder::LittleEndian => Ok(LittleEndian::read_i64(&buf)),
+        }
+    }
+
+    fn read_f32(&mut self) -> Result<f32> {
+        let mut buf = [0; 4];
+        self.read_input(&mut buf)?;
+        match self.byte_order {
+            ByteOrder::BigEndian => Ok(BigEndian::read_f32(&buf)),
+            ByteOrder::LittleEndian => Ok(LittleEndian::read_f32(&buf)),
+        }
+    }
+
+    fn read_f64(&mut self) -> Result<f64> {
+        let mut buf = [0; 8];
+        self.read_input(&mut buf)?;
+        match self.byte_order {
+            ByteOrder::BigEndian => Ok(BigEndian::read_f64(&buf)),
+            ByteOrder::LittleEndian => Ok(LittleEndian::read_f64(&buf)),
+        }
+    }
+}
+
+/// checks a list's element type, vanilla writes empty lists with `TAG_END` or
+/// whichever type it was created with, so any type is accepted if `len` is 0
+fn check_list_header(elem_type: u8, len: usize) -> Result<()> {
+    match elem_type {
+        _ if len == 0 => Ok(()),
+        nbt::TAG_END => Err(Error::MissingListTypeError),
+        nbt::TAG_I8..=nbt::TAG_I64_ARRAY => Ok(()),
+        _ => Err(Error::InvalidTagTypeError),
+    }
+}
+
+/// reads the input one event at a time
+///
+/// Iteration stops at the end of the input once all tags have been read, or
+/// after the first error.
+impl<'de, R> Iterator for Parser<'de, R>
+where
+    R: Input<'de>,
+{
+    type Item = Result<Event<'de>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        #[cfg(debug_assertions)]
+        trace!("Parser::next");
+
+        if self.state == ParserState::InvalidState {
+            return None;
+        }
+
+        let result = self.advance().and_then(|()| {
+            // move on from between root tags to the next one
+            if self.is_between_roots() {
+                self.advance()?;
+            }
+            self.current_event()
+        });
+        if result.is_err() {
+            // the input ending between tags at the root is the regular end
+            let eof = matches!(result, Err(Error::Eof)) && self.stack.is_empty();
+            self.state = ParserState::InvalidState;
+            if eof {
+                return None;
+            }
+        }
+        Some(result)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use std::io::Cursor;
+    use crate::nbt::input::SeekInput;
     use super::*;
 
     #[test]
@@ -831,12 +1659,12 @@ mod tests {
         assert_eq!(parser.state, ParserState::ExpectingTag);
 
         // read the tag's header
-        assert!(parser.next().is_ok());
-        assert_eq!(parser.state, ParserState::TagHeader { value_type: nbt::TAG_I8, name: "".to_string() });
+        assert!(parser.advance().is_ok());
+        assert_eq!(parser.state, ParserState::TagHeader { value_type: nbt::TAG_I8, name: "".into() });
         assert_eq!(parser.get_string_value().unwrap(), "");
 
         // read the tag's value
-        assert!(parser.next().is_ok());
+        assert!(parser.advance().is_ok());
         assert_eq!(parser.state, ParserState::TagValueI8 { value: EXPECTED_VALUE });
         assert_eq!(parser.get_i8_value().unwrap(), EXPECTED_VALUE);
 
@@ -857,12 +1685,12 @@ mod tests {
         assert_eq!(parser.state, ParserState::ExpectingTag);
 
         // read the tag's header
-        assert!(parser.next().is_ok());
-        assert_eq!(parser.state, ParserState::TagHeader { value_type: nbt::TAG_I16, name: "".to_string() });
+        assert!(parser.advance().is_ok());
+        assert_eq!(parser.state, ParserState::TagHeader { value_type: nbt::TAG_I16, name: "".into() });
         assert_eq!(parser.get_string_value().unwrap(), "");
 
         // read the tag's value
-        assert!(parser.next().is_ok());
+        assert!(parser.advance().is_ok());
         assert_eq!(parser.state, ParserState::TagValueI16 { value: EXPECTED_VALUE });
         assert_eq!(parser.get_i16_value().unwrap(), EXPECTED_VALUE);
 
@@ -883,12 +1711,12 @@ mod tests {
         assert_eq!(parser.state, ParserState::ExpectingTag);
 
         // read the tag's header
-        assert!(parser.next().is_ok());
-        assert_eq!(parser.state, ParserState::TagHeader { value_type: nbt::TAG_I32, name: "".to_string() });
+        assert!(parser.advance().is_ok());
+        assert_eq!(parser.state, ParserState::TagHeader { value_type: nbt::TAG_I32, name: "".into() });
         assert_eq!(parser.get_string_value().unwrap(), "");
 
         // read the tag's value
-        assert!(parser.next().is_ok());
+        assert!(parser.advance().is_ok());
         assert_eq!(parser.state, ParserState::TagValueI32 { value: EXPECTED_VALUE });
         assert_eq!(parser.get_i32_value().unwrap(), EXPECTED_VALUE);
 
@@ -909,12 +1737,12 @@ mod tests {
         assert_eq!(parser.state, ParserState::ExpectingTag);
 
         // read the tag's header
-        assert!(parser.next().is_ok());
-        assert_eq!(parser.state, ParserState::TagHeader { value_type: nbt::TAG_I64, name: "".to_string() });
+        assert!(parser.advance().is_ok());
+        assert_eq!(parser.state, ParserState::TagHeader { value_type: nbt::TAG_I64, name: "".into() });
         assert_eq!(parser.get_string_value().unwrap(), "");
 
         // read the tag's value
-        assert!(parser.next().is_ok());
+        assert!(parser.advance().is_ok());
         assert_eq!(parser.state, ParserState::TagValueI64 { value: EXPECTED_VALUE });
         assert_eq!(parser.get_i64_value().unwrap(), EXPECTED_VALUE);
 
@@ -934,12 +1762,12 @@ mod tests {
         assert_eq!(parser.state, ParserState::ExpectingTag);
 
         // read the tag's header
-        assert!(parser.next().is_ok());
-        assert_eq!(parser.state, ParserState::TagHeader { value_type: nbt::TAG_F32, name: "".to_string() });
+        assert!(parser.advance().is_ok());
+        assert_eq!(parser.state, ParserState::TagHeader { value_type: nbt::TAG_F32, name: "".into() });
         assert_eq!(parser.get_string_value().unwrap(), "");
 
         // read the tag's value
-        assert!(parser.next().is_ok());
+        assert!(parser.advance().is_ok());
         assert_eq!(parser.state, ParserState::TagValueF32 { value: EXPECTED_VALUE });
         assert_eq!(parser.get_f32_value().unwrap(), EXPECTED_VALUE);
 
@@ -960,12 +1788,12 @@ mod tests {
         assert_eq!(parser.state, ParserState::ExpectingTag);
 
         // read the tag's header
-        assert!(parser.next().is_ok());
-        assert_eq!(parser.state, ParserState::TagHeader { value_type: nbt::TAG_F64, name: "".to_string() });
+        assert!(parser.advance().is_ok());
+        assert_eq!(parser.state, ParserState::TagHeader { value_type: nbt::TAG_F64, name: "".into() });
         assert_eq!(parser.get_string_value().unwrap(), "");
 
         // read the tag's value
-        assert!(parser.next().is_ok());
+        assert!(parser.advance().is_ok());
         assert_eq!(parser.state, ParserState::TagValueF64 { value: EXPECTED_VALUE });
         assert_eq!(parser.get_f64_value().unwrap(), EXPECTED_VALUE);
 
@@ -987,13 +1815,13 @@ mod tests {
         assert_eq!(parser.state, ParserState::ExpectingTag);
 
         // read the tag's header
-        assert!(parser.next().is_ok());
-        assert_eq!(parser.state, ParserState::TagHeader { value_type: nbt::TAG_STRING, name: tag_name.clone() });
+        assert!(parser.advance().is_ok());
+        assert_eq!(parser.state, ParserState::TagHeader { value_type: nbt::TAG_STRING, name: tag_name.clone().into() });
         assert_eq!(parser.get_string_value().unwrap(), tag_name);
 
         // read the tag's value
-        assert!(parser.next().is_ok());
-        assert_eq!(parser.state, ParserState::TagValueString { value: expected_value.clone() });
+        assert!(parser.advance().is_ok());
+        assert_eq!(parser.state, ParserState::TagValueString { value: expected_value.clone().into() });
         assert_eq!(parser.get_string_value().unwrap(), expected_value);
 
         // TODO: check to make sure that the other `get_*` functions return errors
@@ -1011,16 +1839,16 @@ mod tests {
         assert_eq!(parser.state, ParserState::ExpectingTag);
 
         // read the tag's header
-        assert!(parser.next().is_ok());
-        assert_eq!(parser.state, ParserState::TagHeader { value_type: nbt::TAG_COMPOUND, name: "".to_string() });
+        assert!(parser.advance().is_ok());
+        assert_eq!(parser.state, ParserState::TagHeader { value_type: nbt::TAG_COMPOUND, name: "".into() });
         assert_eq!(parser.get_string_value().unwrap(), "");
 
         // enter into the `TAG_COMPOUND`
-        assert!(parser.next().is_ok());
+        assert!(parser.advance().is_ok());
         assert_eq!(parser.state, ParserState::Compound);
 
         // read the end marker for the empty tag
-        assert!(parser.next().is_ok());
+        assert!(parser.advance().is_ok());
         assert_eq!(parser.state, ParserState::TagEnd);
 
         // TODO: try reading beyond the end of the input
@@ -1040,27 +1868,27 @@ mod tests {
         assert_eq!(parser.state, ParserState::ExpectingTag);
 
         // read the outer tag's header
-        assert!(parser.next().is_ok());
-        assert_eq!(parser.state, ParserState::TagHeader { value_type: nbt::TAG_COMPOUND, name: outer_name.clone() });
+        assert!(parser.advance().is_ok());
+        assert_eq!(parser.state, ParserState::TagHeader { value_type: nbt::TAG_COMPOUND, name: outer_name.clone().into() });
         assert_eq!(parser.get_string_value().unwrap(), outer_name);
 
         // enter into the outer `TAG_COMPOUND`
-        assert!(parser.next().is_ok());
+        assert!(parser.advance().is_ok());
 
         // read the inner tag
-        assert!(parser.next().is_ok());
-        assert_eq!(parser.state, ParserState::TagHeader { value_type: nbt::TAG_COMPOUND, name: inner_name.clone() });
+        assert!(parser.advance().is_ok());
+        assert_eq!(parser.state, ParserState::TagHeader { value_type: nbt::TAG_COMPOUND, name: inner_name.clone().into() });
         assert_eq!(parser.get_string_value().unwrap(), inner_name);
 
         // enter into the inner `TAG_COMPOUND`
-        assert!(parser.next().is_ok());
+        assert!(parser.advance().is_ok());
 
         // read the inner tag's end marker
-        assert!(parser.next().is_ok());
+        assert!(parser.advance().is_ok());
         assert_eq!(parser.state, ParserState::TagEnd);
 
         // read the outer tag's `TAG_END`
-        assert!(parser.next().is_ok());
+        assert!(parser.advance().is_ok());
         assert_eq!(parser.state, ParserState::TagEnd);
 
         // TODO: try reading beyond the end of the input
@@ -1081,39 +1909,39 @@ mod tests {
         assert_eq!(parser.state, ParserState::ExpectingTag);
 
         // read the outer tag's header
-        assert!(parser.next().is_ok());
-        assert_eq!(parser.state, ParserState::TagHeader { value_type: nbt::TAG_COMPOUND, name: outer_name.clone() });
+        assert!(parser.advance().is_ok());
+        assert_eq!(parser.state, ParserState::TagHeader { value_type: nbt::TAG_COMPOUND, name: outer_name.clone().into() });
         assert_eq!(parser.get_string_value().unwrap(), outer_name);
 
         // enter into the outer `TAG_COMPOUND`
-        assert!(parser.next().is_ok());
+        assert!(parser.advance().is_ok());
 
         // read the middle tag
-        assert!(parser.next().is_ok());
-        assert_eq!(parser.state, ParserState::TagHeader { value_type: nbt::TAG_COMPOUND, name: mid_name.clone() });
+        assert!(parser.advance().is_ok());
+        assert_eq!(parser.state, ParserState::TagHeader { value_type: nbt::TAG_COMPOUND, name: mid_name.clone().into() });
         assert_eq!(parser.get_string_value().unwrap(), mid_name);
 
         // enter into the middle TAG_COMPOUND
-        assert!(parser.next().is_ok());
+        assert!(parser.advance().is_ok());
 
         // read the inner tag
-        assert!(parser.next().is_ok());
-        assert_eq!(parser.state, ParserState::TagHeader { value_type: nbt::TAG_COMPOUND, name: inner_name.clone() });
+        assert!(parser.advance().is_ok());
+        assert_eq!(parser.state, ParserState::TagHeader { value_type: nbt::TAG_COMPOUND, name: inner_name.clone().into() });
         assert_eq!(parser.get_string_value().unwrap(), inner_name);
 
         // enter into the inner `TAG_COMPOUND`
-        assert!(parser.next().is_ok());
+        assert!(parser.advance().is_ok());
 
         // read the inner tag's end marker
-        assert!(parser.next().is_ok());
+        assert!(parser.advance().is_ok());
         assert_eq!(parser.state, ParserState::TagEnd);
 
         // read the middle tag's end marker
-        assert!(parser.next().is_ok());
+        assert!(parser.advance().is_ok());
         assert_eq!(parser.state, ParserState::TagEnd);
 
         // read the outer tag's `TAG_END`
-        assert!(parser.next().is_ok());
+        assert!(parser.advance().is_ok());
         assert_eq!(parser.state, ParserState::TagEnd);
 
         // TODO: try reading beyond the end of the input
@@ -1130,15 +1958,15 @@ mod tests {
         assert_eq!(parser.state, ParserState::ExpectingTag);
 
         // tag header
-        assert!(parser.next().is_ok());
-        assert_eq!(parser.state, ParserState::TagHeader { value_type: nbt::TAG_I8_ARRAY, name: "".to_string() });
+        assert!(parser.advance().is_ok());
+        assert_eq!(parser.state, ParserState::TagHeader { value_type: nbt::TAG_I8_ARRAY, name: "".into() });
 
         // begining of the array
-        assert!(parser.next().is_ok());
+        assert!(parser.advance().is_ok());
         assert_eq!(parser.state, ParserState::I8Array { len: 0 });
 
         // end of the array
-        assert!(parser.next().is_ok());
+        assert!(parser.advance().is_ok());
         assert_eq!(parser.state, ParserState::I8ArrayEnd);
 
         // TODO: try reading beyond the end of the input
@@ -1155,15 +1983,15 @@ mod tests {
         assert_eq!(parser.state, ParserState::ExpectingTag);
 
         // tag header
-        assert!(parser.next().is_ok());
-        assert_eq!(parser.state, ParserState::TagHeader { value_type: nbt::TAG_I32_ARRAY, name: "".to_string() });
+        assert!(parser.advance().is_ok());
+        assert_eq!(parser.state, ParserState::TagHeader { value_type: nbt::TAG_I32_ARRAY, name: "".into() });
 
         // begining of the array
-        assert!(parser.next().is_ok());
+        assert!(parser.advance().is_ok());
         assert_eq!(parser.state, ParserState::I32Array { len: 0 });
 
         // end of the array
-        assert!(parser.next().is_ok());
+        assert!(parser.advance().is_ok());
         assert_eq!(parser.state, ParserState::I32ArrayEnd);
 
         // TODO: try reading beyond the end of the input
@@ -1180,15 +2008,15 @@ mod tests {
         assert_eq!(parser.state, ParserState::ExpectingTag);
 
         // tag header
-        assert!(parser.next().is_ok());
-        assert_eq!(parser.state, ParserState::TagHeader { value_type: nbt::TAG_I64_ARRAY, name: "".to_string() });
+        assert!(parser.advance().is_ok());
+        assert_eq!(parser.state, ParserState::TagHeader { value_type: nbt::TAG_I64_ARRAY, name: "".into() });
 
         // begining of the array
-        assert!(parser.next().is_ok());
+        assert!(parser.advance().is_ok());
         assert_eq!(parser.state, ParserState::I64Array { len: 0 });
 
         // end of the array
-        assert!(parser.next().is_ok());
+        assert!(parser.advance().is_ok());
         assert_eq!(parser.state, ParserState::I64ArrayEnd);
 
         // TODO: try reading beyond the end of the input
@@ -1205,15 +2033,15 @@ mod tests {
         assert_eq!(parser.state, ParserState::ExpectingTag);
 
         // tag header
-        assert!(parser.next().is_ok());
-        assert_eq!(parser.state, ParserState::TagHeader { value_type: nbt::TAG_LIST, name: "".to_string() });
+        assert!(parser.advance().is_ok());
+        assert_eq!(parser.state, ParserState::TagHeader { value_type: nbt::TAG_LIST, name: "".into() });
 
         // list header
-        assert!(parser.next().is_ok());
+        assert!(parser.advance().is_ok());
         assert_eq!(parser.state, ParserState::List { len: 0, elem_type: nbt::TAG_END });
 
         // list end
-        assert!(parser.next().is_ok());
+        assert!(parser.advance().is_ok());
         assert_eq!(parser.state, ParserState::ListEnd);
     }
 
@@ -1228,15 +2056,602 @@ mod tests {
         assert_eq!(parser.state, ParserState::ExpectingTag);
 
         // tag header
-        assert!(parser.next().is_ok());
-        assert_eq!(parser.state, ParserState::TagHeader { value_type: nbt::TAG_LIST, name: "".to_string() });
+        assert!(parser.advance().is_ok());
+        assert_eq!(parser.state, ParserState::TagHeader { value_type: nbt::TAG_LIST, name: "".into() });
 
         // list header
-        assert!(parser.next().is_ok());
+        assert!(parser.advance().is_ok());
         assert_eq!(parser.state, ParserState::List { len: 0, elem_type: nbt::TAG_I8 });
 
         // list end
-        assert!(parser.next().is_ok());
+        assert!(parser.advance().is_ok());
+        assert_eq!(parser.state, ParserState::ListEnd);
+    }
+
+    #[test]
+    fn test_i8_array_list() {
+        // `"": [[B; 1B], [B;]]`
+        let buffer = b"\x09\x00\x00\x07\x00\x00\x00\x02\x00\x00\x00\x01\x01\x00\x00\x00\x00";
+        let input = Cursor::new(buffer);
+        let mut parser = Parser::new(input);
+
+        // tag header
+        assert!(parser.advance().is_ok());
+        assert_eq!(parser.state, ParserState::TagHeader { value_type: nbt::TAG_LIST, name: "".into() });
+
+        // list header
+        assert!(parser.advance().is_ok());
+        assert_eq!(parser.state, ParserState::List { len: 2, elem_type: nbt::TAG_I8_ARRAY });
+
+        // first array
+        assert!(parser.advance().is_ok());
+        assert_eq!(parser.state, ParserState::I8Array { len: 1 });
+        assert_eq!(parser.get_value_type(), ValueType::SeqBegin);
+        assert!(parser.advance().is_ok());
+        assert_eq!(parser.state, ParserState::I8ArrayValue { remaining: 0, value: 1 });
+        assert!(parser.advance().is_ok());
+        assert_eq!(parser.state, ParserState::I8ArrayEnd);
+
+        // second array, the parser should resume the list after the first one
+        assert!(parser.advance().is_ok());
+        assert_eq!(parser.state, ParserState::I8Array { len: 0 });
+        assert!(parser.advance().is_ok());
+        assert_eq!(parser.state, ParserState::I8ArrayEnd);
+
+        // list end
+        assert!(parser.advance().is_ok());
         assert_eq!(parser.state, ParserState::ListEnd);
     }
+
+    #[test]
+    fn test_slice_string_tag() {
+        // `"tag name": "Hello, World!"`
+        let buffer = b"\x08\x00\x08tag name\x00\x0dHello, World!";
+        let mut parser = Parser::from_slice(&buffer[..]);
+
+        // the tag's name is borrowed from the input
+        assert!(parser.advance().is_ok());
+        assert_eq!(parser.state, ParserState::TagHeader { value_type: nbt::TAG_STRING, name: Cow::Borrowed("tag name") });
+        assert!(matches!(parser.get_str_value().unwrap(), Reference::Borrowed("tag name")));
+
+        // and so is its value
+        assert!(parser.advance().is_ok());
+        assert!(matches!(parser.get_str_value().unwrap(), Reference::Borrowed("Hello, World!")));
+        assert_eq!(parser.get_string_value().unwrap(), "Hello, World!");
+    }
+
+    #[test]
+    fn test_slice_truncated_input() {
+        // `"tag name": "Hello` (the string's length is larger than the input)
+        let buffer = b"\x08\x00\x08tag name\x00\x0dHello";
+        let mut parser = Parser::from_slice(&buffer[..]);
+
+        assert!(parser.advance().is_ok());
+        assert!(parser.advance().is_err());
+    }
+
+    #[test]
+    fn test_read_i64_array() {
+        // `"": [L; 1L, -1L]`
+        let buffer = b"\x0c\x00\x00\x00\x00\x00\x02\
+            \x00\x00\x00\x00\x00\x00\x00\x01\
+            \xff\xff\xff\xff\xff\xff\xff\xff";
+        let input = Cursor::new(buffer);
+        let mut parser = Parser::new(input);
+
+        // tag header
+        assert!(parser.advance().is_ok());
+
+        // the whole array is read at once
+        assert!(parser.advance().is_ok());
+        assert_eq!(parser.state, ParserState::I64Array { len: 2 });
+        assert_eq!(parser.read_i64_array().unwrap(), vec![1, -1]);
+        assert_eq!(parser.state, ParserState::I64ArrayEnd);
+
+        // reading the array again is an error
+        assert!(parser.read_i64_array().is_err());
+    }
+
+    #[test]
+    fn test_read_i32_array_into() {
+        // `"": [[I; 1, 2], [I; 3]]`
+        let buffer = b"\x09\x00\x00\x0b\x00\x00\x00\x02\
+            \x00\x00\x00\x02\x00\x00\x00\x01\x00\x00\x00\x02\
+            \x00\x00\x00\x01\x00\x00\x00\x03";
+        let mut parser = Parser::from_slice(&buffer[..]);
+        let mut buf = Vec::new();
+
+        // tag header and list header
+        assert!(parser.advance().is_ok());
+        assert!(parser.advance().is_ok());
+
+        // first array
+        assert!(parser.advance().is_ok());
+        assert!(parser.read_i32_array_into(&mut buf).is_ok());
+        assert_eq!(buf, vec![1, 2]);
+
+        // second array, the buffer's previous contents are replaced
+        assert!(parser.advance().is_ok());
+        assert!(parser.read_i32_array_into(&mut buf).is_ok());
+        assert_eq!(buf, vec![3]);
+
+        // list end
+        assert!(parser.advance().is_ok());
+        assert_eq!(parser.state, ParserState::ListEnd);
+    }
+
+    #[test]
+    fn test_read_i8_array() {
+        // `"": [B; 1b, -1b]`
+        let buffer = b"\x07\x00\x00\x00\x00\x00\x02\x01\xff";
+        let input = Cursor::new(buffer);
+        let mut parser = Parser::new(input);
+
+        assert!(parser.advance().is_ok());
+        assert!(parser.advance().is_ok());
+
+        // the wrong array type is an error
+        assert!(parser.read_i32_array().is_err());
+        assert_eq!(parser.read_i8_array().unwrap(), vec![1, -1]);
+        assert_eq!(parser.state, ParserState::I8ArrayEnd);
+    }
+
+    /// `"": [[[...]]]`, `depth` lists nested inside of each other
+    fn nested_lists(depth: usize) -> Vec<u8> {
+        let mut buffer = b"\x09\x00\x00".to_vec();
+        for _ in 1..depth {
+            buffer.extend_from_slice(b"\x09\x00\x00\x00\x01");
+        }
+        buffer.extend_from_slice(b"\x00\x00\x00\x00\x00");
+        buffer
+    }
+
+    /// calls `advance` until the parser leaves the outermost value
+    fn parse_all<'de, R>(parser: &mut Parser<'de, R>) -> Result<()> where R: Input<'de> {
+        loop {
+            match parser.advance() {
+                Ok(()) => {},
+                Err(Error::Eof) => return Ok(()),
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    #[test]
+    fn test_depth_limit() {
+        // vanilla's limit of 512 nested lists is accepted
+        let buffer = nested_lists(nbt::DEFAULT_MAX_DEPTH);
+        let mut parser = Parser::from_slice(&buffer);
+        assert!(parse_all(&mut parser).is_ok());
+
+        // one more is too many
+        let buffer = nested_lists(nbt::DEFAULT_MAX_DEPTH + 1);
+        let mut parser = Parser::from_slice(&buffer);
+        assert!(matches!(parse_all(&mut parser), Err(Error::DepthLimitError)));
+        assert_eq!(parser.state, ParserState::InvalidState);
+    }
+
+    #[test]
+    fn test_configured_depth_limit() {
+        // `"outer": {"mid": {"inner": {}}}`
+        let buffer = b"\x0a\x00\x05outer\x0a\x00\x03mid\x0a\x00\x05inner\x00\x00\x00";
+
+        let mut parser = Parser::from_slice(&buffer[..]);
+        parser.set_max_depth(3);
+        assert!(parse_all(&mut parser).is_ok());
+
+        let mut parser = Parser::from_slice(&buffer[..]);
+        parser.set_max_depth(2);
+        assert!(matches!(parse_all(&mut parser), Err(Error::DepthLimitError)));
+    }
+
+    #[test]
+    fn test_deep_nesting_does_not_recurse() {
+        // leaving many nested lists one after another must not grow the call stack
+        let buffer = nested_lists(100_000);
+        let mut parser = Parser::from_slice(&buffer);
+        parser.set_max_depth(usize::MAX);
+        assert!(parse_all(&mut parser).is_ok());
+    }
+
+    #[test]
+    fn test_size_quota() {
+        // `"a": [I; 1, 2], "b": "hi"`
+        let buffer = b"\x0b\x00\x01a\x00\x00\x00\x02\x00\x00\x00\x01\x00\x00\x00\x02\x08\x00\x01b\x00\x02hi";
+
+        // 8 bytes of ints, 2 bytes of string and 2 bytes of tag names
+        let mut parser = Parser::from_slice(&buffer[..]);
+        parser.set_size_quota(12);
+        assert!(parse_all(&mut parser).is_ok());
+
+        let mut parser = Parser::from_slice(&buffer[..]);
+        parser.set_size_quota(11);
+        assert!(matches!(parse_all(&mut parser), Err(Error::SizeLimitError)));
+        assert_eq!(parser.state, ParserState::InvalidState);
+    }
+
+    #[test]
+    fn test_size_quota_list() {
+        // `"a": [[], []]`, counted as two lists of at least 5 bytes each
+        // plus the tag name
+        let buffer = b"\x09\x00\x01a\x09\x00\x00\x00\x02\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00";
+
+        let mut parser = Parser::from_slice(&buffer[..]);
+        parser.set_size_quota(11);
+        assert!(parse_all(&mut parser).is_ok());
+
+        let mut parser = Parser::from_slice(&buffer[..]);
+        parser.set_size_quota(10);
+        assert!(matches!(parse_all(&mut parser), Err(Error::SizeLimitError)));
+    }
+
+    #[test]
+    fn test_bogus_length_fails_before_allocating() {
+        // `"a": [L; ...]` claiming `LIST_MAX_LEN` elements with no data
+        let buffer = b"\x0c\x00\x01a\x7f\xff\xff\xf7";
+
+        let mut parser = Parser::from_slice(&buffer[..]);
+        parser.set_size_quota(nbt::NETWORK_SIZE_QUOTA);
+        assert!(matches!(parse_all(&mut parser), Err(Error::SizeLimitError)));
+
+        // without a quota a reader runs out of input instead
+        let mut parser = Parser::new(&buffer[..]);
+        parser.advance().unwrap();
+        parser.advance().unwrap();
+        assert!(matches!(parser.read_i64_array(), Err(Error::IoError(_))));
+    }
+
+    #[test]
+    fn test_events() {
+        // `"a": {"b": [1s, 2s], "c": [I; 3], "d": "hi"}` followed by `"e": 4b`
+        let buffer = b"\x0a\x00\x01a\
+            \x09\x00\x01b\x02\x00\x00\x00\x02\x00\x01\x00\x02\
+            \x0b\x00\x01c\x00\x00\x00\x01\x00\x00\x00\x03\
+            \x08\x00\x01d\x00\x02hi\
+            \x00\
+            \x01\x00\x01e\x04";
+
+        let events: Result<Vec<Event>> = Parser::from_slice(&buffer[..]).collect();
+        assert_eq!(events.unwrap(), vec![
+            Event::TagStart { name: "a".into(), ty: nbt::TAG_COMPOUND },
+            Event::CompoundStart,
+            Event::TagStart { name: "b".into(), ty: nbt::TAG_LIST },
+            Event::ListStart { ty: nbt::TAG_I16, len: 2 },
+            Event::I16(1),
+            Event::I16(2),
+            Event::End,
+            Event::TagStart { name: "c".into(), ty: nbt::TAG_I32_ARRAY },
+            Event::ArrayStart { ty: nbt::TAG_I32_ARRAY, len: 1 },
+            Event::I32(3),
+            Event::End,
+            Event::TagStart { name: "d".into(), ty: nbt::TAG_STRING },
+            Event::String("hi".into()),
+            Event::End,
+            Event::TagStart { name: "e".into(), ty: nbt::TAG_I8 },
+            Event::I8(4),
+        ]);
+    }
+
+    #[test]
+    fn test_events_with_adapters() {
+        // `"": {"a": 1, "b": {"c": 2}}`
+        let buffer = b"\x0a\x00\x00\x03\x00\x01a\x00\x00\x00\x01\x0a\x00\x01b\x03\x00\x01c\x00\x00\x00\x02\x00\x00";
+
+        let names: Vec<_> = Parser::from_slice(&buffer[..])
+            .filter_map(|event| match event {
+                Ok(Event::TagStart { name, .. }) => Some(name),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(names, vec!["", "a", "b", "c"]);
+    }
+
+    #[test]
+    fn test_events_stop_after_error() {
+        // `"": {"a": ` and then the input ends
+        let buffer = b"\x0a\x00\x00\x03\x00\x01a\x00\x00";
+
+        let mut parser = Parser::from_slice(&buffer[..]);
+        assert!(matches!(parser.next(), Some(Ok(Event::TagStart { .. }))));
+        assert!(matches!(parser.next(), Some(Ok(Event::CompoundStart))));
+        assert!(matches!(parser.next(), Some(Ok(Event::TagStart { .. }))));
+        assert!(matches!(parser.next(), Some(Err(_))));
+        assert!(parser.next().is_none());
+    }
+
+    #[test]
+    fn test_skip_value() {
+        // `"": {"a": {"b": [[1s], []], "c": "hi"}, "d": [I; 2], "e": 3b}`
+        let buffer = b"\x0a\x00\x00\
+            \x0a\x00\x01a\
+                \x09\x00\x01b\x09\x00\x00\x00\x02\x02\x00\x00\x00\x01\x00\x01\x00\x00\x00\x00\x00\
+                \x08\x00\x01c\x00\x02hi\
+            \x00\
+            \x0b\x00\x01d\x00\x00\x00\x01\x00\x00\x00\x02\
+            \x01\x00\x01e\x03\
+            \x00";
+
+        let mut parser = Parser::from_slice(&buffer[..]);
+        parser.advance().unwrap();
+        parser.advance().unwrap();
+
+        // skip the compound from its header
+        parser.advance().unwrap();
+        assert_eq!(parser.get_string_value().unwrap(), "a");
+        parser.skip_value().unwrap();
+        parser.advance().unwrap();
+        assert_eq!(parser.get_string_value().unwrap(), "d");
+
+        // skip the array from its value
+        parser.advance().unwrap();
+        parser.skip_value().unwrap();
+        parser.advance().unwrap();
+        assert_eq!(parser.get_string_value().unwrap(), "e");
+        parser.advance().unwrap();
+        assert_eq!(parser.get_i8_value().unwrap(), 3);
+        assert!(parse_all(&mut parser).is_ok());
+    }
+
+    #[test]
+    fn test_skip_value_seek() {
+        // `"": {"a": [L; 1L, 2L], "b": 3}`
+        let buffer = b"\x0a\x00\x00\
+            \x0c\x00\x01a\x00\x00\x00\x02\x00\x00\x00\x00\x00\x00\x00\x01\x00\x00\x00\x00\x00\x00\x00\x02\
+            \x03\x00\x01b\x00\x00\x00\x03\
+            \x00";
+
+        let mut parser = Parser::new(SeekInput::new(Cursor::new(&buffer[..])));
+        parser.advance().unwrap();
+        parser.advance().unwrap();
+        parser.advance().unwrap();
+        parser.skip_value().unwrap();
+        parser.advance().unwrap();
+        parser.advance().unwrap();
+        assert_eq!(parser.get_i32_value().unwrap(), 3);
+    }
+
+    #[test]
+    fn test_skip_value_truncated() {
+        // `"": {"a": {"b": "hi"` and then the input ends
+        let buffer = b"\x0a\x00\x00\x0a\x00\x01a\x08\x00\x01b\x00\x02hi";
+
+        let mut parser = Parser::new(&buffer[..]);
+        parser.advance().unwrap();
+        parser.advance().unwrap();
+        parser.advance().unwrap();
+        assert!(parser.skip_value().is_err());
+    }
+
+    #[test]
+    fn test_skip_deep_nesting() {
+        let buffer = nested_lists(nbt::DEFAULT_MAX_DEPTH);
+        let mut parser = Parser::from_slice(&buffer);
+        parser.advance().unwrap();
+        parser.skip_value().unwrap();
+        assert!(parse_all(&mut parser).is_ok());
+
+        let buffer = nested_lists(nbt::DEFAULT_MAX_DEPTH + 1);
+        let mut parser = Parser::from_slice(&buffer);
+        parser.advance().unwrap();
+        assert!(matches!(parser.skip_value(), Err(Error::DepthLimitError)));
+    }
+
+    #[test]
+    fn test_remaining_len() {
+        // `"": {"a": [[1s, 2s], []], "b": [B; 3b]}`
+        let buffer = b"\x0a\x00\x00\
+            \x09\x00\x01a\x09\x00\x00\x00\x02\x02\x00\x00\x00\x02\x00\x01\x00\x02\x00\x00\x00\x00\x00\
+            \x07\x00\x01b\x00\x00\x00\x01\x03\
+            \x00";
+
+        let mut parser = Parser::from_slice(&buffer[..]);
+        let mut lens = Vec::new();
+        while let Some(event) = parser.next() {
+            event.unwrap();
+            lens.push(parser.remaining_len());
+        }
+
+        assert_eq!(lens, vec![
+            None, None,
+            // `"a": [`
+            None, None,
+            // `[1s, 2s]`
+            Some(2), Some(2), Some(1), Some(0),
+            // `[]`, `]`
+            Some(1), Some(0), Some(0),
+            // `"b": [B; 3b]`
+            None, None, Some(1), Some(0),
+            None,
+        ]);
+    }
+
+    #[test]
+    fn test_stops_between_roots() {
+        // `"a": 1b` followed by `"b": {}`
+        let buffer = b"\x01\x00\x01a\x01\x0a\x00\x01b\x00";
+        let mut parser = Parser::from_slice(&buffer[..]);
+        assert!(parser.is_between_roots());
+
+        parser.advance().unwrap();
+        parser.advance().unwrap();
+        assert_eq!(parser.get_i8_value().unwrap(), 1);
+        assert!(!parser.is_between_roots());
+
+        // the next root tag is only read by another call
+        parser.advance().unwrap();
+        assert!(parser.is_between_roots());
+        parser.advance().unwrap();
+        assert_eq!(parser.get_string_value().unwrap(), "b");
+
+        parser.advance().unwrap();
+        parser.advance().unwrap();
+        assert_eq!(parser.get_value_type(), ValueType::MapEnd);
+        parser.advance().unwrap();
+        assert!(parser.is_between_roots());
+        assert!(matches!(parser.advance(), Err(Error::Eof)));
+    }
+
+    #[test]
+    fn test_unnamed_little_endian_root() {
+        // `{"a": 258s}` without the root's name, little endian
+        let buffer = b"\x0a\x02\x01\x00a\x02\x01\x00";
+        let mut parser = Parser::from_slice(&buffer[..]);
+        parser.set_named_root(false);
+        parser.set_byte_order(ByteOrder::LittleEndian);
+
+        parser.advance().unwrap();
+        assert_eq!(parser.get_string_value().unwrap(), "");
+        parser.advance().unwrap();
+        parser.advance().unwrap();
+        assert_eq!(parser.get_string_value().unwrap(), "a");
+        parser.advance().unwrap();
+        assert_eq!(parser.get_i16_value().unwrap(), 258);
+    }
+
+    // `{"l": [{"": 1b}, {"": "a"}, {"b": 2s}, {"": {}}]}`
+    const HETEROGENEOUS_LIST: &[u8] = b"\x0a\x00\x00\
+        \x09\x00\x01l\x0a\x00\x00\x00\x04\
+            \x01\x00\x00\x01\x00\
+            \x08\x00\x00\x00\x01a\x00\
+            \x02\x00\x01b\x00\x02\x00\
+            \x0a\x00\x00\x00\x00\
+        \x00";
+
+    #[test]
+    fn test_heterogeneous_list() {
+        let mut parser = Parser::from_slice(HETEROGENEOUS_LIST);
+        parser.set_heterogeneous_lists(true);
+        let events: Result<Vec<Event>> = parser.collect();
+        assert_eq!(events.unwrap(), vec![
+            Event::TagStart { name: "".into(), ty: nbt::TAG_COMPOUND },
+            Event::CompoundStart,
+            Event::TagStart { name: "l".into(), ty: nbt::TAG_LIST },
+            Event::ListStart { ty: nbt::TAG_COMPOUND, len: 4 },
+            Event::I8(1),
+            Event::String("a".into()),
+            Event::CompoundStart,
+            Event::TagStart { name: "b".into(), ty: nbt::TAG_I16 },
+            Event::I16(2),
+            Event::End,
+            Event::CompoundStart,
+            Event::End,
+            Event::End,
+            Event::End,
+        ]);
+
+        // without unwrapping the elements are plain compounds
+        let events: Result<Vec<Event>> = Parser::from_slice(HETEROGENEOUS_LIST).collect();
+        assert_eq!(events.unwrap()[4..7], [
+            Event::CompoundStart,
+            Event::TagStart { name: "".into(), ty: nbt::TAG_I8 },
+            Event::I8(1),
+        ]);
+    }
+
+    #[test]
+    fn test_heterogeneous_list_skip() {
+        let mut parser = Parser::from_slice(HETEROGENEOUS_LIST);
+        parser.set_heterogeneous_lists(true);
+        for _ in 0..6 {
+            parser.advance().unwrap();
+        }
+        assert_eq!(parser.get_string_value().unwrap(), "a");
+
+        // skip the compound whose first header has already been read
+        parser.advance().unwrap();
+        assert_eq!(parser.get_value_type(), ValueType::MapBegin);
+        parser.skip_value().unwrap();
+        parser.advance().unwrap();
+        assert_eq!(parser.get_value_type(), ValueType::MapBegin);
+        assert!(parse_all(&mut parser).is_ok());
+    }
+
+    #[test]
+    fn test_compound_element_with_unnamed_tag() {
+        // `[{"": 1b, "a": 2b}, {"": {"b": 1b}, "c": 2b}, {"": [1s]}]`, only
+        // compounds holding nothing but the tag named "" are unwrapped
+        let buffer = b"\x09\x00\x00\x0a\x00\x00\x00\x03\
+            \x01\x00\x00\x01\x01\x00\x01a\x02\x00\
+            \x0a\x00\x00\x01\x00\x01b\x01\x00\x01\x00\x01c\x02\x00\
+            \x09\x00\x00\x02\x00\x00\x00\x01\x00\x01\x00";
+        let mut parser = Parser::new(&buffer[..]);
+        parser.set_heterogeneous_lists(true);
+        let events: Result<Vec<Event>> = parser.collect();
+        assert_eq!(events.unwrap(), vec![
+            Event::TagStart { name: "".into(), ty: nbt::TAG_LIST },
+            Event::ListStart { ty: nbt::TAG_COMPOUND, len: 3 },
+            Event::CompoundStart,
+            Event::TagStart { name: "".into(), ty: nbt::TAG_I8 },
+            Event::I8(1),
+            Event::TagStart { name: "a".into(), ty: nbt::TAG_I8 },
+            Event::I8(2),
+            Event::End,
+            Event::CompoundStart,
+            Event::TagStart { name: "".into(), ty: nbt::TAG_COMPOUND },
+            Event::CompoundStart,
+            Event::TagStart { name: "b".into(), ty: nbt::TAG_I8 },
+            Event::I8(1),
+            Event::End,
+            Event::TagStart { name: "c".into(), ty: nbt::TAG_I8 },
+            Event::I8(2),
+            Event::End,
+            Event::ListStart { ty: nbt::TAG_I16, len: 1 },
+            Event::I16(1),
+            Event::End,
+            Event::End,
+        ]);
+
+        // input ending after the first tag's value of a wrapped element
+        let mut parser = Parser::new(&buffer[..buffer.len() - 1]);
+        parser.set_heterogeneous_lists(true);
+        assert!(parse_all(&mut parser).is_err());
+    }
+
+    #[test]
+    fn test_nested_wrapped_elements() {
+        // `[{"": [{"": 1}, {"": 1}, ...]}]`, the elements of the inner list
+        // are read ahead again while the outer element is read again
+        let len = 100_000;
+        let mut buffer = b"\x09\x00\x00\x0a\x00\x00\x00\x01\x09\x00\x00\x0a".to_vec();
+        buffer.extend_from_slice(&(len as i32).to_be_bytes());
+        for _ in 0..len {
+            buffer.extend_from_slice(b"\x03\x00\x00\x00\x00\x00\x01\x00");
+        }
+        buffer.push(0);
+
+        let start = std::time::Instant::now();
+        let mut parser = Parser::new(&buffer[..]);
+        parser.set_heterogeneous_lists(true);
+        let events = parser.collect::<Result<Vec<Event>>>().unwrap();
+        assert_eq!(events.len(), len + 5);
+        assert!(start.elapsed() < std::time::Duration::from_secs(2));
+    }
+
+    #[test]
+    fn test_empty_list_types() {
+        // `{"a": [], "b": []}` with the element types `TAG_END` and `TAG_I32`
+        let buffer = b"\x0a\x00\x00\x09\x00\x01a\x00\x00\x00\x00\x00\x09\x00\x01b\x03\x00\x00\x00\x00\x00";
+        let mut parser = Parser::from_slice(&buffer[..]);
+        assert!(parse_all(&mut parser).is_ok());
+
+        // element types that are not defined are also ignored
+        let buffer = b"\x09\x00\x00\x63\x00\x00\x00\x00";
+        let mut parser = Parser::from_slice(&buffer[..]);
+        assert!(parse_all(&mut parser).is_ok());
+    }
+
+    #[test]
+    fn test_missing_list_type() {
+        // `[1b]` with the element type `TAG_END`
+        let buffer = b"\x09\x00\x00\x00\x00\x00\x00\x01\x01";
+        let mut parser = Parser::from_slice(&buffer[..]);
+        assert!(matches!(parse_all(&mut parser), Err(Error::MissingListTypeError)));
+
+        // also while skipping, `{"l": [[1b]]}`
+        let buffer = b"\x0a\x00\x00\x09\x00\x01l\x09\x00\x00\x00\x01\x00\x00\x00\x00\x01\x01\x00";
+        let mut parser = Parser::from_slice(&buffer[..]);
+        parser.advance().unwrap();
+        parser.advance().unwrap();
+        assert!(matches!(parser.skip_value(), Err(Error::MissingListTypeError)));
+    }
 }
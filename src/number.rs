@@ -0,0 +1,146 @@
+// Conversions between NBT's numeric tags as done by vanilla's `NumericTag`
+// getters, used when the deserializer reads numbers leniently
+
+/// the value of any of the numeric tags
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Number {
+    I8(i8),
+    I16(i16),
+    I32(i32),
+    I64(i64),
+    F32(f32),
+    F64(f64),
+}
+
+impl Number {
+    /// narrowing keeps the low bits, floats are floored first
+    pub(crate) fn as_i8(self) -> i8 {
+        match self {
+            Number::I8(v) => v,
+            Number::I16(v) => v as i8,
+            Number::I32(v) => v as i8,
+            Number::I64(v) => v as i8,
+            Number::F32(v) => floor_f32(v) as i8,
+            Number::F64(v) => floor_f64(v) as i8,
+        }
+    }
+
+    /// narrowing keeps the low bits, floats are floored first
+    pub(crate) fn as_i16(self) -> i16 {
+        match self {
+            Number::I8(v) => v as i16,
+            Number::I16(v) => v,
+            Number::I32(v) => v as i16,
+            Number::I64(v) => v as i16,
+            Number::F32(v) => floor_f32(v) as i16,
+            Number::F64(v) => floor_f64(v) as i16,
+        }
+    }
+
+    /// narrowing keeps the low bits, floats are floored
+    pub(crate) fn as_i32(self) -> i32 {
+        match self {
+            Number::I8(v) => v as i32,
+            Number::I16(v) => v as i32,
+            Number::I32(v) => v,
+            Number::I64(v) => v as i32,
+            Number::F32(v) => floor_f32(v),
+            Number::F64(v) => floor_f64(v),
+        }
+    }
+
+    /// `TAG_F32` is truncated towards zero but `TAG_F64` is floored, like
+    /// vanilla does
+    pub(crate) fn as_i64(self) -> i64 {
+        match self {
+            Number::I8(v) => v as i64,
+            Number::I16(v) => v as i64,
+            Number::I32(v) => v as i64,
+            Number::I64(v) => v,
+            Number::F32(v) => v as i64,
+            Number::F64(v) => v.floor() as i64,
+        }
+    }
+
+    pub(crate) fn as_f32(self) -> f32 {
+        match self {
+            Number::I8(v) => v as f32,
+            Number::I16(v) => v as f32,
+            Number::I32(v) => v as f32,
+            Number::I64(v) => v as f32,
+            Number::F32(v) => v,
+            Number::F64(v) => v as f32,
+        }
+    }
+
+    pub(crate) fn as_f64(self) -> f64 {
+        match self {
+            Number::I8(v) => v as f64,
+            Number::I16(v) => v as f64,
+            Number::I32(v) => v as f64,
+            Number::I64(v) => v as f64,
+            Number::F32(v) => v as f64,
+            Number::F64(v) => v,
+        }
+    }
+
+    /// vanilla's `getBoolean` compares the value as a byte with zero
+    pub(crate) fn as_bool(self) -> bool {
+        self.as_i8() != 0
+    }
+}
+
+// `Mth.floor`, the cast to int saturates and turns NaN into zero like Java's
+fn floor_f32(value: f32) -> i32 {
+    let truncated = value as i32;
+    if value < truncated as f32 {
+        truncated.wrapping_sub(1)
+    } else {
+        truncated
+    }
+}
+
+fn floor_f64(value: f64) -> i32 {
+    let truncated = value as i32;
+    if value < truncated as f64 {
+        truncated.wrapping_sub(1)
+    } else {
+        truncated
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_narrowing_keeps_low_bits() {
+        assert_eq!(Number::I32(0x1234_5678).as_i16(), 0x5678);
+        assert_eq!(Number::I32(0x1234_5678).as_i8(), 0x78);
+        assert_eq!(Number::I64(-1).as_i32(), -1);
+        assert_eq!(Number::I16(256).as_i8(), 0);
+        assert!(!Number::I16(256).as_bool());
+        assert!(Number::I16(257).as_bool());
+    }
+
+    #[test]
+    fn test_widening() {
+        assert_eq!(Number::I8(-3).as_i64(), -3);
+        assert_eq!(Number::I16(-300).as_i32(), -300);
+        assert_eq!(Number::I32(7).as_f64(), 7.0);
+        assert_eq!(Number::F32(0.5).as_f64(), 0.5);
+    }
+
+    #[test]
+    fn test_floats_to_ints() {
+        assert_eq!(Number::F64(2.7).as_i32(), 2);
+        assert_eq!(Number::F64(-2.5).as_i32(), -3);
+        assert_eq!(Number::F32(-2.5).as_i32(), -3);
+        assert_eq!(Number::F32(-2.5).as_i64(), -2);
+        assert_eq!(Number::F64(-2.5).as_i64(), -3);
+        assert_eq!(Number::F64(f64::NAN).as_i32(), 0);
+        assert_eq!(Number::F64(1e20).as_i32(), i32::MAX);
+        assert_eq!(Number::F64(1e20).as_i64(), i64::MAX);
+        assert_eq!(Number::F32(300.9).as_i8(), 44);
+    }
+}
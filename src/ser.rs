@@ -1,18 +1,89 @@
-use crate::error::{Error, Result};
+use std::convert::TryFrom;
+use log::trace;
 use serde::{ser, Serialize};
+use crate::array;
+use crate::config::SerializerConfig;
+use crate::error::{Error, Result};
+use crate::list;
+use crate::nbt;
+use crate::nbt::emitter::Emitter;
+use crate::variant;
 
-pub fn to_writer<W, T>(output: W, value: T) -> Result<()>
+pub fn to_writer<W, T>(output: W, value: &T) -> Result<()>
 where
     W: std::io::Write,
-    T: Serialize,
+    T: ?Sized + Serialize,
 {
     let mut serializer = Serializer::new(output);
     value.serialize(&mut serializer)?;
     Ok(())
 }
 
+/// like `to_writer`, but gives the root tag a name
+pub fn to_writer_named<W, T>(output: W, name: &str, value: &T) -> Result<()>
+where
+    W: std::io::Write,
+    T: ?Sized + Serialize,
+{
+    SerializerConfig::default().to_writer_named(output, name, value)
+}
+
 pub struct Serializer<W> {
-    _output: W,
+    emitter: Emitter<W>,
+    stack: Vec<Context>,
+    // set by the array wrapper types, the next sequence is written as this
+    // array type instead of as a `TAG_LIST`
+    array_type: Option<u8>,
+    // set by `byte_enum` and `int_enum`, the next unit variant is written as
+    // its index with this tag type instead of as its name
+    variant_type: Option<u8>,
+    // set by the list modules, the element type of the next sequence if it
+    // turns out to be empty
+    list_type: Option<u8>,
+    config: SerializerConfig,
+    root_name: String,
+}
+
+/// the type of value that is currently being written
+#[derive(Debug)]
+enum Context {
+    // inside a `TAG_COMPOUND`, `name` is the name of the next tag
+    Compound { name: Option<String> },
+    // inside a `TAG_LIST`, the list is begun along with the first element
+    // since the element type is not known before then, lists of unknown
+    // length are buffered by the emitter, empty lists are written with
+    // `empty_type` as their element type
+    List { header: Option<(String, Option<usize>)>, empty_type: u8 },
+    // inside one of the array types, the elements are collected and the
+    // array is written once the sequence ends
+    Array { name: String, len: Option<usize>, values: ArrayValues },
+}
+
+/// the elements of an array that is being written
+#[derive(Debug)]
+enum ArrayValues {
+    I8(Vec<i8>),
+    I32(Vec<i32>),
+    I64(Vec<i64>),
+}
+
+impl ArrayValues {
+    fn len(&self) -> usize {
+        match self {
+            ArrayValues::I8(values) => values.len(),
+            ArrayValues::I32(values) => values.len(),
+            ArrayValues::I64(values) => values.len(),
+        }
+    }
+
+    /// the number of bytes the elements take up
+    fn size(&self) -> usize {
+        match self {
+            ArrayValues::I8(values) => values.len(),
+            ArrayValues::I32(values) => values.len() * 4,
+            ArrayValues::I64(values) => values.len() * 8,
+        }
+    }
 }
 
 impl<W> Serializer<W>
@@ -20,11 +91,208 @@ where
     W: std::io::Write,
 {
     pub fn new(output: W) -> Self {
-        Self { _output: output }
+        Serializer::with_config(output, SerializerConfig::default())
+    }
+
+    pub fn with_config(output: W, config: SerializerConfig) -> Self {
+        #[cfg(debug_assertions)]
+        trace!("Serializer::with_config");
+
+        let mut emitter = Emitter::new(output);
+        emitter.set_byte_order(config.byte_order);
+        emitter.set_string_encoding(config.string_encoding);
+        emitter.set_named_root(config.root_mode.is_named());
+        emitter.set_max_depth(config.max_depth);
+        emitter.set_size_quota(config.size_quota);
+        emitter.set_sort_keys(config.sort_keys);
+        emitter.set_buffer_limit(config.buffer_limit);
+        emitter.set_heterogeneous_lists(config.heterogeneous_lists);
+
+        Serializer {
+            emitter,
+            stack: Vec::new(),
+            array_type: None,
+            variant_type: None,
+            list_type: None,
+            config,
+            root_name: String::new(),
+        }
+    }
+
+    /// sets the name of the root tag, which has to be empty if the root
+    /// mode has no names
+    pub fn set_root_name(&mut self, name: &str) -> Result<()> {
+        if !name.is_empty() && !self.config.root_mode.is_named() {
+            return Err(Error::Message("the root tag can not have a name in this root mode".to_string()));
+        }
+        self.root_name = name.to_string();
+        Ok(())
+    }
+
+    /// returns the name to write a value of type `tag_type` with, beginning
+    /// the current list if this is its first element
+    fn begin_value(&mut self, tag_type: u8) -> Result<String> {
+        #[cfg(debug_assertions)]
+        trace!("Serializer::begin_value({:?})", tag_type);
+
+        // the array wrapper types and list modules may only contain a
+        // sequence, and the enum wrappers only a unit variant
+        if self.array_type.is_some() || self.variant_type.is_some() || self.list_type.is_some() {
+            return Err(Error::InvalidTagTypeError);
+        }
+
+        match self.stack.last_mut() {
+            None => {
+                self.config.root_mode.check_tag_type(tag_type)?;
+                Ok(std::mem::take(&mut self.root_name))
+            },
+            Some(Context::Compound { name }) => {
+                name.take().ok_or(Error::InvalidEmitterStateError)
+            },
+            Some(Context::List { header, .. }) => {
+                match header.take() {
+                    Some((name, Some(len))) => self.emitter.begin_list(&name, tag_type, len)?,
+                    Some((name, None)) => self.emitter.begin_buffered_list(&name, tag_type)?,
+                    None => {},
+                }
+                Ok(String::new())
+            },
+            Some(Context::Array { .. }) => Err(Error::InvalidTagTypeError),
+        }
+    }
+
+    /// adds an element to the current array, returns `false` if the current
+    /// value is not an array
+    fn push_array_value(&mut self, value: ArrayValue) -> Result<bool> {
+        #[cfg(debug_assertions)]
+        trace!("Serializer::push_array_value({:?})", value);
+
+        let (len, values) = match self.stack.last_mut() {
+            Some(Context::Array { len, values, .. }) => (len, values),
+            _ => return Ok(false),
+        };
+
+        match (&mut *values, value) {
+            (ArrayValues::I8(values), ArrayValue::I8(value)) => values.push(value),
+            (ArrayValues::I32(values), ArrayValue::I32(value)) => values.push(value),
+            (ArrayValues::I64(values), ArrayValue::I64(value)) => values.push(value),
+            _ => return Err(Error::InvalidTagTypeError),
+        }
+
+        // arrays of unknown length are bounded like lists
+        if len.is_none() && values.size() > self.config.buffer_limit {
+            return Err(Error::BufferLimitError);
+        }
+        Ok(true)
+    }
+
+    fn begin_seq(&mut self, len: Option<usize>) -> Result<()> {
+        #[cfg(debug_assertions)]
+        trace!("Serializer::begin_seq");
+
+        // NBT lists and arrays are prefixed with their length, without it the
+        // elements are buffered until the sequence ends
+        match self.array_type.take() {
+            Some(array_type) => {
+                let capacity = len.unwrap_or(0);
+                let values = match array_type {
+                    nbt::TAG_I8_ARRAY => ArrayValues::I8(Vec::with_capacity(capacity)),
+                    nbt::TAG_I32_ARRAY => ArrayValues::I32(Vec::with_capacity(capacity)),
+                    nbt::TAG_I64_ARRAY => ArrayValues::I64(Vec::with_capacity(capacity)),
+                    _ => return Err(Error::InvalidTagTypeError),
+                };
+                let name = self.begin_value(array_type)?;
+                self.stack.push(Context::Array { name, len, values });
+            },
+            None => {
+                let empty_type = match self.list_type.take() {
+                    Some(list_type) if self.config.typed_empty_lists => list_type,
+                    _ => nbt::TAG_END,
+                };
+                let name = self.begin_value(nbt::TAG_LIST)?;
+                self.stack.push(Context::List { header: Some((name, len)), empty_type });
+            },
+        }
+        Ok(())
+    }
+
+    fn end_seq(&mut self) -> Result<()> {
+        #[cfg(debug_assertions)]
+        trace!("Serializer::end_seq");
+
+        match self.stack.pop() {
+            Some(Context::List { header, empty_type }) => {
+                // an empty list has not been begun yet
+                if let Some((name, len)) = header {
+                    if let Some(len) = len.filter(|&len| len != 0) {
+                        return Err(Error::ListLengthError { expected: len, actual: 0 });
+                    }
+                    self.emitter.begin_list(&name, empty_type, 0)?;
+                }
+                self.emitter.end()
+            },
+            Some(Context::Array { name, len, values }) => {
+                if let Some(len) = len.filter(|&len| len != values.len()) {
+                    return Err(Error::ListLengthError { expected: len, actual: values.len() });
+                }
+
+                match values {
+                    ArrayValues::I8(values) => self.emitter.write_i8_array(&name, &values),
+                    ArrayValues::I32(values) => self.emitter.write_i32_array(&name, &values),
+                    ArrayValues::I64(values) => self.emitter.write_i64_array(&name, &values),
+                }
+            },
+            _ => Err(Error::InvalidEmitterStateError),
+        }
+    }
+
+    fn begin_compound(&mut self) -> Result<()> {
+        #[cfg(debug_assertions)]
+        trace!("Serializer::begin_compound");
+
+        let name = self.begin_value(nbt::TAG_COMPOUND)?;
+        self.emitter.begin_compound(&name)?;
+        self.stack.push(Context::Compound { name: None });
+        Ok(())
+    }
+
+    fn end_compound(&mut self) -> Result<()> {
+        #[cfg(debug_assertions)]
+        trace!("Serializer::end_compound");
+
+        match self.stack.pop() {
+            Some(Context::Compound { name: None }) => self.emitter.end(),
+            _ => Err(Error::InvalidEmitterStateError),
+        }
+    }
+
+    /// sets the name of the next tag inside the current `TAG_COMPOUND`
+    fn set_tag_name(&mut self, tag_name: String) -> Result<()> {
+        #[cfg(debug_assertions)]
+        trace!("Serializer::set_tag_name({:?})", tag_name);
+
+        match self.stack.last_mut() {
+            Some(Context::Compound { name }) => {
+                *name = Some(tag_name);
+                Ok(())
+            },
+            _ => Err(Error::InvalidEmitterStateError),
+        }
     }
 }
 
-impl<'ser, W> ser::Serializer for &'ser mut Serializer<W> {
+/// an element of one of the array types
+#[derive(Debug)]
+enum ArrayValue {
+    I8(i8),
+    I32(i32),
+    I64(i64),
+}
+
+impl<W> ser::Serializer for &mut Serializer<W>
+where
+    W: std::io::Write,
+{
     type Ok = ();
     type Error = Error;
     type SerializeSeq = Self;
@@ -35,275 +303,1257 @@ impl<'ser, W> ser::Serializer for &'ser mut Serializer<W> {
     type SerializeStruct = Self;
     type SerializeStructVariant = Self;
 
-    fn serialize_bool(self, _v: bool) -> Result<Self::Ok> {
-        // TODO: convert to byte
-        todo!()
+    fn serialize_bool(self, v: bool) -> Result<Self::Ok> {
+        #[cfg(debug_assertions)]
+        trace!("Serializer::serialize_bool({:?})", v);
+
+        self.serialize_i8(v as i8)
     }
 
-    fn serialize_i8(self, _v: i8) -> Result<Self::Ok> {
-        todo!()
+    fn serialize_i8(self, v: i8) -> Result<Self::Ok> {
+        #[cfg(debug_assertions)]
+        trace!("Serializer::serialize_i8({:?})", v);
+
+        if self.push_array_value(ArrayValue::I8(v))? {
+            return Ok(());
+        }
+
+        let name = self.begin_value(nbt::TAG_I8)?;
+        self.emitter.write_i8(&name, v)
     }
 
-    fn serialize_i16(self, _v: i16) -> Result<Self::Ok> {
-        todo!()
+    fn serialize_i16(self, v: i16) -> Result<Self::Ok> {
+        #[cfg(debug_assertions)]
+        trace!("Serializer::serialize_i16({:?})", v);
+
+        let name = self.begin_value(nbt::TAG_I16)?;
+        self.emitter.write_i16(&name, v)
     }
 
-    fn serialize_i32(self, _v: i32) -> Result<Self::Ok> {
-        todo!()
+    fn serialize_i32(self, v: i32) -> Result<Self::Ok> {
+        #[cfg(debug_assertions)]
+        trace!("Serializer::serialize_i32({:?})", v);
+
+        if self.push_array_value(ArrayValue::I32(v))? {
+            return Ok(());
+        }
+
+        let name = self.begin_value(nbt::TAG_I32)?;
+        self.emitter.write_i32(&name, v)
     }
 
-    fn serialize_i64(self, _v: i64) -> Result<Self::Ok> {
-        todo!()
+    fn serialize_i64(self, v: i64) -> Result<Self::Ok> {
+        #[cfg(debug_assertions)]
+        trace!("Serializer::serialize_i64({:?})", v);
+
+        if self.push_array_value(ArrayValue::I64(v))? {
+            return Ok(());
+        }
+
+        let name = self.begin_value(nbt::TAG_I64)?;
+        self.emitter.write_i64(&name, v)
     }
 
-    fn serialize_u8(self, _v: u8) -> Result<Self::Ok> {
-        todo!()
+    fn serialize_u8(self, v: u8) -> Result<Self::Ok> {
+        #[cfg(debug_assertions)]
+        trace!("Serializer::serialize_u8({:?})", v);
+
+        // the same bits as a `TAG_I8`
+        self.serialize_i8(v as i8)
     }
 
-    fn serialize_u16(self, _v: u16) -> Result<Self::Ok> {
-        todo!()
+    fn serialize_u16(self, v: u16) -> Result<Self::Ok> {
+        #[cfg(debug_assertions)]
+        trace!("Serializer::serialize_u16({:?})", v);
+
+        // the same bits as a `TAG_I16`
+        self.serialize_i16(v as i16)
     }
 
-    fn serialize_u32(self, _v: u32) -> Result<Self::Ok> {
-        todo!()
+    fn serialize_u32(self, v: u32) -> Result<Self::Ok> {
+        #[cfg(debug_assertions)]
+        trace!("Serializer::serialize_u32({:?})", v);
+
+        let v = self.config.unsigned_mode.i32_from_u32(v)?;
+        self.serialize_i32(v)
     }
 
-    fn serialize_u64(self, _v: u64) -> Result<Self::Ok> {
-        todo!()
+    fn serialize_u64(self, v: u64) -> Result<Self::Ok> {
+        #[cfg(debug_assertions)]
+        trace!("Serializer::serialize_u64({:?})", v);
+
+        let v = self.config.unsigned_mode.i64_from_u64(v)?;
+        self.serialize_i64(v)
     }
 
-    fn serialize_f32(self, _v: f32) -> Result<Self::Ok> {
-        todo!()
+    fn serialize_f32(self, v: f32) -> Result<Self::Ok> {
+        #[cfg(debug_assertions)]
+        trace!("Serializer::serialize_f32({:?})", v);
+
+        let name = self.begin_value(nbt::TAG_F32)?;
+        self.emitter.write_f32(&name, v)
     }
 
-    fn serialize_f64(self, _v: f64) -> Result<Self::Ok> {
-        todo!()
+    fn serialize_f64(self, v: f64) -> Result<Self::Ok> {
+        #[cfg(debug_assertions)]
+        trace!("Serializer::serialize_f64({:?})", v);
+
+        let name = self.begin_value(nbt::TAG_F64)?;
+        self.emitter.write_f64(&name, v)
     }
 
-    fn serialize_char(self, _v: char) -> Result<Self::Ok> {
-        todo!()
+    fn serialize_char(self, v: char) -> Result<Self::Ok> {
+        #[cfg(debug_assertions)]
+        trace!("Serializer::serialize_char({:?})", v);
+
+        // a `TAG_STRING` holding the one character
+        self.serialize_str(v.encode_utf8(&mut [0; 4]))
     }
 
-    fn serialize_str(self, _v: &str) -> Result<Self::Ok> {
-        todo!()
+    fn serialize_str(self, v: &str) -> Result<Self::Ok> {
+        #[cfg(debug_assertions)]
+        trace!("Serializer::serialize_str({:?})", v);
+
+        let name = self.begin_value(nbt::TAG_STRING)?;
+        self.emitter.write_string(&name, v)
     }
 
-    fn serialize_bytes(self, _v: &[u8]) -> Result<Self::Ok> {
-        todo!()
+    fn serialize_bytes(self, v: &[u8]) -> Result<Self::Ok> {
+        #[cfg(debug_assertions)]
+        trace!("Serializer::serialize_bytes");
+
+        let name = self.begin_value(nbt::TAG_I8_ARRAY)?;
+        self.emitter.write_byte_array(&name, v)
     }
 
     fn serialize_none(self) -> Result<Self::Ok> {
-        todo!()
+        #[cfg(debug_assertions)]
+        trace!("Serializer::serialize_none");
+
+        if self.array_type.is_some() || self.variant_type.is_some() {
+            return Err(Error::InvalidTagTypeError);
+        }
+
+        // `None` is written by leaving out the compound's tag, lists have no
+        // way to leave out an element
+        match self.stack.last_mut() {
+            Some(Context::Compound { name }) => {
+                name.take().ok_or(Error::InvalidEmitterStateError)?;
+                Ok(())
+            },
+            _ => Err(Error::Message("None can only be written as the value of a compound's tag".to_string())),
+        }
     }
 
-    fn serialize_some<T: ?Sized>(self, _value: &T) -> Result<Self::Ok>
+    fn serialize_some<T>(self, value: &T) -> Result<Self::Ok>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
-        todo!()
+        #[cfg(debug_assertions)]
+        trace!("Serializer::serialize_some");
+
+        value.serialize(self)
     }
 
     fn serialize_unit(self) -> Result<Self::Ok> {
-        todo!()
+        #[cfg(debug_assertions)]
+        trace!("Serializer::serialize_unit");
+
+        // unit is written as an empty compound
+        self.begin_compound()?;
+        self.end_compound()
     }
 
     fn serialize_unit_struct(self, _name: &'static str) -> Result<Self::Ok> {
-        todo!()
+        #[cfg(debug_assertions)]
+        trace!("Serializer::serialize_unit_struct");
+
+        self.serialize_unit()
     }
 
     fn serialize_unit_variant(
         self,
         _name: &'static str,
-        _variant_index: u32,
-        _variant: &'static str,
+        variant_index: u32,
+        variant: &'static str,
     ) -> Result<Self::Ok> {
-        todo!()
+        #[cfg(debug_assertions)]
+        trace!("Serializer::serialize_unit_variant({:?})", variant);
+
+        match self.variant_type.take() {
+            Some(nbt::TAG_I8) => {
+                let index = i8::try_from(variant_index)
+                    .map_err(|_| Error::Message("variant index does not fit in a TAG_I8".to_string()))?;
+                self.serialize_i8(index)
+            },
+            Some(_) => {
+                let index = i32::try_from(variant_index)
+                    .map_err(|_| Error::Message("variant index does not fit in a TAG_I32".to_string()))?;
+                self.serialize_i32(index)
+            },
+            None => self.serialize_str(variant),
+        }
     }
 
-    fn serialize_newtype_struct<T: ?Sized>(
+    fn serialize_newtype_struct<T>(
         self,
-        _name: &'static str,
-        _value: &T,
+        name: &'static str,
+        value: &T,
     ) -> Result<Self::Ok>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
-        todo!()
+        #[cfg(debug_assertions)]
+        trace!("Serializer::serialize_newtype_struct({:?})", name);
+
+        let variant_type = match name {
+            variant::I8_VARIANT_NAME => Some(nbt::TAG_I8),
+            variant::I32_VARIANT_NAME => Some(nbt::TAG_I32),
+            _ => None,
+        };
+        if let Some(variant_type) = variant_type {
+            self.variant_type = Some(variant_type);
+            value.serialize(&mut *self)?;
+
+            // the wrapped value must have been a unit variant
+            return match self.variant_type.take() {
+                Some(_) => Err(Error::InvalidTagTypeError),
+                None => Ok(()),
+            };
+        }
+
+        if let Some(list_type) = list::list_type(name) {
+            self.list_type = Some(list_type);
+            value.serialize(&mut *self)?;
+
+            // the wrapped value must have been a sequence
+            return match self.list_type.take() {
+                Some(_) => Err(Error::InvalidTagTypeError),
+                None => Ok(()),
+            };
+        }
+
+        // other newtype structs are the same as the value they wrap
+        let array_type = match name {
+            array::I8_ARRAY_NAME => nbt::TAG_I8_ARRAY,
+            array::I32_ARRAY_NAME => nbt::TAG_I32_ARRAY,
+            array::I64_ARRAY_NAME => nbt::TAG_I64_ARRAY,
+            _ => return value.serialize(self),
+        };
+
+        self.array_type = Some(array_type);
+        value.serialize(&mut *self)?;
+
+        // the wrapped value must have been a sequence
+        match self.array_type.take() {
+            Some(_) => Err(Error::InvalidTagTypeError),
+            None => Ok(()),
+        }
     }
 
-    fn serialize_newtype_variant<T: ?Sized>(
+    fn serialize_newtype_variant<T>(
         self,
         _name: &'static str,
         _variant_index: u32,
-        _variant: &'static str,
-        _value: &T,
+        variant: &'static str,
+        value: &T,
     ) -> Result<Self::Ok>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
-        todo!()
+        #[cfg(debug_assertions)]
+        trace!("Serializer::serialize_newtype_variant({:?})", variant);
+
+        // written as a compound with the variant as its only tag
+        self.begin_compound()?;
+        self.set_tag_name(variant.to_string())?;
+        value.serialize(&mut *self)?;
+        self.end_compound()
     }
 
-    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq> {
-        todo!()
+    fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq> {
+        #[cfg(debug_assertions)]
+        trace!("Serializer::serialize_seq({:?})", len);
+
+        self.begin_seq(len)?;
+        Ok(self)
     }
 
-    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple> {
-        todo!()
+    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple> {
+        #[cfg(debug_assertions)]
+        trace!("Serializer::serialize_tuple({:?})", len);
+
+        self.begin_seq(Some(len))?;
+        Ok(self)
     }
 
     fn serialize_tuple_struct(
         self,
         _name: &'static str,
-        _len: usize,
+        len: usize,
     ) -> Result<Self::SerializeTupleStruct> {
-        todo!()
+        #[cfg(debug_assertions)]
+        trace!("Serializer::serialize_tuple_struct({:?})", len);
+
+        self.begin_seq(Some(len))?;
+        Ok(self)
     }
 
     fn serialize_tuple_variant(
         self,
         _name: &'static str,
         _variant_index: u32,
-        _variant: &'static str,
-        _len: usize,
+        variant: &'static str,
+        len: usize,
     ) -> Result<Self::SerializeTupleVariant> {
-        todo!()
+        #[cfg(debug_assertions)]
+        trace!("Serializer::serialize_tuple_variant({:?}, {:?})", variant, len);
+
+        self.begin_compound()?;
+        self.set_tag_name(variant.to_string())?;
+        self.begin_seq(Some(len))?;
+        Ok(self)
     }
 
     fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
-        todo!()
+        #[cfg(debug_assertions)]
+        trace!("Serializer::serialize_map");
+
+        self.begin_compound()?;
+        Ok(self)
     }
 
     fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeStruct> {
-        todo!()
+        #[cfg(debug_assertions)]
+        trace!("Serializer::serialize_struct");
+
+        self.begin_compound()?;
+        Ok(self)
     }
 
     fn serialize_struct_variant(
         self,
         _name: &'static str,
         _variant_index: u32,
-        _variant: &'static str,
+        variant: &'static str,
         _len: usize,
     ) -> Result<Self::SerializeStructVariant> {
-        todo!()
+        #[cfg(debug_assertions)]
+        trace!("Serializer::serialize_struct_variant({:?})", variant);
+
+        self.begin_compound()?;
+        self.set_tag_name(variant.to_string())?;
+        self.begin_compound()?;
+        Ok(self)
     }
 }
 
-impl<'ser, W> ser::SerializeSeq for &'ser mut Serializer<W> {
+impl<W> ser::SerializeSeq for &mut Serializer<W>
+where
+    W: std::io::Write,
+{
     type Ok = ();
     type Error = Error;
 
-    fn serialize_element<T: ?Sized>(&mut self, _value: &T) -> Result<Self::Ok>
+    fn serialize_element<T>(&mut self, value: &T) -> Result<Self::Ok>
     where
-        T: serde::Serialize,
+        T: ?Sized + serde::Serialize,
     {
-        todo!()
+        value.serialize(&mut **self)
     }
 
     fn end(self) -> Result<Self::Ok> {
-        todo!()
+        self.end_seq()
     }
 }
 
-impl<'ser, W> ser::SerializeTuple for &'ser mut Serializer<W> {
+impl<W> ser::SerializeTuple for &mut Serializer<W>
+where
+    W: std::io::Write,
+{
     type Ok = ();
     type Error = Error;
 
-    fn serialize_element<T: ?Sized>(&mut self, _value: &T) -> Result<Self::Ok>
+    fn serialize_element<T>(&mut self, value: &T) -> Result<Self::Ok>
     where
-        T: serde::Serialize,
+        T: ?Sized + serde::Serialize,
     {
-        todo!()
+        value.serialize(&mut **self)
     }
 
     fn end(self) -> Result<Self::Ok> {
-        todo!()
+        self.end_seq()
     }
 }
 
-impl<'ser, W> ser::SerializeTupleStruct for &'ser mut Serializer<W> {
+impl<W> ser::SerializeTupleStruct for &mut Serializer<W>
+where
+    W: std::io::Write,
+{
     type Ok = ();
     type Error = Error;
 
-    fn serialize_field<T: ?Sized>(&mut self, _value: &T) -> Result<()>
+    fn serialize_field<T>(&mut self, value: &T) -> Result<()>
     where
-        T: serde::Serialize,
+        T: ?Sized + serde::Serialize,
     {
-        todo!()
+        value.serialize(&mut **self)
     }
 
     fn end(self) -> Result<Self::Ok> {
-        todo!()
+        self.end_seq()
     }
 }
 
-impl<'ser, W> ser::SerializeTupleVariant for &'ser mut Serializer<W> {
+impl<W> ser::SerializeTupleVariant for &mut Serializer<W>
+where
+    W: std::io::Write,
+{
     type Ok = ();
     type Error = Error;
 
-    fn serialize_field<T: ?Sized>(&mut self, _value: &T) -> Result<Self::Ok>
+    fn serialize_field<T>(&mut self, value: &T) -> Result<Self::Ok>
     where
-        T: serde::Serialize,
+        T: ?Sized + serde::Serialize,
     {
-        todo!()
+        value.serialize(&mut **self)
     }
 
     fn end(self) -> Result<Self::Ok> {
-        todo!()
+        self.end_seq()?;
+        self.end_compound()
     }
 }
 
-impl<'ser, W> ser::SerializeMap for &'ser mut Serializer<W> {
+impl<W> ser::SerializeMap for &mut Serializer<W>
+where
+    W: std::io::Write,
+{
     type Ok = ();
     type Error = Error;
 
-    fn serialize_key<T: ?Sized>(&mut self, _key: &T) -> Result<Self::Ok>
+    fn serialize_key<T>(&mut self, key: &T) -> Result<Self::Ok>
     where
-        T: serde::Serialize,
+        T: ?Sized + serde::Serialize,
     {
-        todo!()
+        let name = key.serialize(KeySerializer)?;
+        self.set_tag_name(name)
     }
 
-    fn serialize_value<T: ?Sized>(&mut self, _value: &T) -> Result<Self::Ok>
+    fn serialize_value<T>(&mut self, value: &T) -> Result<Self::Ok>
     where
-        T: serde::Serialize,
+        T: ?Sized + serde::Serialize,
     {
-        todo!()
+        value.serialize(&mut **self)
     }
 
     fn end(self) -> Result<Self::Ok> {
-        todo!()
+        self.end_compound()
     }
 }
 
-impl<'ser, W> ser::SerializeStruct for &'ser mut Serializer<W> {
+impl<W> ser::SerializeStruct for &mut Serializer<W>
+where
+    W: std::io::Write,
+{
     type Ok = ();
     type Error = Error;
 
-    fn serialize_field<T: ?Sized>(&mut self, _key: &'static str, _value: &T) -> Result<Self::Ok>
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<Self::Ok>
     where
-        T: serde::Serialize,
+        T: ?Sized + serde::Serialize,
     {
-        todo!()
+        self.set_tag_name(key.to_string())?;
+        value.serialize(&mut **self)
     }
 
     fn end(self) -> Result<Self::Ok> {
-        todo!()
+        self.end_compound()
     }
 }
 
-impl<'ser, W> ser::SerializeStructVariant for &'ser mut Serializer<W> {
+impl<W> ser::SerializeStructVariant for &mut Serializer<W>
+where
+    W: std::io::Write,
+{
     type Ok = ();
     type Error = Error;
 
-    fn serialize_field<T: ?Sized>(&mut self, _key: &'static str, _value: &T) -> Result<Self::Ok>
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<Self::Ok>
     where
-        T: serde::Serialize,
+        T: ?Sized + serde::Serialize,
     {
-        todo!()
+        self.set_tag_name(key.to_string())?;
+        value.serialize(&mut **self)
     }
 
     fn end(self) -> Result<Self::Ok> {
-        todo!()
+        self.end_compound()?;
+        self.end_compound()
+    }
+}
+
+/// serializes the keys of maps, which have to be strings since they are
+/// written as the names of the compound's tags
+struct KeySerializer;
+
+impl ser::Serializer for KeySerializer {
+    type Ok = String;
+    type Error = Error;
+    type SerializeSeq = ser::Impossible<String, Error>;
+    type SerializeTuple = ser::Impossible<String, Error>;
+    type SerializeTupleStruct = ser::Impossible<String, Error>;
+    type SerializeTupleVariant = ser::Impossible<String, Error>;
+    type SerializeMap = ser::Impossible<String, Error>;
+    type SerializeStruct = ser::Impossible<String, Error>;
+    type SerializeStructVariant = ser::Impossible<String, Error>;
+
+    fn serialize_bool(self, _v: bool) -> Result<String> {
+        Err(Error::InvalidTagTypeError)
+    }
+
+    fn serialize_i8(self, _v: i8) -> Result<String> {
+        Err(Error::InvalidTagTypeError)
+    }
+
+    fn serialize_i16(self, _v: i16) -> Result<String> {
+        Err(Error::InvalidTagTypeError)
+    }
+
+    fn serialize_i32(self, _v: i32) -> Result<String> {
+        Err(Error::InvalidTagTypeError)
+    }
+
+    fn serialize_i64(self, _v: i64) -> Result<String> {
+        Err(Error::InvalidTagTypeError)
+    }
+
+    fn serialize_u8(self, _v: u8) -> Result<String> {
+        Err(Error::InvalidTagTypeError)
+    }
+
+    fn serialize_u16(self, _v: u16) -> Result<String> {
+        Err(Error::InvalidTagTypeError)
+    }
+
+    fn serialize_u32(self, _v: u32) -> Result<String> {
+        Err(Error::InvalidTagTypeError)
+    }
+
+    fn serialize_u64(self, _v: u64) -> Result<String> {
+        Err(Error::InvalidTagTypeError)
+    }
+
+    fn serialize_f32(self, _v: f32) -> Result<String> {
+        Err(Error::InvalidTagTypeError)
+    }
+
+    fn serialize_f64(self, _v: f64) -> Result<String> {
+        Err(Error::InvalidTagTypeError)
+    }
+
+    fn serialize_char(self, v: char) -> Result<String> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_str(self, v: &str) -> Result<String> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_bytes(self, _v: &[u8]) -> Result<String> {
+        Err(Error::InvalidTagTypeError)
+    }
+
+    fn serialize_none(self) -> Result<String> {
+        Err(Error::InvalidTagTypeError)
+    }
+
+    fn serialize_some<T>(self, _value: &T) -> Result<String>
+    where
+        T: ?Sized + Serialize,
+    {
+        Err(Error::InvalidTagTypeError)
+    }
+
+    fn serialize_unit(self) -> Result<String> {
+        Err(Error::InvalidTagTypeError)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<String> {
+        Err(Error::InvalidTagTypeError)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<String> {
+        Ok(variant.to_string())
+    }
+
+    fn serialize_newtype_struct<T>(self, _name: &'static str, value: &T) -> Result<String>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<String>
+    where
+        T: ?Sized + Serialize,
+    {
+        Err(Error::InvalidTagTypeError)
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq> {
+        Err(Error::InvalidTagTypeError)
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple> {
+        Err(Error::InvalidTagTypeError)
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct> {
+        Err(Error::InvalidTagTypeError)
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant> {
+        Err(Error::InvalidTagTypeError)
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
+        Err(Error::InvalidTagTypeError)
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeStruct> {
+        Err(Error::InvalidTagTypeError)
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant> {
+        Err(Error::InvalidTagTypeError)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Serialize;
+    use crate::{ByteArray, ByteOrder, IntArray, LongArray, RootMode, StringEncoding, UnsignedMode};
+    use super::*;
+
+    #[test]
+    fn test_primitive_tags() {
+        #[derive(Serialize)]
+        struct Test {
+            a: i8,
+            b: i16,
+            c: i32,
+            d: i64,
+            e: f32,
+            f: f64,
+            g: bool,
+            h: String,
+        }
+
+        let value = Test { a: 1, b: 2, c: 3, d: 4, e: 0.0, f: 0.0, g: true, h: "hi".to_string() };
+        let mut buffer = Vec::new();
+        to_writer(&mut buffer, &value).unwrap();
+
+        // `{"a": 1b, "b": 2s, "c": 3, "d": 4L, "e": 0f, "f": 0d, "g": 1b, "h": "hi"}`
+        let expected = b"\x0a\x00\x00\
+            \x01\x00\x01a\x01\
+            \x02\x00\x01b\x00\x02\
+            \x03\x00\x01c\x00\x00\x00\x03\
+            \x04\x00\x01d\x00\x00\x00\x00\x00\x00\x00\x04\
+            \x05\x00\x01e\x00\x00\x00\x00\
+            \x06\x00\x01f\x00\x00\x00\x00\x00\x00\x00\x00\
+            \x01\x00\x01g\x01\
+            \x08\x00\x01h\x00\x02hi\
+            \x00";
+        assert_eq!(buffer, &expected[..]);
+    }
+
+    #[test]
+    fn test_nested_lists_and_compounds() {
+        #[derive(Serialize)]
+        struct Inner {
+            a: i8,
+        }
+
+        #[derive(Serialize)]
+        struct Test {
+            lists: Vec<Vec<i16>>,
+            compounds: Vec<Inner>,
+            inner: Inner,
+        }
+
+        let value = Test {
+            lists: vec![vec![1], vec![]],
+            compounds: vec![Inner { a: 2 }],
+            inner: Inner { a: 3 },
+        };
+        let mut buffer = Vec::new();
+        to_writer(&mut buffer, &value).unwrap();
+
+        // `{"lists": [[1s], []], "compounds": [{"a": 2b}], "inner": {"a": 3b}}`
+        let expected = b"\x0a\x00\x00\
+            \x09\x00\x05lists\x09\x00\x00\x00\x02\
+                \x02\x00\x00\x00\x01\x00\x01\
+                \x00\x00\x00\x00\x00\
+            \x09\x00\x09compounds\x0a\x00\x00\x00\x01\
+                \x01\x00\x01a\x02\x00\
+            \x0a\x00\x05inner\
+                \x01\x00\x01a\x03\x00\
+            \x00";
+        assert_eq!(buffer, &expected[..]);
+    }
+
+    #[test]
+    fn test_root_must_be_compound() {
+        let mut buffer = Vec::new();
+        assert!(to_writer(&mut buffer, &1i32).is_err());
+    }
+
+    #[test]
+    fn test_array_wrappers() {
+        #[derive(Serialize)]
+        struct Test {
+            b: ByteArray,
+            i: IntArray,
+            l: LongArray,
+            list: Vec<i32>,
+        }
+
+        let value = Test {
+            b: ByteArray(vec![1, 2]),
+            i: IntArray(vec![3]),
+            l: LongArray(vec![]),
+            list: vec![4],
+        };
+        let mut buffer = Vec::new();
+        to_writer(&mut buffer, &value).unwrap();
+
+        // `{"b": [B; 1b, 2b], "i": [I; 3], "l": [L;], "list": [4]}`
+        let expected = b"\x0a\x00\x00\
+            \x07\x00\x01b\x00\x00\x00\x02\x01\x02\
+            \x0b\x00\x01i\x00\x00\x00\x01\x00\x00\x00\x03\
+            \x0c\x00\x01l\x00\x00\x00\x00\
+            \x09\x00\x04list\x03\x00\x00\x00\x01\x00\x00\x00\x04\
+            \x00";
+        assert_eq!(buffer, &expected[..]);
+    }
+
+    #[test]
+    fn test_array_with_modules() {
+        #[derive(Serialize)]
+        struct Test {
+            #[serde(rename = "UUID", with = "crate::int_array")]
+            uuid: [i32; 4],
+            #[serde(with = "crate::long_array")]
+            states: Vec<i64>,
+            lists: Vec<IntArray>,
+        }
+
+        let value = Test {
+            uuid: [1, 2, 3, 4],
+            states: vec![5],
+            lists: vec![IntArray(vec![6])],
+        };
+        let mut buffer = Vec::new();
+        to_writer(&mut buffer, &value).unwrap();
+
+        // `{"UUID": [I; 1, 2, 3, 4], "states": [L; 5L], "lists": [[I; 6]]}`
+        let expected = b"\x0a\x00\x00\
+            \x0b\x00\x04UUID\x00\x00\x00\x04\x00\x00\x00\x01\x00\x00\x00\x02\x00\x00\x00\x03\x00\x00\x00\x04\
+            \x0c\x00\x06states\x00\x00\x00\x01\x00\x00\x00\x00\x00\x00\x00\x05\
+            \x09\x00\x05lists\x0b\x00\x00\x00\x01\x00\x00\x00\x01\x00\x00\x00\x06\
+            \x00";
+        assert_eq!(buffer, &expected[..]);
+    }
+
+    #[test]
+    fn test_array_wrong_element_type() {
+        #[derive(Serialize)]
+        struct Test {
+            #[serde(with = "crate::int_array")]
+            i: Vec<i32>,
+        }
+
+        #[derive(Serialize)]
+        struct Wrong {
+            i: WrongArray,
+        }
+
+        // a TAG_I32_ARRAY that is given i64 elements
+        struct WrongArray;
+
+        impl Serialize for WrongArray {
+            fn serialize<S: ser::Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+                serializer.serialize_newtype_struct(array::I32_ARRAY_NAME, &[1i64])
+            }
+        }
+
+        let mut buffer = Vec::new();
+        assert!(to_writer(&mut buffer, &Test { i: vec![1] }).is_ok());
+
+        let mut buffer = Vec::new();
+        assert!(to_writer(&mut buffer, &Wrong { i: WrongArray }).is_err());
+    }
+
+    #[test]
+    fn test_enums() {
+        #[derive(Serialize)]
+        enum Facing {
+            #[serde(rename = "minecraft:north")]
+            North,
+            #[serde(rename = "minecraft:east")]
+            East,
+        }
+
+        #[derive(Serialize)]
+        enum Shape {
+            Circle(f32),
+            Line(i32, i32),
+            Rect { w: i16, h: i16 },
+        }
+
+        #[derive(Serialize)]
+        struct Test {
+            a: Facing,
+            #[serde(with = "crate::byte_enum")]
+            b: Facing,
+            #[serde(with = "crate::int_enum")]
+            c: Facing,
+            shapes: Vec<Shape>,
+        }
+
+        let value = Test {
+            a: Facing::East,
+            b: Facing::East,
+            c: Facing::North,
+            shapes: vec![Shape::Circle(1.0), Shape::Line(2, 3), Shape::Rect { w: 4, h: 5 }],
+        };
+        let mut buffer = Vec::new();
+        to_writer(&mut buffer, &value).unwrap();
+
+        // `{"a": "minecraft:east", "b": 1b, "c": 0,
+        //   "shapes": [{"Circle": 1f}, {"Line": [2, 3]}, {"Rect": {"w": 4s, "h": 5s}}]}`
+        let expected = b"\x0a\x00\x00\
+            \x08\x00\x01a\x00\x0eminecraft:east\
+            \x01\x00\x01b\x01\
+            \x03\x00\x01c\x00\x00\x00\x00\
+            \x09\x00\x06shapes\x0a\x00\x00\x00\x03\
+                \x05\x00\x06Circle\x3f\x80\x00\x00\x00\
+                \x09\x00\x04Line\x03\x00\x00\x00\x02\x00\x00\x00\x02\x00\x00\x00\x03\x00\
+                \x0a\x00\x04Rect\x02\x00\x01w\x00\x04\x02\x00\x01h\x00\x05\x00\x00\
+            \x00";
+        assert_eq!(buffer, &expected[..]);
+    }
+
+    #[test]
+    fn test_enum_index_requires_unit_variant() {
+        #[derive(Serialize)]
+        enum Shape {
+            Circle(f32),
+        }
+
+        #[derive(Serialize)]
+        struct Test {
+            #[serde(with = "crate::byte_enum")]
+            shape: Shape,
+        }
+
+        let mut buffer = Vec::new();
+        assert!(to_writer(&mut buffer, &Test { shape: Shape::Circle(1.0) }).is_err());
+    }
+
+    #[test]
+    fn test_internally_tagged_enum() {
+        #[derive(Serialize)]
+        #[serde(tag = "id")]
+        enum Entity {
+            #[serde(rename = "minecraft:item")]
+            Item {
+                #[serde(rename = "PickupDelay")]
+                pickup_delay: i16,
+                #[serde(rename = "Invulnerable", with = "crate::byte_bool")]
+                invulnerable: bool,
+            },
+        }
+
+        let value = Entity::Item { pickup_delay: 10, invulnerable: true };
+        let mut buffer = Vec::new();
+        to_writer(&mut buffer, &value).unwrap();
+
+        // `{"id": "minecraft:item", "PickupDelay": 10s, "Invulnerable": 1b}`
+        let expected = b"\x0a\x00\x00\
+            \x08\x00\x02id\x00\x0eminecraft:item\
+            \x02\x00\x0bPickupDelay\x00\x0a\
+            \x01\x00\x0cInvulnerable\x01\
+            \x00";
+        assert_eq!(buffer, &expected[..]);
+    }
+
+    #[test]
+    fn test_options_units_and_newtypes() {
+        #[derive(Serialize)]
+        struct Count(i8);
+
+        #[derive(Serialize)]
+        struct Marker;
+
+        #[derive(Serialize)]
+        struct Test {
+            #[serde(rename = "CustomName")]
+            custom_name: Option<String>,
+            count: Option<Count>,
+            marker: Marker,
+            unit: (),
+        }
+
+        let value = Test { custom_name: None, count: Some(Count(2)), marker: Marker, unit: () };
+        let mut buffer = Vec::new();
+        to_writer(&mut buffer, &value).unwrap();
+
+        // `{"count": 2b, "marker": {}, "unit": {}}`
+        let expected = b"\x0a\x00\x00\
+            \x01\x00\x05count\x02\
+            \x0a\x00\x06marker\x00\
+            \x0a\x00\x04unit\x00\
+            \x00";
+        assert_eq!(buffer, &expected[..]);
+    }
+
+    #[test]
+    fn test_none_in_list() {
+        #[derive(Serialize)]
+        struct Test {
+            counts: Vec<Option<i8>>,
+        }
+
+        let mut buffer = Vec::new();
+        assert!(to_writer(&mut buffer, &Test { counts: vec![Some(1)] }).is_ok());
+
+        let mut buffer = Vec::new();
+        assert!(to_writer(&mut buffer, &Test { counts: vec![Some(1), None] }).is_err());
+    }
+
+    #[test]
+    fn test_unsigned_and_char() {
+        #[derive(Serialize)]
+        struct Test {
+            a: u8,
+            b: u16,
+            c: u32,
+            d: u64,
+            e: char,
+            colors: Vec<u32>,
+        }
+
+        let value = Test { a: 255, b: 65535, c: u32::MAX, d: 1, e: 'é', colors: vec![0xff00ff00] };
+        let mut buffer = Vec::new();
+        to_writer(&mut buffer, &value).unwrap();
+
+        // `{"a": -1b, "b": -1s, "c": -1, "d": 1L, "e": "é", "colors": [-16711936]}`
+        let expected = b"\x0a\x00\x00\
+            \x01\x00\x01a\xff\
+            \x02\x00\x01b\xff\xff\
+            \x03\x00\x01c\xff\xff\xff\xff\
+            \x04\x00\x01d\x00\x00\x00\x00\x00\x00\x00\x01\
+            \x08\x00\x01e\x00\x02\xc3\xa9\
+            \x09\x00\x06colors\x03\x00\x00\x00\x01\xff\x00\xff\x00\
+            \x00";
+        assert_eq!(buffer, &expected[..]);
+
+        // the values above `i32::MAX` do not fit when range checked
+        let config = SerializerConfig::new().unsigned_mode(UnsignedMode::RangeChecked);
+        assert!(config.to_writer(Vec::new(), &value).is_err());
+    }
+
+    #[test]
+    fn test_config() {
+        #[derive(Serialize)]
+        struct Test {
+            s: &'static str,
+            v: IntArray,
+        }
+
+        let value = Test { s: "a\0", v: IntArray(vec![1]) };
+        let config = SerializerConfig::new()
+            .byte_order(ByteOrder::LittleEndian)
+            .string_encoding(StringEncoding::ModifiedUtf8)
+            .root_mode(RootMode::UnnamedCompound);
+        let mut buffer = Vec::new();
+        config.to_writer(&mut buffer, &value).unwrap();
+
+        // `{"s": "a\0", "v": [I; 1]}`, little endian in modified UTF-8 without
+        // the root tag's name
+        let expected = b"\x0a\
+            \x08\x01\x00s\x03\x00a\xc0\x80\
+            \x0b\x01\x00v\x01\x00\x00\x00\x01\x00\x00\x00\
+            \x00";
+        assert_eq!(buffer, &expected[..]);
+    }
+
+    #[test]
+    fn test_sort_keys() {
+        let mut value = std::collections::HashMap::new();
+        for (i, name) in ["d", "a", "c", "b", "e"].iter().enumerate() {
+            value.insert(*name, i as i8);
+        }
+
+        let config = SerializerConfig::new().sort_keys(true);
+        let mut buffer = Vec::new();
+        config.to_writer(&mut buffer, &value).unwrap();
+
+        // `{"a": 1b, "b": 3b, "c": 2b, "d": 0b, "e": 4b}`
+        let expected = b"\x0a\x00\x00\
+            \x01\x00\x01a\x01\
+            \x01\x00\x01b\x03\
+            \x01\x00\x01c\x02\
+            \x01\x00\x01d\x00\
+            \x01\x00\x01e\x04\
+            \x00";
+        assert_eq!(buffer, &expected[..]);
+    }
+
+    // serialized as a sequence of unknown length, like filtered iterators
+    struct Unsized<T>(Vec<T>);
+
+    impl<T: Serialize> Serialize for Unsized<T> {
+        fn serialize<S: ser::Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+            serializer.collect_seq(self.0.iter().filter(|_| true))
+        }
+    }
+
+    #[test]
+    fn test_unknown_length() {
+        #[derive(Serialize)]
+        struct Test {
+            a: Unsized<i32>,
+            b: Unsized<i8>,
+            c: Unsized<Unsized<i16>>,
+            d: IntArrayUnsized,
+        }
+
+        struct IntArrayUnsized;
+
+        impl Serialize for IntArrayUnsized {
+            fn serialize<S: ser::Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+                serializer.serialize_newtype_struct(array::I32_ARRAY_NAME, &Unsized(vec![5]))
+            }
+        }
+
+        let value = Test {
+            a: Unsized(vec![1, 2]),
+            b: Unsized(vec![]),
+            c: Unsized(vec![Unsized(vec![3]), Unsized(vec![4])]),
+            d: IntArrayUnsized,
+        };
+        let mut buffer = Vec::new();
+        to_writer(&mut buffer, &value).unwrap();
+
+        // `{"a": [1, 2], "b": [], "c": [[3s], [4s]], "d": [I; 5]}`
+        let expected = b"\x0a\x00\x00\
+            \x09\x00\x01a\x03\x00\x00\x00\x02\x00\x00\x00\x01\x00\x00\x00\x02\
+            \x09\x00\x01b\x00\x00\x00\x00\x00\
+            \x09\x00\x01c\x09\x00\x00\x00\x02\
+                \x02\x00\x00\x00\x01\x00\x03\
+                \x02\x00\x00\x00\x01\x00\x04\
+            \x0b\x00\x01d\x00\x00\x00\x01\x00\x00\x00\x05\
+            \x00";
+        assert_eq!(buffer, &expected[..]);
+    }
+
+    #[test]
+    fn test_unknown_length_limit() {
+        let mut value = std::collections::HashMap::new();
+        value.insert("a", Unsized(vec![1, 2, 3]));
+
+        let config = SerializerConfig::new().buffer_limit(12);
+        assert!(config.to_writer(Vec::new(), &value).is_ok());
+        let config = SerializerConfig::new().buffer_limit(11);
+        assert!(matches!(config.to_writer(Vec::new(), &value), Err(Error::BufferLimitError)));
+    }
+
+    #[test]
+    fn test_mixed_element_types() {
+        use crate::Value;
+
+        let mut value = std::collections::HashMap::new();
+        value.insert("a", Value::List(vec![Value::Int(1), Value::Long(2)]));
+        assert!(matches!(
+            to_writer(Vec::new(), &value),
+            Err(Error::ListElementTypeError { list: nbt::TAG_I32, element: nbt::TAG_I64 })
+        ));
+
+        let mut value = std::collections::HashMap::new();
+        value.insert("a", Unsized(vec![Value::String("b".to_string()), Value::Byte(1)]));
+        let error = to_writer(Vec::new(), &value).unwrap_err();
+        assert_eq!(error.to_string(), "list of TAG_String can not contain a TAG_Byte");
+    }
+
+    #[test]
+    fn test_root_mode() {
+        // only compounds are accepted at the root by default
+        assert!(matches!(to_writer(Vec::new(), &7), Err(Error::InvalidTagTypeError)));
+
+        let config = SerializerConfig::new().root_mode(RootMode::UnnamedAny);
+        let mut buffer = Vec::new();
+        config.to_writer(&mut buffer, &7).unwrap();
+        assert_eq!(buffer, b"\x03\x00\x00\x00\x07");
+    }
+
+    #[test]
+    fn test_limits() {
+        #[derive(Serialize)]
+        struct Test {
+            s: &'static str,
+            inner: Vec<Vec<i8>>,
+        }
+
+        let value = Test { s: "hello", inner: vec![vec![]] };
+
+        let config = SerializerConfig::new().max_depth(3);
+        assert!(config.to_writer(Vec::new(), &value).is_ok());
+        let config = SerializerConfig::new().max_depth(2);
+        assert!(matches!(config.to_writer(Vec::new(), &value), Err(Error::DepthLimitError)));
+
+        // names and strings take 11 bytes, the outer list's element 5
+        let config = SerializerConfig::new().size_quota(16);
+        assert!(config.to_writer(Vec::new(), &value).is_ok());
+        let config = SerializerConfig::new().size_quota(15);
+        assert!(matches!(config.to_writer(Vec::new(), &value), Err(Error::SizeLimitError)));
+    }
+
+    #[test]
+    fn test_root_name() {
+        let value: std::collections::HashMap<&str, i8> = vec![("a", 1)].into_iter().collect();
+
+        let mut buffer = Vec::new();
+        to_writer_named(&mut buffer, "Level", &value).unwrap();
+        assert_eq!(buffer, b"\x0a\x00\x05Level\x01\x00\x01a\x01\x00");
+
+        // unnamed root tags can only be given an empty name
+        let config = SerializerConfig::new().root_mode(RootMode::UnnamedCompound);
+        assert!(config.to_writer_named(Vec::new(), "Level", &value).is_err());
+        assert!(config.to_writer_named(Vec::new(), "", &value).is_ok());
+    }
+
+    #[test]
+    fn test_empty_list_types() {
+        #[derive(Serialize)]
+        struct Test {
+            #[serde(with = "crate::int_list")]
+            a: Vec<i32>,
+            #[serde(with = "crate::compound_list")]
+            b: Vec<std::collections::HashMap<String, i8>>,
+            #[serde(with = "crate::string_list")]
+            c: Vec<&'static str>,
+            d: Vec<i8>,
+        }
+
+        let value = Test { a: vec![], b: vec![], c: vec!["x"], d: vec![] };
+
+        // `{"a": [], "b": [], "c": ["x"], "d": []}` with `TAG_END` for empty lists
+        let mut buffer = Vec::new();
+        to_writer(&mut buffer, &value).unwrap();
+        let expected = b"\x0a\x00\x00\
+            \x09\x00\x01a\x00\x00\x00\x00\x00\
+            \x09\x00\x01b\x00\x00\x00\x00\x00\
+            \x09\x00\x01c\x08\x00\x00\x00\x01\x00\x01x\
+            \x09\x00\x01d\x00\x00\x00\x00\x00\
+            \x00";
+        assert_eq!(buffer, &expected[..]);
+
+        // the same with the element types of the list modules
+        let mut buffer = Vec::new();
+        SerializerConfig::new().typed_empty_lists(true).to_writer(&mut buffer, &value).unwrap();
+        let expected = b"\x0a\x00\x00\
+            \x09\x00\x01a\x03\x00\x00\x00\x00\
+            \x09\x00\x01b\x0a\x00\x00\x00\x00\
+            \x09\x00\x01c\x08\x00\x00\x00\x01\x00\x01x\
+            \x09\x00\x01d\x00\x00\x00\x00\x00\
+            \x00";
+        assert_eq!(buffer, &expected[..]);
+    }
+
+    #[test]
+    fn test_list_module_on_other_types() {
+        #[derive(Serialize)]
+        struct Test {
+            #[serde(with = "crate::int_list")]
+            a: i32,
+        }
+
+        let value = Test { a: 1 };
+        assert!(matches!(to_writer(Vec::new(), &value), Err(Error::InvalidTagTypeError)));
+    }
+
+    #[test]
+    fn test_wrong_sequence_length() {
+        // a sequence which claims two elements
+        struct Test(Vec<i32>);
+
+        impl Serialize for Test {
+            fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+            where
+                S: serde::Serializer,
+            {
+                use serde::ser::SerializeSeq;
+                let mut seq = serializer.serialize_seq(Some(2))?;
+                for value in &self.0 {
+                    seq.serialize_element(value)?;
+                }
+                seq.end()
+            }
+        }
+
+        let mut value = std::collections::HashMap::new();
+        value.insert("a", Test(vec![1]));
+        assert!(matches!(to_writer(Vec::new(), &value), Err(Error::ListLengthError { expected: 2, actual: 1 })));
+        value.insert("a", Test(vec![]));
+        assert!(matches!(to_writer(Vec::new(), &value), Err(Error::ListLengthError { expected: 2, actual: 0 })));
+    }
+
+    #[test]
+    fn test_value_without_key() {
+        struct Test;
+
+        impl Serialize for Test {
+            fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+            where
+                S: serde::Serializer,
+            {
+                use serde::ser::SerializeMap;
+                let mut map = serializer.serialize_map(None)?;
+                map.serialize_value(&1)?;
+                map.end()
+            }
+        }
+
+        assert!(matches!(to_writer(Vec::new(), &Test), Err(Error::InvalidEmitterStateError)));
     }
 }
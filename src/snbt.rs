@@ -0,0 +1,911 @@
+// Stringified NBT, the text format used by commands
+//
+// The legacy dialect is what vanilla reads and writes before 1.21.5. The
+// modern dialect of 1.21.5 adds `true` and `false`, hexadecimal and binary
+// integers, `_` between digits, signedness suffixes such as `ub` and `sl`,
+// more escape sequences in quoted strings, lists with elements of different
+// types and the operations `bool(...)` and `uuid(...)`. Unquoted strings may
+// then no longer start with a digit, `+`, `-` or `.`.
+
+use std::fmt::Write;
+use crate::config::{SnbtConfig, SnbtDialect};
+use crate::error::{Error, Result};
+use crate::nbt;
+use crate::value::Value;
+
+/// reads a value from legacy SNBT, use `SnbtConfig` for the modern dialect
+pub fn from_snbt(input: &str) -> Result<Value> {
+    SnbtConfig::new().from_str(input)
+}
+
+/// writes a value as legacy SNBT on a single line, NaN and infinite floats
+/// fail since SNBT can not express them
+pub fn to_snbt(value: &Value) -> Result<String> {
+    SnbtConfig::new().to_string(value)
+}
+
+/// integer suffixes of the modern dialect, a signedness followed by a type
+const INTEGER_SUFFIXES: [&str; 12] = ["ub", "sb", "us", "ss", "ui", "si", "ul", "sl", "b", "s", "i", "l"];
+
+fn is_unquoted_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.' | '+')
+}
+
+pub(crate) struct SnbtParser<'a> {
+    input: &'a str,
+    pos: usize,
+    depth: usize,
+    config: SnbtConfig,
+}
+
+impl<'a> SnbtParser<'a> {
+    pub(crate) fn new(input: &'a str, config: SnbtConfig) -> Self {
+        SnbtParser { input, pos: 0, depth: 0, config }
+    }
+
+    /// reads a single value, which may only be followed by whitespace
+    pub(crate) fn parse(mut self) -> Result<Value> {
+        let value = self.parse_value()?;
+        self.skip_whitespace();
+        if self.pos < self.input.len() {
+            return self.error("trailing characters after the value");
+        }
+        Ok(value)
+    }
+
+    fn is_modern(&self) -> bool {
+        self.config.dialect == SnbtDialect::Modern
+    }
+
+    fn error<T>(&self, message: impl Into<String>) -> Result<T> {
+        self.error_at(self.pos, message)
+    }
+
+    fn error_at<T>(&self, offset: usize, message: impl Into<String>) -> Result<T> {
+        Err(Error::SyntaxError { offset, message: message.into() })
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.input[self.pos..].chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        Some(c)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(c) = self.peek() {
+            if !c.is_whitespace() {
+                break;
+            }
+            self.pos += c.len_utf8();
+        }
+    }
+
+    /// consumes `c` if it is the next character after any whitespace
+    fn eat(&mut self, c: char) -> bool {
+        self.skip_whitespace();
+        if self.peek() == Some(c) {
+            self.pos += c.len_utf8();
+            return true;
+        }
+        false
+    }
+
+    fn expect(&mut self, c: char) -> Result<()> {
+        if !self.eat(c) {
+            return self.error(format!("expected '{}'", c));
+        }
+        Ok(())
+    }
+
+    fn read_unquoted(&mut self) -> &'a str {
+        let start = self.pos;
+        while let Some(c) = self.peek() {
+            if !is_unquoted_char(c) {
+                break;
+            }
+            self.pos += 1;
+        }
+        &self.input[start..self.pos]
+    }
+
+    fn enter(&mut self) -> Result<()> {
+        if self.depth >= self.config.max_depth {
+            return Err(Error::DepthLimitError);
+        }
+        self.depth += 1;
+        Ok(())
+    }
+
+    fn parse_value(&mut self) -> Result<Value> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some('{') => self.parse_compound(),
+            Some('[') => self.parse_list(),
+            Some(quote @ ('"' | '\'')) => {
+                self.pos += 1;
+                self.parse_quoted(quote).map(Value::String)
+            },
+            Some(_) => self.parse_unquoted_value(),
+            None => self.error("expected a value"),
+        }
+    }
+
+    fn parse_compound(&mut self) -> Result<Value> {
+        self.enter()?;
+        self.pos += 1;
+
+        let mut compound = crate::value::Compound::new();
+        while !self.eat('}') {
+            let name = self.parse_name()?;
+            self.expect(':')?;
+            let value = self.parse_value()?;
+            compound.insert(name, value);
+
+            // a trailing comma is allowed like vanilla does
+            if !self.eat(',') {
+                self.expect('}')?;
+                break;
+            }
+        }
+
+        self.depth -= 1;
+        Ok(Value::Compound(compound))
+    }
+
+    fn parse_name(&mut self) -> Result<String> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some(quote @ ('"' | '\'')) => {
+                self.pos += 1;
+                self.parse_quoted(quote)
+            },
+            _ => match self.read_unquoted() {
+                "" => self.error("expected a name"),
+                name => Ok(name.to_string()),
+            },
+        }
+    }
+
+    fn parse_list(&mut self) -> Result<Value> {
+        self.enter()?;
+        self.pos += 1;
+
+        // arrays begin with their type right after the bracket
+        let array_type = match &self.input.as_bytes()[self.pos..] {
+            [b'B', b';', ..] => Some(nbt::TAG_I8_ARRAY),
+            [b'I', b';', ..] => Some(nbt::TAG_I32_ARRAY),
+            [b'L', b';', ..] => Some(nbt::TAG_I64_ARRAY),
+            _ => None,
+        };
+        let value = match array_type {
+            Some(array_type) => {
+                self.pos += 2;
+                self.parse_array(array_type)?
+            },
+            None => {
+                let mut values: Vec<Value> = Vec::new();
+                while !self.eat(']') {
+                    self.skip_whitespace();
+                    let start = self.pos;
+                    let value = self.parse_value()?;
+                    if let Some(first) = values.first() {
+                        if !self.is_modern() && first.tag_type() != value.tag_type() {
+                            return self.error_at(start, format!(
+                                "list of {} can not contain a {}",
+                                nbt::tag_type_name(first.tag_type()),
+                                nbt::tag_type_name(value.tag_type())
+                            ));
+                        }
+                    }
+                    values.push(value);
+
+                    if !self.eat(',') {
+                        self.expect(']')?;
+                        break;
+                    }
+                }
+                Value::List(values)
+            },
+        };
+
+        self.depth -= 1;
+        Ok(value)
+    }
+
+    fn parse_array(&mut self, array_type: u8) -> Result<Value> {
+        let mut array = match array_type {
+            nbt::TAG_I8_ARRAY => Value::ByteArray(Vec::new()),
+            nbt::TAG_I32_ARRAY => Value::IntArray(Vec::new()),
+            _ => Value::LongArray(Vec::new()),
+        };
+
+        while !self.eat(']') {
+            self.skip_whitespace();
+            let start = self.pos;
+            match (&mut array, self.parse_value()?) {
+                (Value::ByteArray(values), Value::Byte(value)) => values.push(value),
+                (Value::IntArray(values), Value::Int(value)) => values.push(value),
+                (Value::LongArray(values), Value::Long(value)) => values.push(value),
+                (_, value) => {
+                    return self.error_at(start, format!(
+                        "{} can not contain a {}",
+                        nbt::tag_type_name(array_type),
+                        nbt::tag_type_name(value.tag_type())
+                    ))
+                },
+            }
+
+            if !self.eat(',') {
+                self.expect(']')?;
+                break;
+            }
+        }
+        Ok(array)
+    }
+
+    /// reads the rest of a quoted string whose opening quote has been read
+    fn parse_quoted(&mut self, quote: char) -> Result<String> {
+        let mut value = String::new();
+        loop {
+            match self.bump() {
+                None => return self.error("unterminated string"),
+                Some(c) if c == quote => return Ok(value),
+                Some('\\') => value.push(self.parse_escape(quote)?),
+                Some(c) => value.push(c),
+            }
+        }
+    }
+
+    fn parse_escape(&mut self, quote: char) -> Result<char> {
+        let start = self.pos - 1;
+        let c = match self.bump() {
+            Some(c) => c,
+            None => return self.error("unterminated string"),
+        };
+        match c {
+            '\\' => Ok('\\'),
+            _ if c == quote => Ok(c),
+            _ if !self.is_modern() => self.error_at(start, format!("invalid escape sequence '\\{}'", c)),
+            '"' | '\'' => Ok(c),
+            'b' => Ok('\u{8}'),
+            's' => Ok(' '),
+            't' => Ok('\t'),
+            'n' => Ok('\n'),
+            'f' => Ok('\u{c}'),
+            'r' => Ok('\r'),
+            'x' => self.parse_hex_escape(start, 2),
+            'u' => self.parse_hex_escape(start, 4),
+            'U' => self.parse_hex_escape(start, 8),
+            'N' => self.parse_named_escape(start),
+            _ => self.error_at(start, format!("invalid escape sequence '\\{}'", c)),
+        }
+    }
+
+    /// reads the rest of a `\N{name}` escape, naming a Unicode character
+    fn parse_named_escape(&mut self, start: usize) -> Result<char> {
+        let name = self.input[self.pos..]
+            .strip_prefix('{')
+            .and_then(|rest| rest.split_once('}'))
+            .map(|(name, _)| name)
+            .unwrap_or("");
+        match unicode_names2::character(name) {
+            Some(c) => {
+                self.pos += name.len() + 2;
+                Ok(c)
+            },
+            None => self.error_at(start, "invalid character escape"),
+        }
+    }
+
+    fn parse_hex_escape(&mut self, start: usize, len: usize) -> Result<char> {
+        let digits = self.input.get(self.pos..self.pos + len).unwrap_or("");
+        let c = match digits.bytes().all(|b| b.is_ascii_hexdigit()) && digits.len() == len {
+            true => u32::from_str_radix(digits, 16).ok().and_then(char::from_u32),
+            false => None,
+        };
+        match c {
+            Some(c) => {
+                self.pos += len;
+                Ok(c)
+            },
+            None => self.error_at(start, "invalid character escape"),
+        }
+    }
+
+    fn parse_unquoted_value(&mut self) -> Result<Value> {
+        let start = self.pos;
+        let token = self.read_unquoted();
+        if token.is_empty() {
+            return self.error(format!("unexpected character '{}'", self.peek().unwrap_or_default()));
+        }
+
+        if !self.is_modern() {
+            return Ok(legacy_number(token).unwrap_or_else(|| legacy_literal(token)));
+        }
+
+        if token.starts_with(|c: char| c.is_ascii_digit() || matches!(c, '+' | '-' | '.')) {
+            return match modern_number(token) {
+                Ok(value) => Ok(value),
+                Err(message) => self.error_at(start, message),
+            };
+        }
+        if self.peek() == Some('(') {
+            return self.parse_operation(token, start);
+        }
+        Ok(legacy_literal(token))
+    }
+
+    fn parse_operation(&mut self, name: &str, start: usize) -> Result<Value> {
+        self.pos += 1;
+        let value = match name {
+            "bool" => {
+                self.enter()?;
+                let value = self.parse_value()?;
+                self.depth -= 1;
+                let value = match value {
+                    Value::Byte(v) => v != 0,
+                    Value::Short(v) => v != 0,
+                    Value::Int(v) => v != 0,
+                    Value::Long(v) => v != 0,
+                    Value::Float(v) => v != 0.0,
+                    Value::Double(v) => v != 0.0,
+                    _ => return self.error_at(start, "bool() expects a number"),
+                };
+                Value::Byte(value as i8)
+            },
+            "uuid" => {
+                self.skip_whitespace();
+                let uuid = match self.peek() {
+                    Some(quote @ ('"' | '\'')) => {
+                        self.pos += 1;
+                        self.parse_quoted(quote)?
+                    },
+                    _ => self.read_unquoted().to_string(),
+                };
+                match parse_uuid(&uuid) {
+                    Some(value) => Value::IntArray(value),
+                    None => return self.error_at(start, format!("invalid UUID '{}'", uuid)),
+                }
+            },
+            _ => return self.error_at(start, format!("unknown operation '{}'", name)),
+        };
+        self.expect(')')?;
+        Ok(value)
+    }
+}
+
+/// `true` and `false` are bytes, anything else is a string
+fn legacy_literal(token: &str) -> Value {
+    if token.eq_ignore_ascii_case("true") {
+        Value::Byte(1)
+    } else if token.eq_ignore_ascii_case("false") {
+        Value::Byte(0)
+    } else {
+        Value::String(token.to_string())
+    }
+}
+
+/// reads a number the way vanilla did before 1.21.5, anything which does not
+/// look like a number or is out of range is a string instead
+fn legacy_number(token: &str) -> Option<Value> {
+    let (body, suffix) = match token.char_indices().last() {
+        Some((i, c)) if c.is_ascii_alphabetic() => (&token[..i], Some(c.to_ascii_lowercase())),
+        _ => (token, None),
+    };
+    match suffix {
+        Some('b') if is_legacy_integer(body) => body.parse().ok().map(Value::Byte),
+        Some('s') if is_legacy_integer(body) => body.parse().ok().map(Value::Short),
+        Some('l') if is_legacy_integer(body) => body.parse().ok().map(Value::Long),
+        None if is_legacy_integer(body) => body.parse().ok().map(Value::Int),
+        Some('f') if is_legacy_float(body, true) => body.parse().ok().map(Value::Float),
+        Some('d') if is_legacy_float(body, true) => body.parse().ok().map(Value::Double),
+        None if is_legacy_float(body, false) => body.parse().ok().map(Value::Double),
+        _ => None,
+    }
+}
+
+fn strip_sign(value: &str) -> &str {
+    value.strip_prefix(['+', '-']).unwrap_or(value)
+}
+
+fn is_digits(value: &str) -> bool {
+    value.bytes().all(|b| b.is_ascii_digit())
+}
+
+fn is_legacy_integer(body: &str) -> bool {
+    let digits = strip_sign(body);
+    digits == "0" || (is_digits(digits) && digits.starts_with(|c| ('1'..='9').contains(&c)))
+}
+
+/// whether `body` has a decimal point, or any digits if a suffix follows it
+fn is_legacy_float(body: &str, suffixed: bool) -> bool {
+    let body = strip_sign(body);
+    let (mantissa, exponent) = match body.find(['e', 'E']) {
+        Some(i) => (&body[..i], Some(strip_sign(&body[i + 1..]))),
+        None => (body, None),
+    };
+    if exponent.is_some_and(|exponent| exponent.is_empty() || !is_digits(exponent)) {
+        return false;
+    }
+    match mantissa.split_once('.') {
+        Some((int, frac)) => is_digits(int) && is_digits(frac) && !(int.is_empty() && frac.is_empty()),
+        None => suffixed && !mantissa.is_empty() && is_digits(mantissa),
+    }
+}
+
+/// removes `_` between digits, which may not start or end a number
+fn strip_underscores(digits: &str, radix: u32) -> Option<String> {
+    if digits.starts_with('_') || digits.ends_with('_') {
+        return None;
+    }
+    match digits.chars().all(|c| c == '_' || c.is_digit(radix)) {
+        true => Some(digits.replace('_', "")),
+        false => None,
+    }
+}
+
+/// reads a number of the modern dialect, which starts with a digit, a sign
+/// or a decimal point
+fn modern_number(token: &str) -> std::result::Result<Value, String> {
+    let invalid = || format!("invalid number '{}'", token);
+    let negative = token.starts_with('-');
+    let lower = strip_sign(token).to_ascii_lowercase();
+
+    let prefixed = |prefix: &str, radix: u32| {
+        lower.strip_prefix(prefix).filter(|rest| rest.starts_with(|c: char| c == '_' || c.is_digit(radix)))
+    };
+    if let Some(rest) = prefixed("0x", 16) {
+        return modern_integer(token, negative, rest, 16);
+    }
+    if let Some(rest) = prefixed("0b", 2) {
+        return modern_integer(token, negative, rest, 2);
+    }
+
+    // decimal points, exponents and the suffixes `f` and `d` make a float
+    if !lower.contains(['.', 'e']) && !lower.ends_with(['f', 'd']) {
+        return modern_integer(token, negative, &lower, 10);
+    }
+
+    let (body, is_float) = match lower.strip_suffix('f') {
+        Some(body) => (body, true),
+        None => (lower.strip_suffix('d').unwrap_or(&lower), false),
+    };
+    let (mantissa, exponent) = match body.split_once('e') {
+        Some((mantissa, exponent)) => {
+            let exponent_sign = if exponent.starts_with('-') { "-" } else { "" };
+            let exponent = strip_underscores(strip_sign(exponent), 10).filter(|e| !e.is_empty()).ok_or_else(invalid)?;
+            (mantissa, format!("e{}{}", exponent_sign, exponent))
+        },
+        None => (body, String::new()),
+    };
+    let (int, frac) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    let int = strip_underscores(int, 10).ok_or_else(invalid)?;
+    let frac = strip_underscores(frac, 10).ok_or_else(invalid)?;
+    if int.is_empty() && frac.is_empty() {
+        return Err(invalid());
+    }
+
+    let number = format!("{}{}.{}{}", if negative { "-" } else { "" }, int, frac, exponent);
+    let value = match is_float {
+        true => number.parse().ok().filter(|v: &f32| v.is_finite()).map(Value::Float),
+        false => number.parse().ok().filter(|v: &f64| v.is_finite()).map(Value::Double),
+    };
+    value.ok_or_else(|| format!("number '{}' is out of range", token))
+}
+
+fn modern_integer(token: &str, negative: bool, lower: &str, radix: u32) -> std::result::Result<Value, String> {
+    // `b` is a hexadecimal digit, so hexadecimal bytes need a signedness
+    let suffix = INTEGER_SUFFIXES
+        .iter()
+        .find(|suffix| !(radix == 16 && **suffix == "b") && lower.ends_with(*suffix))
+        .copied()
+        .unwrap_or("");
+    let digits = &lower[..lower.len() - suffix.len()];
+    let digits = strip_underscores(digits, radix)
+        .filter(|digits| !digits.is_empty())
+        .ok_or_else(|| format!("invalid number '{}'", token))?;
+    let out_of_range = || format!("number '{}' is out of range", token);
+    let magnitude = u64::from_str_radix(&digits, radix).map_err(|_| out_of_range())?;
+
+    let bits = match suffix.chars().last() {
+        Some('b') => 8,
+        Some('s') => 16,
+        Some('l') => 64,
+        _ => 32,
+    };
+    let value = if suffix.len() == 2 && suffix.starts_with('u') {
+        if negative {
+            return Err(format!("unsigned number '{}' can not be negative", token));
+        }
+        if bits < 64 && magnitude >> bits != 0 {
+            return Err(out_of_range());
+        }
+        // unsigned numbers keep their bits
+        magnitude as i64
+    } else {
+        let value = if negative { -(magnitude as i128) } else { magnitude as i128 };
+        if value < -(1 << (bits - 1)) || value >= 1 << (bits - 1) {
+            return Err(out_of_range());
+        }
+        value as i64
+    };
+
+    Ok(match bits {
+        8 => Value::Byte(value as i8),
+        16 => Value::Short(value as i16),
+        32 => Value::Int(value as i32),
+        _ => Value::Long(value),
+    })
+}
+
+/// reads a UUID as the four `TAG_I32` vanilla stores it as, most significant
+/// bits first
+fn parse_uuid(value: &str) -> Option<Vec<i32>> {
+    let groups: Vec<&str> = value.split('-').collect();
+    let valid = groups.len() == 5
+        && groups
+            .iter()
+            .zip([8, 4, 4, 4, 12])
+            .all(|(group, len)| group.len() == len && group.bytes().all(|b| b.is_ascii_hexdigit()));
+    if !valid {
+        return None;
+    }
+    let bits = u128::from_str_radix(&groups.concat(), 16).ok()?;
+    Some((0..4).rev().map(|i| (bits >> (i * 32)) as u32 as i32).collect())
+}
+
+/// SNBT has no way to write NaN or infinities
+fn non_finite(value: f64) -> Error {
+    Error::Message(format!("{} can not be written as SNBT", value))
+}
+
+pub(crate) struct SnbtPrinter {
+    out: String,
+    level: usize,
+    config: SnbtConfig,
+}
+
+impl SnbtPrinter {
+    pub(crate) fn new(config: SnbtConfig) -> Self {
+        SnbtPrinter { out: String::new(), level: 0, config }
+    }
+
+    pub(crate) fn print(mut self, value: &Value) -> Result<String> {
+        self.write_value(value)?;
+        Ok(self.out)
+    }
+
+    fn is_modern(&self) -> bool {
+        self.config.dialect == SnbtDialect::Modern
+    }
+
+    fn write_value(&mut self, value: &Value) -> Result<()> {
+        // writing to a `String` can not fail
+        let _ = match value {
+            Value::Byte(v) => write!(self.out, "{}b", v),
+            Value::Short(v) => write!(self.out, "{}s", v),
+            Value::Int(v) => write!(self.out, "{}", v),
+            Value::Long(v) => write!(self.out, "{}L", v),
+            Value::Float(v) if !v.is_finite() => return Err(non_finite(*v as f64)),
+            Value::Float(v) => write!(self.out, "{:?}f", v),
+            Value::Double(v) if !v.is_finite() => return Err(non_finite(*v)),
+            Value::Double(v) => write!(self.out, "{:?}d", v),
+            Value::ByteArray(v) => {
+                self.write_array('B', v.iter().map(|v| format!("{}B", v)));
+                Ok(())
+            },
+            Value::String(v) => {
+                self.write_string(v);
+                Ok(())
+            },
+            Value::List(v) => {
+                // older versions read lists with elements of different types
+                // the way vanilla writes them since 1.21.5, as compounds
+                // holding each element as a tag named ""
+                let wrapped = !self.is_modern() && v.windows(2).any(|w| w[0].tag_type() != w[1].tag_type());
+                self.write_nested('[', ']', v, |printer, value| match wrapped {
+                    true => {
+                        printer.out.push('{');
+                        printer.write_entry("", value)?;
+                        printer.out.push('}');
+                        Ok(())
+                    },
+                    false => printer.write_value(value),
+                })?;
+                Ok(())
+            },
+            Value::Compound(v) => {
+                let mut entries: Vec<_> = v.iter().collect();
+                if self.config.sort_keys {
+                    entries.sort_by(|a, b| a.0.cmp(b.0));
+                }
+                self.write_nested('{', '}', &entries, |printer, (name, value)| printer.write_entry(name, value))?;
+                Ok(())
+            },
+            Value::IntArray(v) => {
+                self.write_array('I', v.iter().map(|v| v.to_string()));
+                Ok(())
+            },
+            Value::LongArray(v) => {
+                self.write_array('L', v.iter().map(|v| format!("{}L", v)));
+                Ok(())
+            },
+        };
+        Ok(())
+    }
+
+    fn write_entry(&mut self, name: &str, value: &Value) -> Result<()> {
+        if !name.is_empty() && name.chars().all(is_unquoted_char) {
+            self.out.push_str(name);
+        } else {
+            self.write_string(name);
+        }
+        self.out.push_str(if self.config.pretty { ": " } else { ":" });
+        self.write_value(value)
+    }
+
+    /// writes the elements of a list or compound, each on its own line when
+    /// pretty printing
+    fn write_nested<T>(
+        &mut self,
+        open: char,
+        close: char,
+        items: &[T],
+        mut write: impl FnMut(&mut Self, &T) -> Result<()>,
+    ) -> Result<()> {
+        self.out.push(open);
+        if items.is_empty() {
+            self.out.push(close);
+            return Ok(());
+        }
+
+        self.level += 1;
+        for (i, item) in items.iter().enumerate() {
+            if i > 0 {
+                self.out.push(',');
+            }
+            self.write_newline();
+            write(self, item)?;
+        }
+        self.level -= 1;
+        self.write_newline();
+        self.out.push(close);
+        Ok(())
+    }
+
+    fn write_newline(&mut self) {
+        if self.config.pretty {
+            self.out.push('\n');
+            for _ in 0..self.level {
+                self.out.push_str("    ");
+            }
+        }
+    }
+
+    /// arrays are written on a single line even when pretty printing
+    fn write_array(&mut self, array_type: char, values: impl Iterator<Item = String>) {
+        let separator = if self.config.pretty { ", " } else { "," };
+        let _ = write!(self.out, "[{};", array_type);
+        for (i, value) in values.enumerate() {
+            self.out.push_str(if i > 0 { separator } else if self.config.pretty { " " } else { "" });
+            self.out.push_str(&value);
+        }
+        self.out.push(']');
+    }
+
+    fn write_string(&mut self, value: &str) {
+        let quote = if value.contains('"') && !value.contains('\'') { '\'' } else { '"' };
+        self.out.push(quote);
+        for c in value.chars() {
+            match c {
+                '\\' => self.out.push_str("\\\\"),
+                _ if c == quote => {
+                    self.out.push('\\');
+                    self.out.push(c);
+                },
+                // the legacy dialect has no escapes for control characters
+                _ if !self.is_modern() => self.out.push(c),
+                '\t' => self.out.push_str("\\t"),
+                '\n' => self.out.push_str("\\n"),
+                '\r' => self.out.push_str("\\r"),
+                '\u{8}' => self.out.push_str("\\b"),
+                '\u{c}' => self.out.push_str("\\f"),
+                _ if c.is_control() && (c as u32) < 0x100 => {
+                    let _ = write!(self.out, "\\x{:02x}", c as u32);
+                },
+                _ => self.out.push(c),
+            }
+        }
+        self.out.push(quote);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::value::Compound;
+    use super::*;
+
+    fn modern() -> SnbtConfig {
+        SnbtConfig::new().dialect(SnbtDialect::Modern)
+    }
+
+    fn compound(entries: Vec<(&str, Value)>) -> Value {
+        Value::Compound(entries.into_iter().map(|(name, value)| (name.to_string(), value)).collect::<Compound>())
+    }
+
+    #[test]
+    fn test_legacy_values() {
+        let value = from_snbt(
+            "{a: 1b, b: 2S, c: 3, d: 4l, e: 5.0f, f: 6.5, g: [B; 7b], 'h i': \"j\\\"\", k: [[I; 8, 9]], \
+             l: {}, m: [L; 10L], n: [], o: true, p: abc, q: 1.5e3D, r: -.5f,}",
+        )
+        .unwrap();
+        let expected = compound(vec![
+            ("a", Value::Byte(1)),
+            ("b", Value::Short(2)),
+            ("c", Value::Int(3)),
+            ("d", Value::Long(4)),
+            ("e", Value::Float(5.0)),
+            ("f", Value::Double(6.5)),
+            ("g", Value::ByteArray(vec![7])),
+            ("h i", Value::String("j\"".to_string())),
+            ("k", Value::List(vec![Value::IntArray(vec![8, 9])])),
+            ("l", compound(vec![])),
+            ("m", Value::LongArray(vec![10])),
+            ("n", Value::List(vec![])),
+            ("o", Value::Byte(1)),
+            ("p", Value::String("abc".to_string())),
+            ("q", Value::Double(1500.0)),
+            ("r", Value::Float(-0.5)),
+        ]);
+        assert_eq!(value, expected);
+    }
+
+    #[test]
+    fn test_legacy_strings_that_look_like_numbers() {
+        // out of range, leading zeros and modern syntax are read as strings
+        assert_eq!(from_snbt("300b").unwrap(), Value::String("300b".to_string()));
+        assert_eq!(from_snbt("012").unwrap(), Value::String("012".to_string()));
+        assert_eq!(from_snbt("0xff").unwrap(), Value::String("0xff".to_string()));
+        assert_eq!(from_snbt("1_000").unwrap(), Value::String("1_000".to_string()));
+        assert!(from_snbt("1ub").is_ok());
+        assert!(from_snbt("bool(1)").is_err());
+    }
+
+    #[test]
+    fn test_legacy_errors() {
+        assert!(from_snbt("[1, 2b]").is_err());
+        assert!(from_snbt("[B; 1, 2]").is_err());
+        assert!(from_snbt("\"a\\nb\"").is_err());
+        assert!(from_snbt("{a: 1").is_err());
+        assert!(from_snbt("{a 1}").is_err());
+        assert!(from_snbt("1 2").is_err());
+        assert!(matches!(from_snbt("{a: [1, \"b\"]}"), Err(Error::SyntaxError { offset: 8, message: _ })));
+    }
+
+    #[test]
+    fn test_modern_numbers() {
+        let config = modern();
+        let read = |input| config.from_str(input).unwrap();
+        assert_eq!(read("0xff"), Value::Int(255));
+        assert_eq!(read("0xffub"), Value::Byte(-1));
+        assert_eq!(read("0x7fsb"), Value::Byte(127));
+        assert_eq!(read("-0b1010s"), Value::Short(-10));
+        assert_eq!(read("1_000_000"), Value::Int(1_000_000));
+        assert_eq!(read("65535us"), Value::Short(-1));
+        assert_eq!(read("0xffff_ffffui"), Value::Int(-1));
+        assert_eq!(read("-9223372036854775808L"), Value::Long(i64::MIN));
+        assert_eq!(read("0b"), Value::Byte(0));
+        assert_eq!(read("1_0.2_5e1_0f"), Value::Float(10.25e10));
+        assert_eq!(read("-.5"), Value::Double(-0.5));
+        assert_eq!(read("2e-1d"), Value::Double(0.2));
+        assert_eq!(read("True"), Value::Byte(1));
+
+        // out of range or malformed numbers are errors rather than strings
+        assert!(config.from_str("128b").is_err());
+        assert!(config.from_str("0xffsb").is_err());
+        assert!(config.from_str("-1ub").is_err());
+        assert!(config.from_str("_1").is_ok());
+        assert!(config.from_str("1_").is_err());
+        assert!(config.from_str("1.5ub").is_err());
+        assert!(config.from_str("1e999f").is_err());
+        assert!(config.from_str("012abc").is_err());
+    }
+
+    #[test]
+    fn test_modern_strings_and_operations() {
+        let config = modern();
+        assert_eq!(
+            config.from_str(r#""\x41\u00e9\U0001f600\n\s\t\'""#).unwrap(),
+            Value::String("Aé\u{1f600}\n \t'".to_string())
+        );
+        assert_eq!(
+            config.from_str(r#"'\N{SNOWMAN}\N{Latin Small Letter A}'"#).unwrap(),
+            Value::String("\u{2603}a".to_string())
+        );
+        assert!(config.from_str(r#""\N{NOT A CHARACTER}""#).is_err());
+        assert!(config.from_str(r#""\N{SNOWMAN""#).is_err());
+        assert!(config.from_str(r#""\ud800""#).is_err());
+
+        assert_eq!(config.from_str("bool(5)").unwrap(), Value::Byte(1));
+        assert_eq!(config.from_str("bool(0.0)").unwrap(), Value::Byte(0));
+        assert_eq!(
+            config.from_str("uuid(f81d4fae-7dec-11d0-a765-00a0c91e6bf6)").unwrap(),
+            Value::IntArray(vec![0xf81d4faeu32 as i32, 0x7dec11d0, 0xa76500a0u32 as i32, 0xc91e6bf6u32 as i32])
+        );
+        assert!(config.from_str("uuid(\"f81d4fae\")").is_err());
+        assert!(config.from_str("bool(\"a\")").is_err());
+        assert!(config.from_str("nope(1)").is_err());
+
+        assert_eq!(
+            config.from_str("[1, \"a\", {b: 2b}]").unwrap(),
+            Value::List(vec![Value::Int(1), Value::String("a".to_string()), compound(vec![("b", Value::Byte(2))])])
+        );
+    }
+
+    #[test]
+    fn test_max_depth() {
+        let config = SnbtConfig::new().max_depth(2);
+        assert!(config.from_str("{a: [1]}").is_ok());
+        assert!(matches!(config.from_str("{a: [[1]]}"), Err(Error::DepthLimitError)));
+        assert!(from_snbt(&"[".repeat(100_000)).is_err());
+
+        // operations count as nesting too
+        let config = modern().max_depth(2);
+        assert_eq!(config.from_str("bool(bool(1))").unwrap(), Value::Byte(1));
+        assert!(matches!(config.from_str("bool(bool(bool(1)))"), Err(Error::DepthLimitError)));
+        assert!(matches!(modern().from_str(&"bool(".repeat(1_000_000)), Err(Error::DepthLimitError)));
+    }
+
+    #[test]
+    fn test_print() {
+        let value = compound(vec![
+            ("a", Value::Byte(1)),
+            ("b c", Value::Short(-2)),
+            ("d", Value::Long(3)),
+            ("e", Value::Float(0.5)),
+            ("f", Value::Double(1e20)),
+            ("g", Value::ByteArray(vec![1, 2])),
+            ("h", Value::IntArray(vec![])),
+            ("i", Value::LongArray(vec![4])),
+            ("j", Value::String("say \"hi\"\n".to_string())),
+            ("k", Value::List(vec![Value::Int(1), Value::String("x".to_string())])),
+            ("l", compound(vec![])),
+        ]);
+
+        let config = SnbtConfig::new().sort_keys(true);
+        let legacy = config.to_string(&value).unwrap();
+        assert_eq!(
+            legacy,
+            "{a:1b,\"b c\":-2s,d:3L,e:0.5f,f:1e20d,g:[B;1B,2B],h:[I;],i:[L;4L],j:'say \"hi\"\n',\
+             k:[{\"\":1},{\"\":\"x\"}],l:{}}"
+        );
+
+        let config = config.dialect(SnbtDialect::Modern);
+        let modern = config.to_string(&value).unwrap();
+        assert_eq!(
+            modern,
+            "{a:1b,\"b c\":-2s,d:3L,e:0.5f,f:1e20d,g:[B;1B,2B],h:[I;],i:[L;4L],j:'say \"hi\"\\n',k:[1,\"x\"],l:{}}"
+        );
+        assert_eq!(config.from_str(&modern).unwrap(), value);
+
+        let pretty = config.pretty(true).to_string(&compound(vec![
+            ("a", Value::List(vec![Value::Int(1), compound(vec![])])),
+            ("b", Value::ByteArray(vec![1, 2])),
+        ]));
+        assert_eq!(pretty.unwrap(), "{\n    a: [\n        1,\n        {}\n    ],\n    b: [B; 1B, 2B]\n}");
+    }
+
+    #[test]
+    fn test_print_non_finite() {
+        for value in [Value::Float(f32::NAN), Value::Double(f64::INFINITY), Value::Double(f64::NEG_INFINITY)] {
+            assert!(to_snbt(&value).is_err());
+            assert!(modern().to_string(&Value::List(vec![value])).is_err());
+        }
+        assert_eq!(to_snbt(&Value::Float(f32::MAX)).unwrap(), "3.4028235e38f");
+    }
+}
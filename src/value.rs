@@ -0,0 +1,388 @@
+// A dynamically typed NBT value, for data without a fixed schema
+//
+// Values can be read and written with any serde data format. With this
+// crate's deserializer, arrays keep their tag type: for the newtype struct
+// name below it passes arrays to the visitor as a map with a single entry,
+// whose key is the name of the array wrapper type and whose value holds the
+// array's elements.
+
+use std::fmt;
+use serde::de::{Deserialize, Deserializer, MapAccess, SeqAccess, Visitor};
+use serde::ser::{Serialize, SerializeMap, SerializeSeq, Serializer};
+use crate::array;
+use crate::error::Result;
+use crate::nbt;
+
+pub(crate) const VALUE_NAME: &str = "__serde_nbt_value";
+
+/// the tags of a `TAG_COMPOUND` by name, in the order they were read or
+/// inserted with the `preserve_order` feature
+#[cfg(not(feature = "preserve_order"))]
+pub type Compound = std::collections::HashMap<String, Value>;
+
+/// the tags of a `TAG_COMPOUND` by name, in the order they were read or
+/// inserted with the `preserve_order` feature
+#[cfg(feature = "preserve_order")]
+pub type Compound = indexmap::IndexMap<String, Value>;
+
+/// any NBT tag's value
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Byte(i8),
+    Short(i16),
+    Int(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+    ByteArray(Vec<i8>),
+    String(String),
+    List(Vec<Value>),
+    Compound(Compound),
+    IntArray(Vec<i32>),
+    LongArray(Vec<i64>),
+}
+
+impl Value {
+    /// returns the NBT tag type this value is written as
+    pub fn tag_type(&self) -> u8 {
+        match self {
+            Value::Byte(_) => nbt::TAG_I8,
+            Value::Short(_) => nbt::TAG_I16,
+            Value::Int(_) => nbt::TAG_I32,
+            Value::Long(_) => nbt::TAG_I64,
+            Value::Float(_) => nbt::TAG_F32,
+            Value::Double(_) => nbt::TAG_F64,
+            Value::ByteArray(_) => nbt::TAG_I8_ARRAY,
+            Value::String(_) => nbt::TAG_STRING,
+            Value::List(_) => nbt::TAG_LIST,
+            Value::Compound(_) => nbt::TAG_COMPOUND,
+            Value::IntArray(_) => nbt::TAG_I32_ARRAY,
+            Value::LongArray(_) => nbt::TAG_I64_ARRAY,
+        }
+    }
+}
+
+/// a value along with the name of the tag holding it, used for root tags
+/// whose name has to be kept
+#[derive(Clone, Debug, PartialEq)]
+pub struct NamedTag {
+    pub name: String,
+    pub value: Value,
+}
+
+impl NamedTag {
+    pub fn new<S>(name: S, value: Value) -> Self
+    where
+        S: Into<String>,
+    {
+        NamedTag { name: name.into(), value }
+    }
+
+    /// reads the root tag along with its name
+    pub fn from_reader<R>(input: R) -> Result<Self>
+    where
+        R: std::io::Read,
+    {
+        let (name, value) = crate::de::from_reader_named(input)?;
+        Ok(NamedTag { name, value })
+    }
+
+    /// like `from_reader`, but reads from a byte slice
+    pub fn from_slice(input: &[u8]) -> Result<Self> {
+        let (name, value) = crate::de::from_slice_named(input)?;
+        Ok(NamedTag { name, value })
+    }
+
+    /// writes the value as the root tag with this tag's name
+    pub fn to_writer<W>(&self, output: W) -> Result<()>
+    where
+        W: std::io::Write,
+    {
+        crate::ser::to_writer_named(output, &self.name, &self.value)
+    }
+}
+
+impl Serialize for Value {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self {
+            Value::Byte(v) => serializer.serialize_i8(*v),
+            Value::Short(v) => serializer.serialize_i16(*v),
+            Value::Int(v) => serializer.serialize_i32(*v),
+            Value::Long(v) => serializer.serialize_i64(*v),
+            Value::Float(v) => serializer.serialize_f32(*v),
+            Value::Double(v) => serializer.serialize_f64(*v),
+            Value::ByteArray(v) => serializer.serialize_newtype_struct(array::I8_ARRAY_NAME, v),
+            Value::String(v) => serializer.serialize_str(v),
+            Value::List(v) => {
+                let mut seq = serializer.serialize_seq(Some(v.len()))?;
+                for element in v {
+                    seq.serialize_element(element)?;
+                }
+                seq.end()
+            },
+            Value::Compound(v) => {
+                let mut map = serializer.serialize_map(Some(v.len()))?;
+                for (name, value) in v {
+                    map.serialize_entry(name, value)?;
+                }
+                map.end()
+            },
+            Value::IntArray(v) => serializer.serialize_newtype_struct(array::I32_ARRAY_NAME, v),
+            Value::LongArray(v) => serializer.serialize_newtype_struct(array::I64_ARRAY_NAME, v),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Value {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_newtype_struct(VALUE_NAME, ValueVisitor)
+    }
+}
+
+struct ValueVisitor;
+
+impl<'de> Visitor<'de> for ValueVisitor {
+    type Value = Value;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("any NBT value")
+    }
+
+    fn visit_bool<E>(self, v: bool) -> std::result::Result<Value, E> {
+        Ok(Value::Byte(v as i8))
+    }
+
+    fn visit_i8<E>(self, v: i8) -> std::result::Result<Value, E> {
+        Ok(Value::Byte(v))
+    }
+
+    fn visit_i16<E>(self, v: i16) -> std::result::Result<Value, E> {
+        Ok(Value::Short(v))
+    }
+
+    fn visit_i32<E>(self, v: i32) -> std::result::Result<Value, E> {
+        Ok(Value::Int(v))
+    }
+
+    fn visit_i64<E>(self, v: i64) -> std::result::Result<Value, E> {
+        Ok(Value::Long(v))
+    }
+
+    // other data formats, unsigned values are kept as the same bits
+    fn visit_u64<E>(self, v: u64) -> std::result::Result<Value, E> {
+        Ok(Value::Long(v as i64))
+    }
+
+    fn visit_f32<E>(self, v: f32) -> std::result::Result<Value, E> {
+        Ok(Value::Float(v))
+    }
+
+    fn visit_f64<E>(self, v: f64) -> std::result::Result<Value, E> {
+        Ok(Value::Double(v))
+    }
+
+    fn visit_str<E>(self, v: &str) -> std::result::Result<Value, E> {
+        Ok(Value::String(v.to_string()))
+    }
+
+    fn visit_string<E>(self, v: String) -> std::result::Result<Value, E> {
+        Ok(Value::String(v))
+    }
+
+    fn visit_bytes<E>(self, v: &[u8]) -> std::result::Result<Value, E> {
+        Ok(Value::ByteArray(v.iter().map(|&v| v as i8).collect()))
+    }
+
+    fn visit_unit<E>(self) -> std::result::Result<Value, E> {
+        Ok(Value::Compound(Compound::new()))
+    }
+
+    fn visit_some<D>(self, deserializer: D) -> std::result::Result<Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Value::deserialize(deserializer)
+    }
+
+    fn visit_newtype_struct<D>(self, deserializer: D) -> std::result::Result<Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(self)
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> std::result::Result<Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut values = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(value) = seq.next_element()? {
+            values.push(value);
+        }
+        Ok(Value::List(values))
+    }
+
+    fn visit_map<A>(self, mut map: A) -> std::result::Result<Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let name = match map.next_key::<String>()? {
+            Some(name) => name,
+            None => return Ok(Value::Compound(Compound::new())),
+        };
+
+        // arrays as passed by this crate's deserializer
+        match name.as_str() {
+            array::I8_ARRAY_NAME => return Ok(Value::ByteArray(map.next_value()?)),
+            array::I32_ARRAY_NAME => return Ok(Value::IntArray(map.next_value()?)),
+            array::I64_ARRAY_NAME => return Ok(Value::LongArray(map.next_value()?)),
+            _ => {},
+        }
+
+        let mut values = Compound::new();
+        values.insert(name, map.next_value()?);
+        while let Some((name, value)) = map.next_entry()? {
+            values.insert(name, value);
+        }
+        Ok(Value::Compound(values))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{from_slice, to_writer, DeserializerConfig, Error, SerializerConfig};
+    use super::*;
+
+    #[test]
+    fn test_all_tag_types() {
+        // `"Level": {"a": 1b, "b": 2s, "c": 3, "d": 4L, "e": 5.0f, "f": 6.0d, "g": [B; 7b],
+        //  "h": "i", "j": [[I; 8]], "k": {}, "l": [L; 9L], "m": []}`
+        let buffer = b"\x0a\x00\x05Level\
+            \x01\x00\x01a\x01\
+            \x02\x00\x01b\x00\x02\
+            \x03\x00\x01c\x00\x00\x00\x03\
+            \x04\x00\x01d\x00\x00\x00\x00\x00\x00\x00\x04\
+            \x05\x00\x01e\x40\xa0\x00\x00\
+            \x06\x00\x01f\x40\x18\x00\x00\x00\x00\x00\x00\
+            \x07\x00\x01g\x00\x00\x00\x01\x07\
+            \x08\x00\x01h\x00\x01i\
+            \x09\x00\x01j\x0b\x00\x00\x00\x01\x00\x00\x00\x01\x00\x00\x00\x08\
+            \x0a\x00\x01k\x00\
+            \x0c\x00\x01l\x00\x00\x00\x01\x00\x00\x00\x00\x00\x00\x00\x09\
+            \x09\x00\x01m\x00\x00\x00\x00\x00\
+            \x00";
+
+        let tag = NamedTag::from_slice(&buffer[..]).unwrap();
+        assert_eq!(tag.name, "Level");
+
+        let mut expected = Compound::new();
+        expected.insert("a".to_string(), Value::Byte(1));
+        expected.insert("b".to_string(), Value::Short(2));
+        expected.insert("c".to_string(), Value::Int(3));
+        expected.insert("d".to_string(), Value::Long(4));
+        expected.insert("e".to_string(), Value::Float(5.0));
+        expected.insert("f".to_string(), Value::Double(6.0));
+        expected.insert("g".to_string(), Value::ByteArray(vec![7]));
+        expected.insert("h".to_string(), Value::String("i".to_string()));
+        expected.insert("j".to_string(), Value::List(vec![Value::IntArray(vec![8])]));
+        expected.insert("k".to_string(), Value::Compound(Compound::new()));
+        expected.insert("l".to_string(), Value::LongArray(vec![9]));
+        expected.insert("m".to_string(), Value::List(vec![]));
+        assert_eq!(tag.value, Value::Compound(expected));
+
+        // the tag types and the name survive being written again
+        let mut output = Vec::new();
+        tag.to_writer(&mut output).unwrap();
+        assert_eq!(output.len(), buffer.len());
+        assert_eq!(NamedTag::from_slice(&output).unwrap(), tag);
+    }
+
+    #[test]
+    fn test_root_name() {
+        let mut value = Compound::new();
+        value.insert("a".to_string(), Value::Byte(1));
+        let tag = NamedTag::new("Schematic", Value::Compound(value));
+
+        let mut buffer = Vec::new();
+        tag.to_writer(&mut buffer).unwrap();
+        assert_eq!(buffer, b"\x0a\x00\x09Schematic\x01\x00\x01a\x01\x00");
+
+        // reading and writing without a name uses an empty one
+        let value: Value = from_slice(&buffer).unwrap();
+        let mut buffer = Vec::new();
+        to_writer(&mut buffer, &value).unwrap();
+        assert_eq!(buffer, b"\x0a\x00\x00\x01\x00\x01a\x01\x00");
+    }
+
+    #[test]
+    fn test_heterogeneous_list() {
+        let mut compound = Compound::new();
+        compound.insert("a".to_string(), Value::Byte(2));
+        let mut value = Compound::new();
+        value.insert("l".to_string(), Value::List(vec![
+            Value::Int(1),
+            Value::String("b".to_string()),
+            Value::Compound(compound),
+        ]));
+        let value = Value::Compound(value);
+
+        assert!(matches!(to_writer(Vec::new(), &value), Err(Error::ListElementTypeError { .. })));
+
+        let mut buffer = Vec::new();
+        SerializerConfig::new().heterogeneous_lists(true).to_writer(&mut buffer, &value).unwrap();
+
+        // `{"l": [{"": 1}, {"": "b"}, {"": {"a": 2b}}]}`
+        let expected = b"\x0a\x00\x00\
+            \x09\x00\x01l\x0a\x00\x00\x00\x03\
+                \x03\x00\x00\x00\x00\x00\x01\x00\
+                \x08\x00\x00\x00\x01b\x00\
+                \x0a\x00\x00\x01\x00\x01a\x02\x00\x00\
+            \x00";
+        assert_eq!(buffer, &expected[..]);
+        let config = DeserializerConfig::new().heterogeneous_lists(true);
+        assert_eq!(config.from_slice::<Value>(&buffer).unwrap(), value);
+    }
+
+    #[test]
+    fn test_heterogeneous_list_of_wrapper_like_compounds() {
+        let mut compound = Compound::new();
+        compound.insert("".to_string(), Value::Int(5));
+        let mut value = Compound::new();
+        value.insert("l".to_string(), Value::List(vec![
+            Value::Compound(compound.clone()),
+            Value::Compound(compound),
+        ]));
+        let value = Value::Compound(value);
+
+        let mut buffer = Vec::new();
+        SerializerConfig::new().heterogeneous_lists(true).to_writer(&mut buffer, &value).unwrap();
+
+        // `{"l": [{"": {"": 5}}, {"": {"": 5}}]}`
+        let expected = b"\x0a\x00\x00\
+            \x09\x00\x01l\x0a\x00\x00\x00\x02\
+                \x0a\x00\x00\x03\x00\x00\x00\x00\x00\x05\x00\x00\
+                \x0a\x00\x00\x03\x00\x00\x00\x00\x00\x05\x00\x00\
+            \x00";
+        assert_eq!(buffer, &expected[..]);
+        let config = DeserializerConfig::new().heterogeneous_lists(true);
+        assert_eq!(config.from_slice::<Value>(&buffer).unwrap(), value);
+    }
+
+    #[cfg(feature = "preserve_order")]
+    #[test]
+    fn test_preserve_order() {
+        // `{"c": 1b, "a": 2b, "b": 3b}`
+        let buffer = b"\x0a\x00\x00\x01\x00\x01c\x01\x01\x00\x01a\x02\x01\x00\x01b\x03\x00";
+
+        let value: Value = from_slice(&buffer[..]).unwrap();
+        let mut output = Vec::new();
+        to_writer(&mut output, &value).unwrap();
+        assert_eq!(output, &buffer[..]);
+    }
+}
@@ -0,0 +1,53 @@
+// `#[serde(with = "...")]` modules which write the unit variants of an enum
+// as the variant's index instead of as the variant's name
+//
+// The serializer recognizes the newtype struct names below, other serde data
+// formats see an ordinary newtype around the enum. The deserializer accepts
+// both names and indices for any enum, so reading needs no special handling.
+
+pub(crate) const I8_VARIANT_NAME: &str = "__serde_nbt_i8_variant";
+pub(crate) const I32_VARIANT_NAME: &str = "__serde_nbt_i32_variant";
+
+/// use with `#[serde(with = "serde_nbt::byte_enum")]` to write a field's unit
+/// variants as a `TAG_I8` holding the variant's index
+pub mod byte_enum {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: ?Sized + Serialize,
+        S: Serializer,
+    {
+        serializer.serialize_newtype_struct(super::I8_VARIANT_NAME, value)
+    }
+
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+    where
+        T: Deserialize<'de>,
+        D: Deserializer<'de>,
+    {
+        T::deserialize(deserializer)
+    }
+}
+
+/// use with `#[serde(with = "serde_nbt::int_enum")]` to write a field's unit
+/// variants as a `TAG_I32` holding the variant's index
+pub mod int_enum {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: ?Sized + Serialize,
+        S: Serializer,
+    {
+        serializer.serialize_newtype_struct(super::I32_VARIANT_NAME, value)
+    }
+
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+    where
+        T: Deserialize<'de>,
+        D: Deserializer<'de>,
+    {
+        T::deserialize(deserializer)
+    }
+}